@@ -0,0 +1,381 @@
+use crate::error::{Result, TranscriptError};
+use crate::{http_client_builder, YouTubeTranscript, DEFAULT_ACCEPT_LANGUAGE, DEFAULT_USER_AGENT};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures and builds a [`YouTubeTranscript`]
+///
+/// ```no_run
+/// # fn main() -> ytt::Result<()> {
+/// let api = ytt::YouTubeTranscript::builder()
+///     .delay(1000)
+///     .retries(3)
+///     .proxy("socks5://127.0.0.1:9050")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct YouTubeTranscriptBuilder {
+    delay_ms: u64,
+    jitter_percent: u8,
+    user_agent: String,
+    accept_language: String,
+    proxy: Option<String>,
+    retries: u32,
+    retry_budget: Option<u32>,
+    cookies: Option<PathBuf>,
+    client: Option<reqwest::Client>,
+    po_token: Option<String>,
+    visitor_data: Option<String>,
+    timeout: Option<Duration>,
+    keep_empty_cues: bool,
+    any_language_fallback: bool,
+    http1_only: bool,
+    cache_dir: Option<PathBuf>,
+}
+
+impl Default for YouTubeTranscriptBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl YouTubeTranscriptBuilder {
+    pub fn new() -> Self {
+        Self {
+            delay_ms: 500,
+            jitter_percent: 0,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            accept_language: DEFAULT_ACCEPT_LANGUAGE.to_string(),
+            proxy: None,
+            retries: 0,
+            retry_budget: None,
+            cookies: None,
+            client: None,
+            po_token: None,
+            visitor_data: None,
+            timeout: None,
+            keep_empty_cues: false,
+            any_language_fallback: false,
+            http1_only: false,
+            cache_dir: None,
+        }
+    }
+
+    /// Delay between requests in milliseconds (default: 500)
+    pub fn delay(mut self, delay_ms: u64) -> Self {
+        self.delay_ms = delay_ms;
+        self
+    }
+
+    /// Vary each delay randomly by up to this percentage of it in either direction, so
+    /// requests don't arrive at a fixed rhythm (default: 0, capped at 100)
+    pub fn jitter(mut self, percent: u8) -> Self {
+        self.jitter_percent = percent.min(100);
+        self
+    }
+
+    /// `User-Agent` header sent with every request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// `Accept-Language` header sent with every request (default: `en-US`)
+    pub fn accept_language(mut self, accept_language: impl Into<String>) -> Self {
+        self.accept_language = accept_language.into();
+        self
+    }
+
+    /// Route all requests through a proxy (`http://`, `https://` or `socks5://` URL)
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Number of times to retry a request after a connection error, 429 or 5xx (default: 0)
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Cap the retries made across all YouTube requests of the client's lifetime (default: no
+    /// cap). Once `budget` retries have been spent, a failing request fails straight
+    /// away instead of being retried, bounding how long a large batch can spend waiting.
+    pub fn retry_budget(mut self, budget: u32) -> Self {
+        self.retry_budget = Some(budget);
+        self
+    }
+
+    /// Give up on a request that hasn't completed within `timeout` (default: no limit).
+    /// Timed-out requests count as retryable failures.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Netscape-format cookies.txt to load into the cookie store
+    pub fn cookies(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cookies = Some(path.into());
+        self
+    }
+
+    /// Proof-of-origin token appended as `pot=` to timedtext requests, needed for
+    /// protected videos that otherwise fail with `PoTokenRequired`
+    pub fn po_token(mut self, po_token: impl Into<String>) -> Self {
+        self.po_token = Some(po_token.into());
+        self
+    }
+
+    /// Visitor data the PO token was minted for; sent as `visitorData` in the
+    /// InnerTube player request context and as the `X-Goog-Visitor-Id` header
+    pub fn visitor_data(mut self, visitor_data: impl Into<String>) -> Self {
+        self.visitor_data = Some(visitor_data.into());
+        self
+    }
+
+    /// Keep cues without text instead of dropping them (default: false).
+    /// See [`TranscriptParser::keep_empty_cues`](crate::TranscriptParser::keep_empty_cues).
+    pub fn keep_empty_cues(mut self, keep: bool) -> Self {
+        self.keep_empty_cues = keep;
+        self
+    }
+
+    /// When none of the requested languages (or the default) has a transcript, use any
+    /// available track instead of failing with `NoTranscriptFound`, preferring manually
+    /// created ones (default: false)
+    pub fn any_language_fallback(mut self, enabled: bool) -> Self {
+        self.any_language_fallback = enabled;
+        self
+    }
+
+    /// Only speak HTTP/1.1, even where YouTube offers HTTP/2 (default: false). A fallback
+    /// for networks or bot detection that treat HTTP/2 clients differently.
+    pub fn http1_only(mut self, enabled: bool) -> Self {
+        self.http1_only = enabled;
+        self
+    }
+
+    /// Keep fetched transcripts in `dir` along with their `ETag` / `Last-Modified`
+    /// headers, and refetch them with `If-None-Match` / `If-Modified-Since` so an
+    /// unchanged track comes back as a bodyless 304 and is read from disk (default: off)
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Use a preconfigured client. The user agent, accept language, proxy, timeout and
+    /// HTTP version settings are ignored, and [`build`](Self::build) fails if
+    /// [`cookies`](Self::cookies) is set too, since the client doesn't use the builder's cookie store.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn build(self) -> Result<YouTubeTranscript> {
+        if self.client.is_some() && self.cookies.is_some() {
+            return Err(TranscriptError::InvalidArgument(
+                "cookies can't be loaded into a preconfigured client; add them to that client instead".to_string(),
+            ));
+        }
+        let cookie_jar = Arc::new(reqwest::cookie::Jar::default());
+
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut headers = reqwest::header::HeaderMap::new();
+                headers.insert(
+                    reqwest::header::ACCEPT_LANGUAGE,
+                    reqwest::header::HeaderValue::from_str(&self.accept_language).map_err(
+                        |_| {
+                            TranscriptError::InvalidArgument(format!(
+                                "Invalid Accept-Language: {}",
+                                self.accept_language
+                            ))
+                        },
+                    )?,
+                );
+
+                let mut builder = http_client_builder()
+                    .cookie_provider(Arc::clone(&cookie_jar))
+                    .default_headers(headers)
+                    .user_agent(&self.user_agent);
+
+                if self.http1_only {
+                    builder = builder.http1_only();
+                }
+
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+
+                if let Some(proxy) = &self.proxy {
+                    let proxy = reqwest::Proxy::all(proxy).map_err(|e| {
+                        TranscriptError::InvalidArgument(format!("Invalid proxy {}: {}", proxy, e))
+                    })?;
+                    builder = builder.proxy(proxy);
+                }
+
+                builder.build().map_err(|e| {
+                    TranscriptError::InvalidArgument(format!("Failed to create HTTP client: {}", e))
+                })?
+            }
+        };
+
+        let api = YouTubeTranscript {
+            client,
+            cookie_jar,
+            delay_ms: self.delay_ms,
+            jitter_percent: self.jitter_percent,
+            retries: self.retries,
+            retry_budget: self.retry_budget.map(std::sync::atomic::AtomicU32::new),
+            po_token: self.po_token,
+            visitor_data: self.visitor_data,
+            keep_empty_cues: self.keep_empty_cues,
+            any_language_fallback: self.any_language_fallback,
+            cache: self.cache_dir.map(crate::cache::BodyCache::new),
+            watch_pages: Default::default(),
+        };
+
+        if let Some(path) = &self.cookies {
+            api.load_cookies(path)?;
+        }
+
+        Ok(api)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let api = YouTubeTranscriptBuilder::new().build().unwrap();
+        assert_eq!(api.delay_ms, 500);
+        assert_eq!(api.jitter_percent, 0);
+        assert_eq!(api.retries, 0);
+        assert!(!api.keep_empty_cues);
+    }
+
+    #[test]
+    fn test_builder_options() {
+        let api = YouTubeTranscriptBuilder::new()
+            .delay(1000)
+            .jitter(20)
+            .retries(3)
+            .user_agent("my-agent/1.0")
+            .proxy("socks5://127.0.0.1:9050")
+            .build()
+            .unwrap();
+        assert_eq!(api.delay_ms, 1000);
+        assert_eq!(api.jitter_percent, 20);
+        assert_eq!(api.retries, 3);
+    }
+
+    #[test]
+    fn test_builder_po_token() {
+        let api = YouTubeTranscriptBuilder::new()
+            .po_token("token123")
+            .visitor_data("visitor456")
+            .build()
+            .unwrap();
+        assert_eq!(api.po_token.as_deref(), Some("token123"));
+        assert_eq!(api.visitor_data.as_deref(), Some("visitor456"));
+    }
+
+    #[test]
+    fn test_builder_retry_budget() {
+        let api = YouTubeTranscriptBuilder::new()
+            .retries(5)
+            .retry_budget(2)
+            .build()
+            .unwrap();
+        assert!(api.take_retry());
+        assert!(api.take_retry());
+        assert!(!api.take_retry());
+
+        let unlimited = YouTubeTranscriptBuilder::new().build().unwrap();
+        assert!((0..100).all(|_| unlimited.take_retry()));
+    }
+
+    #[test]
+    fn test_builder_timeout() {
+        assert!(YouTubeTranscriptBuilder::new()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .is_ok());
+    }
+
+    /// The start of the TLS handshake `api` sends to a local server
+    async fn client_hello(api: &YouTubeTranscript) -> Vec<u8> {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let mut hello = vec![0u8; 4096];
+            let n = conn.read(&mut hello).await.unwrap();
+            hello.truncate(n);
+            hello
+        });
+        // No TLS server answers, so the request itself fails
+        assert!(api.client.get(url).send().await.is_err());
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_builder_http1_only() {
+        // ALPN protocol names are length-prefixed in the ClientHello
+        let offers =
+            |hello: &[u8], protocol: &[u8]| hello.windows(protocol.len()).any(|w| w == protocol);
+
+        let hello = client_hello(&YouTubeTranscriptBuilder::new().build().unwrap()).await;
+        assert!(offers(&hello, b"\x02h2"));
+        assert!(offers(&hello, b"\x08http/1.1"));
+
+        let hello = client_hello(
+            &YouTubeTranscriptBuilder::new()
+                .http1_only(true)
+                .build()
+                .unwrap(),
+        )
+        .await;
+        assert!(!offers(&hello, b"\x02h2"));
+        assert!(offers(&hello, b"\x08http/1.1"));
+    }
+
+    #[test]
+    fn test_builder_invalid_proxy() {
+        assert!(YouTubeTranscriptBuilder::new()
+            .proxy("not a proxy url")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_missing_cookie_file() {
+        assert!(YouTubeTranscriptBuilder::new()
+            .cookies("/nonexistent/cookies.txt")
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_custom_client() {
+        let api = YouTubeTranscriptBuilder::new()
+            .client(reqwest::Client::new())
+            .delay(0)
+            .build()
+            .unwrap();
+        assert_eq!(api.delay_ms, 0);
+
+        let result = YouTubeTranscriptBuilder::new()
+            .client(reqwest::Client::new())
+            .cookies("cookies.txt")
+            .build();
+        assert!(matches!(result, Err(TranscriptError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::error::{Result, TranscriptError};
+use crate::TranscriptInfo;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A timedtext body saved with the validators the server sent for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedBody {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+/// Directory of [`CachedBody`] entries, one JSON file per track
+pub(crate) struct BodyCache {
+    dir: PathBuf,
+}
+
+impl BodyCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// File name for a track. Timedtext URLs carry signatures that change on every
+    /// watch page load, so entries are keyed by what the URL points at instead,
+    /// including its `fmt`, since srv3 and the default XML are different bodies.
+    pub fn key(video_id: &str, info: &TranscriptInfo, translate_to: Option<&str>) -> String {
+        let mut key = format!("{}.{}", video_id, info.language_code);
+        if info.is_generated {
+            key.push_str(".asr");
+        }
+        let format = url::Url::parse(&info.base_url).ok().and_then(|url| {
+            url.query_pairs()
+                .find(|(name, _)| name == "fmt")
+                .map(|(_, value)| value.into_owned())
+        });
+        if let Some(format) = format {
+            key = format!("{}.fmt-{}", key, format);
+        }
+        if let Some(target) = translate_to {
+            key = format!("{}.to-{}", key, target);
+        }
+        let key: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}.json", key)
+    }
+
+    /// The saved entry, if any. An unreadable or corrupt entry counts as missing,
+    /// so the track is simply fetched again.
+    pub fn load(&self, key: &str) -> Option<CachedBody> {
+        let content = std::fs::read_to_string(self.dir.join(key)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Save an entry, replacing the old one in a single rename so a reader never sees
+    /// half a file
+    pub fn store(&self, key: &str, entry: &CachedBody) -> Result<()> {
+        let io_error = |e: std::io::Error| {
+            TranscriptError::IoError(format!(
+                "Failed to write cache {}: {}",
+                self.dir.display(),
+                e
+            ))
+        };
+        std::fs::create_dir_all(&self.dir).map_err(io_error)?;
+        let path = self.dir.join(key);
+        let temp = self.dir.join(format!("{}.tmp", key));
+        std::fs::write(&temp, serde_json::to_vec(entry)?).map_err(io_error)?;
+        std::fs::rename(&temp, &path).map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_key() {
+        let mut info = TranscriptInfo {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            language_code: "zh-Hans".to_string(),
+            language: "Chinese".to_string(),
+            is_generated: false,
+            is_translatable: true,
+            base_url: String::new(),
+            translation_languages: Vec::new(),
+        };
+        assert_eq!(
+            BodyCache::key("dQw4w9WgXcQ", &info, None),
+            "dQw4w9WgXcQ.zh-Hans.json"
+        );
+        info.is_generated = true;
+        info.language_code = "en/../x".to_string();
+        assert_eq!(
+            BodyCache::key("dQw4w9WgXcQ", &info, Some("es")),
+            "dQw4w9WgXcQ.en_.._x.asr.to-es.json"
+        );
+        info.base_url =
+            "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en&fmt=srv3".to_string();
+        assert_eq!(
+            BodyCache::key("dQw4w9WgXcQ", &info, None),
+            "dQw4w9WgXcQ.en_.._x.asr.fmt-srv3.json"
+        );
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cache = BodyCache::new(temp_dir.path().join("cache"));
+        assert!(cache.load("a.json").is_none());
+
+        let entry = CachedBody {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            body: "<transcript/>".to_string(),
+        };
+        cache.store("a.json", &entry).unwrap();
+        assert_eq!(cache.load("a.json"), Some(entry));
+
+        std::fs::write(temp_dir.path().join("cache/b.json"), "not json").unwrap();
+        assert!(cache.load("b.json").is_none());
+    }
+}
@@ -1,13 +1,35 @@
 use crate::error::{Result, TranscriptError};
+use crate::{http_client_builder, TranscriptItem};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
 
+/// Cues sent per request by [`ChatGPT::cleanup_cues`]. Larger windows give the model
+/// more context and repeat the instructions less often, but a reply that drops or
+/// merges lines costs more cues their cleanup.
+const CLEANUP_WINDOW_CUES: usize = 40;
+
 #[derive(Debug, Serialize)]
 struct ChatRequest {
     model: String,
     messages: Vec<Message>,
     temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    /// Ask for a last chunk with the token counts, which streamed replies otherwise lack
+    include_usage: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -19,8 +41,153 @@ struct Message {
 #[derive(Debug, Deserialize)]
 struct ChatResponse {
     choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+/// Token counts the OpenAI API reports for each request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl Usage {
+    /// Tokens used since `earlier`, a previous reading of the same running total
+    pub fn since(&self, earlier: Usage) -> Usage {
+        Usage {
+            prompt_tokens: self.prompt_tokens.saturating_sub(earlier.prompt_tokens),
+            completion_tokens: self
+                .completion_tokens
+                .saturating_sub(earlier.completion_tokens),
+            total_tokens: self.total_tokens.saturating_sub(earlier.total_tokens),
+        }
+    }
+
+    fn add(&mut self, other: Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} prompt + {} completion = {} tokens",
+            self.prompt_tokens, self.completion_tokens, self.total_tokens
+        )
+    }
+}
+
+/// `{ "error": { ... } }` body OpenAI sends for failed requests
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
 }
 
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+/// One `data:` event of a streamed reply
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Delta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// A streamed chat completion put back together from its server-sent events
+#[derive(Default)]
+struct StreamedReply {
+    /// Bytes of a line that hasn't been completed yet
+    pending: Vec<u8>,
+    text: String,
+    usage: Option<Usage>,
+}
+
+impl StreamedReply {
+    /// Take the next piece of the response body, returning the content it adds
+    fn push(&mut self, bytes: &[u8]) -> Result<String> {
+        self.pending.extend_from_slice(bytes);
+        let mut added = String::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            added.push_str(&self.push_line(&String::from_utf8_lossy(&line))?);
+        }
+        Ok(added)
+    }
+
+    fn push_line(&mut self, line: &str) -> Result<String> {
+        // Blank lines separate events and `:` lines are comments
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            return Ok(String::new());
+        };
+        let data = data.trim();
+        if data == "[DONE]" {
+            return Ok(String::new());
+        }
+        if let Ok(ErrorResponse { error }) = serde_json::from_str::<ErrorResponse>(data) {
+            return Err(TranscriptError::HttpError(format!(
+                "OpenAI API error: {}",
+                error.message
+            )));
+        }
+
+        let chunk: StreamChunk = serde_json::from_str(data).map_err(|e| {
+            TranscriptError::JsonParseError(format!(
+                "Failed to parse OpenAI stream event: {} (data: {})",
+                e,
+                body_snippet(data)
+            ))
+        })?;
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+        let added: String = chunk
+            .choices
+            .into_iter()
+            .filter_map(|choice| choice.delta.content)
+            .collect();
+        self.text.push_str(&added);
+        Ok(added)
+    }
+
+    /// The whole reply, once the body has ended
+    fn finish(mut self) -> Result<(String, Option<Usage>)> {
+        let rest = std::mem::take(&mut self.pending);
+        self.push_line(&String::from_utf8_lossy(&rest))?;
+        if self.text.trim().is_empty() {
+            return Err(TranscriptError::HttpError(
+                "No response from OpenAI API".to_string(),
+            ));
+        }
+        Ok((self.text.trim().to_string(), self.usage))
+    }
+}
+
+/// How much of an unparsable response body to include in the error
+const BODY_SNIPPET_CHARS: usize = 300;
+
 #[derive(Debug, Deserialize)]
 struct Choice {
     message: MessageResponse,
@@ -31,12 +198,122 @@ struct MessageResponse {
     content: String,
 }
 
-pub struct ChatGPT {
+/// Token bucket that spaces out requests to at most a given rate
+///
+/// Tokens are earned continuously up to `burst`, and each request spends one. A caller
+/// that finds the bucket empty reserves the next token and sleeps until it is earned,
+/// so concurrent callers queue up in order instead of all retrying at once.
+pub struct RateLimiter {
+    /// Time it takes to earn one token
+    interval: Duration,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Allow `requests` per minute, with up to `burst` of them back to back
+    pub fn per_minute(requests: u32, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            interval: Duration::from_secs(60) / requests.max(1),
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let earned =
+                now.duration_since(bucket.updated).as_secs_f64() / self.interval.as_secs_f64();
+            bucket.tokens = (bucket.tokens + earned).min(self.burst) - 1.0;
+            bucket.updated = now;
+            if bucket.tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                self.interval.mul_f64(-bucket.tokens)
+            }
+        };
+        if !wait.is_zero() {
+            log::debug!(
+                "Waiting {:.1}s for the OpenAI request rate limit",
+                wait.as_secs_f64()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// A chat completion backend that cleanup and translation prompts are sent to
+///
+/// [`OpenAi`] is the only built-in provider. Implement this to send the same prompts
+/// to another API or a local model, and hand it to [`ChatGPT::with_provider`]. The
+/// trait is object safe, so a provider picked at runtime can be used as
+/// `ChatGPT<Box<dyn LlmProvider>>` (see [`ChatGPT::boxed`]).
+///
+/// ```
+/// use ytt::chatgpt::{ChatGPT, Completion, LlmProvider};
+///
+/// /// Replies with the transcript it was sent, unchanged
+/// struct Echo;
+///
+/// impl LlmProvider for Echo {
+///     fn complete<'a>(&'a self, _system: &'a str, user: &'a str) -> Completion<'a> {
+///         Box::pin(async move { Ok(user.to_string()) })
+///     }
+/// }
+///
+/// let chatgpt = ChatGPT::with_provider(Echo);
+/// ```
+pub trait LlmProvider: Send + Sync {
+    /// The model's reply to `user`, following the `system` instructions
+    fn complete<'a>(&'a self, system: &'a str, user: &'a str) -> Completion<'a>;
+
+    /// Tokens used by every request so far; zero for providers that don't report them
+    fn usage(&self) -> Usage {
+        Usage::default()
+    }
+}
+
+/// A reply being produced by [`LlmProvider::complete`]
+pub type Completion<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+impl<T: LlmProvider + ?Sized> LlmProvider for Box<T> {
+    fn complete<'a>(&'a self, system: &'a str, user: &'a str) -> Completion<'a> {
+        (**self).complete(system, user)
+    }
+
+    fn usage(&self) -> Usage {
+        (**self).usage()
+    }
+}
+
+/// The OpenAI chat completions API
+pub struct OpenAi {
     client: reqwest::Client,
     api_key: String,
+    usage: Mutex<Usage>,
+    rate_limiter: Option<RateLimiter>,
+    /// Per-request time limit, set on each request so it holds whichever client is used
+    timeout: Option<Duration>,
+    /// Called with each piece of a streamed reply; replies aren't streamed when unset
+    on_delta: Option<Box<DeltaCallback>>,
 }
 
-impl ChatGPT {
+/// Receives the pieces of a streamed reply, see [`ChatGPT::with_streaming`]
+type DeltaCallback = dyn Fn(&str) + Send + Sync;
+
+impl OpenAi {
     pub fn new(api_key: Option<String>) -> Result<Self> {
         let api_key = api_key
             .or_else(|| std::env::var("OPENAI_API_KEY").ok())
@@ -45,16 +322,256 @@ impl ChatGPT {
             ))?;
 
         Ok(Self {
-            client: reqwest::Client::new(),
+            client: http_client_builder().build()?,
             api_key,
+            usage: Mutex::new(Usage::default()),
+            rate_limiter: None,
+            timeout: None,
+            on_delta: None,
         })
     }
 
+    /// Read a streamed reply as it arrives, handing each piece of content to `on_delta`
+    async fn read_stream(
+        &self,
+        mut response: reqwest::Response,
+    ) -> Result<(String, Option<Usage>)> {
+        let mut reply = StreamedReply::default();
+        while let Some(bytes) = response.chunk().await? {
+            let added = reply.push(&bytes)?;
+            if let (false, Some(on_delta)) = (added.is_empty(), &self.on_delta) {
+                on_delta(&added);
+            }
+        }
+        if let Some(on_delta) = &self.on_delta {
+            on_delta("\n");
+        }
+        reply.finish()
+    }
+}
+
+impl LlmProvider for OpenAi {
+    fn complete<'a>(&'a self, system: &'a str, user: &'a str) -> Completion<'a> {
+        Box::pin(self.send_chat(system, user))
+    }
+
+    fn usage(&self) -> Usage {
+        *self.usage.lock().unwrap()
+    }
+}
+
+impl OpenAi {
+    async fn send_chat(&self, system: &str, user: &str) -> Result<String> {
+        let request = ChatRequest {
+            model: "gpt-4o-mini".to_string(),
+            messages: vec![
+                Message {
+                    role: "system".to_string(),
+                    content: system.to_string(),
+                },
+                Message {
+                    role: "user".to_string(),
+                    content: user.to_string(),
+                },
+            ],
+            temperature: 0.3,
+            stream: self.on_delta.is_some(),
+            stream_options: self.on_delta.as_ref().map(|_| StreamOptions {
+                include_usage: true,
+            }),
+        };
+
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let mut request = self
+            .client
+            .post(OPENAI_API_URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&request);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        let (text, usage) = if self.on_delta.is_some() && status.is_success() {
+            self.read_stream(response).await?
+        } else {
+            let body = response.text().await?;
+            log::debug!("OpenAI response ({}): {}", status, body);
+            parse_chat_response(status, &body)?
+        };
+        if let Some(usage) = usage {
+            self.usage.lock().unwrap().add(usage);
+        }
+        Ok(text)
+    }
+}
+
+/// Transcript cleanup and translation on top of an [`LlmProvider`], OpenAI by default
+pub struct ChatGPT<P = OpenAi> {
+    provider: P,
+    cleanup_prompt: Option<String>,
+    /// Whether the built-in cleanup prompts ask for filler words and repetitions to go
+    remove_filler: bool,
+    /// Whether the built-in cleanup prompt asks for promotional content to go
+    remove_promotional: bool,
+}
+
+impl ChatGPT<OpenAi> {
+    pub fn new(api_key: Option<String>) -> Result<Self> {
+        Ok(Self::with_provider(OpenAi::new(api_key)?))
+    }
+
+    /// Give up on OpenAI requests that take longer than `timeout`, including ones sent
+    /// through a client passed to [`with_client`](Self::with_client)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.provider.timeout = Some(timeout);
+        self
+    }
+
+    /// Send OpenAI requests through a preconfigured client, e.g. one shared with other
+    /// parts of an application so they pool connections together
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.provider.client = client;
+        self
+    }
+
+    /// Send at most `requests_per_minute` requests, evenly spaced. The limit covers
+    /// every call made through this client, including concurrent ones.
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.provider.rate_limiter = Some(RateLimiter::per_minute(requests_per_minute, 1));
+        self
+    }
+
+    /// Stream replies instead of waiting for them whole, passing each piece of text to
+    /// `on_delta` as it arrives, e.g. to show progress on a long cleanup. Each reply is
+    /// followed by a `"\n"`, so consecutive replies start on their own line. The finished
+    /// reply is still returned as usual.
+    pub fn with_streaming(mut self, on_delta: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.provider.on_delta = Some(Box::new(on_delta));
+        self
+    }
+}
+
+impl<P: LlmProvider> ChatGPT<P> {
+    /// Send the prompts to `provider` instead of OpenAI
+    pub fn with_provider(provider: P) -> Self {
+        Self {
+            provider,
+            cleanup_prompt: None,
+            remove_filler: true,
+            remove_promotional: true,
+        }
+    }
+
+    /// The same client with its provider boxed, so clients for different providers
+    /// share one type and the provider can be chosen at runtime
+    pub fn boxed(self) -> ChatGPT<Box<dyn LlmProvider>>
+    where
+        P: 'static,
+    {
+        ChatGPT {
+            provider: Box::new(self.provider),
+            cleanup_prompt: self.cleanup_prompt,
+            remove_filler: self.remove_filler,
+            remove_promotional: self.remove_promotional,
+        }
+    }
+
+    /// Tokens used by every request this client has made so far
+    pub fn usage(&self) -> Usage {
+        self.provider.usage()
+    }
+
+    /// Replace the built-in cleanup instructions with `prompt`. The transcript (and
+    /// the markdown formatting instructions, if requested) are still appended to it.
+    pub fn with_cleanup_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.cleanup_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Whether the built-in cleanup prompts tell the model to remove filler words and
+    /// repetitions (on by default). Turn it off when those deletions take substance with them.
+    pub fn with_filler_removal(mut self, remove: bool) -> Self {
+        self.remove_filler = remove;
+        self
+    }
+
+    /// Whether the built-in cleanup prompt tells the model to remove products, links,
+    /// courses and other promotional content (on by default). Turn it off for videos
+    /// where such mentions are the subject rather than an advert.
+    pub fn with_promotional_removal(mut self, remove: bool) -> Self {
+        self.remove_promotional = remove;
+        self
+    }
+
     pub async fn cleanup_transcript(
         &self,
         transcript_text: &str,
         format_markdown: bool,
     ) -> Result<String> {
+        let (system_prompt, prompt) = self.cleanup_prompts(transcript_text, format_markdown);
+        self.complete(system_prompt, prompt).await
+    }
+
+    /// Clean up cue by cue, keeping each cue's `start` and `duration` so cleaned
+    /// subtitles stay in sync
+    ///
+    /// Cues are sent in windows of numbered lines and the model is asked to return the
+    /// same numbered lines. A cue the model empties (only filler) is dropped; a cue
+    /// missing from the reply keeps its original text. Every window repeats the
+    /// instructions, so this uses more prompt tokens than `cleanup_transcript`.
+    pub async fn cleanup_cues(&self, items: &[TranscriptItem]) -> Result<Vec<TranscriptItem>> {
+        let mut cleaned = Vec::with_capacity(items.len());
+        for window in items.chunks(CLEANUP_WINDOW_CUES) {
+            let (system_prompt, prompt) = self.cleanup_cues_prompts(window);
+            let reply = self.complete(system_prompt, prompt).await?;
+            cleaned.extend(reattach_cleaned_lines(window, &reply));
+        }
+        Ok(cleaned)
+    }
+
+    /// System and user prompts for one window of `cleanup_cues`
+    fn cleanup_cues_prompts(&self, window: &[TranscriptItem]) -> (&'static str, String) {
+        let instructions = match &self.cleanup_prompt {
+            Some(custom_prompt) => custom_prompt.trim(),
+            None if self.remove_filler => "Please clean up the following transcript lines. Fix grammar and punctuation \
+                and remove filler words and repetitions, preserving the original meaning. \
+                Do not add any information that wasn't in the original transcript.",
+            None => "Please clean up the following transcript lines. Fix grammar and punctuation, \
+                keeping filler words and repetitions as spoken and preserving the original meaning. \
+                Do not add any information that wasn't in the original transcript.",
+        };
+        let lines: String = window
+            .iter()
+            .enumerate()
+            .map(|(index, item)| format!("{}: {}\n", index + 1, item.text.replace('\n', " ")))
+            .collect();
+        let prompt = format!(
+            "{}\n\n\
+            Each numbered line is one subtitle. Answer with exactly the same numbered lines, \
+            in the same order and in the form `N: text`, cleaning each line on its own. \
+            Do not merge, split or reorder lines. If nothing of a line is left, answer with its \
+            number followed by nothing. Respond with the lines only.\n\n\
+            Transcript lines:\n\n{}",
+            instructions, lines
+        );
+        (
+            "You are a helpful assistant that cleans up subtitle lines one by one, keeping their numbering.",
+            prompt,
+        )
+    }
+
+    /// System and user prompts for `cleanup_transcript`
+    fn cleanup_prompts(
+        &self,
+        transcript_text: &str,
+        format_markdown: bool,
+    ) -> (&'static str, String) {
         let format_instruction = if format_markdown {
             "Format the cleaned transcript using Markdown syntax. Use appropriate markdown elements like:\n\
             - **Bold** for emphasis on important points\n\
@@ -68,65 +585,179 @@ impl ChatGPT {
             ""
         };
 
+        if let Some(custom_prompt) = &self.cleanup_prompt {
+            let prompt = format!(
+                "{}\n\n{}Transcript:\n\n{}",
+                custom_prompt.trim(),
+                format_instruction,
+                transcript_text
+            );
+            return (
+                "You are a helpful assistant that processes video transcripts as instructed.",
+                prompt,
+            );
+        }
+
+        let filler_instruction = if self.remove_filler {
+            "remove filler words and repetitions, "
+        } else {
+            ""
+        };
+        // The promotional content is the one thing the model is asked to take out
+        let (keep_instruction, promotional_instruction) = if self.remove_promotional {
+            (
+                "and do not remove any other information beyond the promotional content described below.",
+                "IMPORTANT: Remove all references to products, websites, courses, training programs, \
+                email addresses, social media handles, or any promotional content that the presenter may offer. \
+                Focus only on the educational or informational content.\n\n",
+            )
+        } else {
+            ("and do not remove any that was.", "")
+        };
         let prompt = format!(
             "Please clean up and improve the following transcript. \
-            Fix any grammar errors, improve sentence structure, remove filler words and repetitions, \
+            Fix any grammar errors, improve sentence structure, {}\
             and make it more readable while preserving the original meaning and content. \
-            Do not add any information that wasn't in the original transcript.\n\n\
-            IMPORTANT: Remove all references to products, websites, courses, training programs, \
-            email addresses, social media handles, or any promotional content that the presenter may offer. \
-            Focus only on the educational or informational content.\n\n\
-            {}\
+            Do not add any information that wasn't in the original transcript, \
+            {}\n\n\
+            {}{}\
             Transcript:\n\n{}",
+            filler_instruction,
+            keep_instruction,
+            promotional_instruction,
             format_instruction,
             transcript_text
         );
 
-        let request = ChatRequest {
-            model: "gpt-4o-mini".to_string(),
-            messages: vec![
-                Message {
-                    role: "system".to_string(),
-                    content: "You are a helpful assistant that cleans up and improves transcripts while preserving their original meaning. You remove promotional content like product mentions, website URLs, course offers, and training programs.".to_string(),
-                },
-                Message {
-                    role: "user".to_string(),
-                    content: prompt,
-                },
-            ],
-            temperature: 0.3,
+        let system_prompt = if self.remove_promotional {
+            "You are a helpful assistant that cleans up and improves transcripts while preserving their original meaning. You remove promotional content like product mentions, website URLs, course offers, and training programs."
+        } else {
+            "You are a helpful assistant that cleans up and improves transcripts while preserving their original meaning."
         };
+        (system_prompt, prompt)
+    }
 
-        let response = self
-            .client
-            .post(OPENAI_API_URL)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| TranscriptError::HttpError(format!("Failed to call OpenAI API: {}", e)))?;
+    /// Translate transcript text into `target_language` (a language code such as `de`)
+    pub async fn translate(&self, transcript_text: &str, target_language: &str) -> Result<String> {
+        let prompt = format!(
+            "Translate the following transcript into the language with code \"{}\". \
+            Preserve the original meaning, tone, and paragraph structure. \
+            Do not summarize, add commentary, or include the original text. \
+            Respond with the translation only.\n\n\
+            Transcript:\n\n{}",
+            target_language, transcript_text
+        );
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(TranscriptError::HttpError(format!(
-                "OpenAI API error ({}): {}",
-                status, error_text
-            )));
+        self.complete(
+            "You are a professional translator who translates video transcripts faithfully and fluently.",
+            prompt,
+        )
+        .await
+    }
+
+    async fn complete(&self, system_prompt: &str, user_prompt: String) -> Result<String> {
+        self.provider.complete(system_prompt, &user_prompt).await
+    }
+}
+
+/// Put the cleaned `N: text` lines of a `cleanup_cues` reply back onto the cues
+/// they came from
+fn reattach_cleaned_lines(window: &[TranscriptItem], reply: &str) -> Vec<TranscriptItem> {
+    let mut cleaned: Vec<Option<String>> = vec![None; window.len()];
+    for line in reply.lines() {
+        let Some((number, text)) = line.split_once(':') else {
+            continue;
+        };
+        let slot = number
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|index| cleaned.get_mut(index));
+        if let Some(slot) = slot {
+            *slot = Some(text.trim().to_string());
         }
+    }
 
-        let chat_response: ChatResponse = response.json().await.map_err(|e| {
-            TranscriptError::JsonParseError(format!("Failed to parse OpenAI response: {}", e))
-        })?;
+    let missing = cleaned.iter().filter(|text| text.is_none()).count();
+    if missing > 0 {
+        log::warn!(
+            "ChatGPT left out {} of {} lines; keeping their original text",
+            missing,
+            window.len()
+        );
+    }
 
-        let cleaned_text = chat_response
-            .choices
-            .first()
-            .map(|choice| choice.message.content.clone())
-            .ok_or_else(|| TranscriptError::HttpError("No response from OpenAI API".to_string()))?;
+    window
+        .iter()
+        .zip(cleaned)
+        .filter_map(|(item, text)| match text {
+            Some(text) if text.is_empty() => None,
+            Some(text) => Some(TranscriptItem {
+                text,
+                ..item.clone()
+            }),
+            None => Some(item.clone()),
+        })
+        .collect()
+}
+
+/// Extract the reply from an OpenAI response body, surfacing the API's own error
+/// message when there is one and a snippet of the body when it can't be parsed
+fn parse_chat_response(status: reqwest::StatusCode, body: &str) -> Result<(String, Option<Usage>)> {
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let message = match serde_json::from_str::<ErrorResponse>(body) {
+            Ok(ErrorResponse { error }) => error.message,
+            Err(_) => body_snippet(body),
+        };
+        return Err(TranscriptError::RateLimited(format!(
+            "OpenAI API: {}",
+            message
+        )));
+    }
+
+    if let Ok(ErrorResponse { error }) = serde_json::from_str::<ErrorResponse>(body) {
+        let kind = error.kind.map(|k| format!(" [{}]", k)).unwrap_or_default();
+        return Err(TranscriptError::HttpError(format!(
+            "OpenAI API error ({}){}: {}",
+            status, kind, error.message
+        )));
+    }
+
+    if !status.is_success() {
+        return Err(TranscriptError::HttpError(format!(
+            "OpenAI API error ({}): {}",
+            status,
+            body_snippet(body)
+        )));
+    }
+
+    let chat_response: ChatResponse = serde_json::from_str(body).map_err(|e| {
+        TranscriptError::JsonParseError(format!(
+            "Failed to parse OpenAI response: {} (body: {})",
+            e,
+            body_snippet(body)
+        ))
+    })?;
+
+    let text = chat_response
+        .choices
+        .first()
+        .map(|choice| choice.message.content.clone())
+        .ok_or_else(|| TranscriptError::HttpError("No response from OpenAI API".to_string()))?;
+
+    Ok((text.trim().to_string(), chat_response.usage))
+}
 
-        Ok(cleaned_text.trim().to_string())
+fn body_snippet(body: &str) -> String {
+    let body = body.trim();
+    if body.chars().count() > BODY_SNIPPET_CHARS {
+        format!(
+            "{}...",
+            body.chars().take(BODY_SNIPPET_CHARS).collect::<String>()
+        )
+    } else {
+        body.to_string()
     }
 }
 
@@ -146,6 +777,291 @@ mod tests {
         assert!(ChatGPT::new(Some("test-key".to_string())).is_ok());
     }
 
+    /// Answers every prompt with the user message it was sent, uppercased
+    struct Shout;
+
+    impl LlmProvider for Shout {
+        fn complete<'a>(&'a self, _system: &'a str, user: &'a str) -> Completion<'a> {
+            Box::pin(async move { Ok(user.to_uppercase()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_provider() {
+        let chatgpt = ChatGPT::with_provider(Shout).with_cleanup_prompt("Clean:");
+        let cleaned = chatgpt.cleanup_transcript("hello", false).await.unwrap();
+        assert!(cleaned.ends_with("TRANSCRIPT:\n\nHELLO"));
+        assert_eq!(chatgpt.usage(), Usage::default());
+
+        // Boxed, providers are interchangeable behind one type
+        let providers: Vec<ChatGPT<Box<dyn LlmProvider>>> = vec![
+            ChatGPT::with_provider(Shout)
+                .with_cleanup_prompt("Clean:")
+                .boxed(),
+            ChatGPT::new(Some("test-key".to_string())).unwrap().boxed(),
+        ];
+        let cleaned = providers[0]
+            .cleanup_transcript("hello", false)
+            .await
+            .unwrap();
+        assert!(cleaned.ends_with("TRANSCRIPT:\n\nHELLO"));
+        assert_eq!(providers[1].usage(), Usage::default());
+    }
+
+    #[test]
+    fn test_reattach_cleaned_lines() {
+        let window = vec![
+            TranscriptItem {
+                text: "um so like we start".into(),
+                start: 0.0,
+                duration: 2.0,
+            },
+            TranscriptItem {
+                text: "uh".into(),
+                start: 2.0,
+                duration: 0.5,
+            },
+            TranscriptItem {
+                text: "the the results".into(),
+                start: 2.5,
+                duration: 3.0,
+            },
+            TranscriptItem {
+                text: "thanks".into(),
+                start: 5.5,
+                duration: 1.0,
+            },
+        ];
+        let reply = "1: So we start.\n2:\n3: The results: great\n";
+
+        let cleaned = reattach_cleaned_lines(&window, reply);
+        let texts: Vec<&str> = cleaned.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["So we start.", "The results: great", "thanks"]);
+        assert_eq!((cleaned[1].start, cleaned[1].duration), (2.5, 3.0));
+        assert_eq!(cleaned[2].start, 5.5);
+    }
+
+    #[test]
+    fn test_cleanup_cues_prompts_number_lines() {
+        let chatgpt = ChatGPT::new(Some("test-key".to_string())).unwrap();
+        let window = vec![
+            TranscriptItem {
+                text: "hello".into(),
+                start: 0.0,
+                duration: 1.0,
+            },
+            TranscriptItem {
+                text: "two\nlines".into(),
+                start: 1.0,
+                duration: 1.0,
+            },
+        ];
+        let (_, prompt) = chatgpt.cleanup_cues_prompts(&window);
+        assert!(prompt.ends_with("1: hello\n2: two lines\n"));
+    }
+
+    #[test]
+    fn test_cleanup_prompts_default() {
+        let chatgpt = ChatGPT::new(Some("test-key".to_string())).unwrap();
+        let (system, prompt) = chatgpt.cleanup_prompts("hello there", false);
+        assert!(system.contains("promotional"));
+        assert!(prompt.contains("Remove all references to products"));
+        assert!(
+            prompt.contains("do not remove any other information beyond the promotional content")
+        );
+        assert!(!prompt.contains("do not remove any that was"));
+        assert!(prompt.ends_with("Transcript:\n\nhello there"));
+    }
+
+    #[test]
+    fn test_cleanup_prompts_toggles() {
+        let chatgpt = ChatGPT::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_promotional_removal(false);
+        let (system, prompt) = chatgpt.cleanup_prompts("hello there", false);
+        assert!(!system.contains("promotional"));
+        assert!(!prompt.contains("Remove all references"));
+        assert!(prompt.contains("do not remove any that was"));
+        assert!(prompt.contains("remove filler words"));
+
+        let chatgpt = chatgpt
+            .with_filler_removal(false)
+            .with_promotional_removal(true);
+        let (system, prompt) = chatgpt.cleanup_prompts("hello there", false);
+        assert!(system.contains("promotional"));
+        assert!(prompt.contains("Remove all references"));
+        assert!(!prompt.contains("filler"));
+        assert!(prompt.contains("improve sentence structure, and make it more readable"));
+
+        let window = vec![TranscriptItem {
+            text: "um hello".into(),
+            start: 0.0,
+            duration: 1.0,
+        }];
+        let (_, prompt) = chatgpt.cleanup_cues_prompts(&window);
+        assert!(prompt.contains("keeping filler words"));
+    }
+
+    #[test]
+    fn test_cleanup_prompts_custom() {
+        let chatgpt = ChatGPT::new(Some("test-key".to_string()))
+            .unwrap()
+            .with_cleanup_prompt("Summarize this lecture in five bullet points.\n");
+        let (system, prompt) = chatgpt.cleanup_prompts("hello there", true);
+        assert!(!system.contains("promotional"));
+        assert!(prompt.starts_with("Summarize this lecture in five bullet points.\n\n"));
+        assert!(!prompt.contains("Remove all references"));
+        assert!(prompt.contains("Markdown syntax"));
+        assert!(prompt.ends_with("Transcript:\n\nhello there"));
+    }
+
+    #[test]
+    fn test_parse_chat_response_success() {
+        let body =
+            r#"{"choices":[{"message":{"role":"assistant","content":"  Cleaned text \n"}}]}"#;
+        assert_eq!(
+            parse_chat_response(reqwest::StatusCode::OK, body).unwrap(),
+            ("Cleaned text".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_parse_chat_response_usage() {
+        let body = r#"{"choices":[{"message":{"content":"Hi"}}],"usage":{"prompt_tokens":120,"completion_tokens":30,"total_tokens":150}}"#;
+        let (_, usage) = parse_chat_response(reqwest::StatusCode::OK, body).unwrap();
+        let usage = usage.unwrap();
+        assert_eq!(usage.total_tokens, 150);
+        assert_eq!(usage.to_string(), "120 prompt + 30 completion = 150 tokens");
+    }
+
+    #[test]
+    fn test_streamed_reply() {
+        let body = "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n\
+            data: {\"choices\":[{\"delta\":{\"content\":\" Caf\u{e9}\"}}]}\n\n\
+            : keep-alive\n\n\
+            data: {\"choices\":[{\"delta\":{\"content\":\" au lait \"}}]}\n\n\
+            data: {\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":3,\"total_tokens\":12}}\n\n\
+            data: [DONE]\n\n";
+
+        // Split mid-line, and inside the two-byte é
+        let bytes = body.as_bytes();
+        let split = body.find('\u{e9}').unwrap() + 1;
+        let mut reply = StreamedReply::default();
+        let mut pieces = vec![reply.push(&bytes[..split]).unwrap()];
+        pieces.push(reply.push(&bytes[split..]).unwrap());
+        assert_eq!(pieces.concat(), " Caf\u{e9} au lait ");
+
+        let (text, usage) = reply.finish().unwrap();
+        assert_eq!(text, "Caf\u{e9} au lait");
+        assert_eq!(usage.unwrap().total_tokens, 12);
+    }
+
+    #[test]
+    fn test_streamed_reply_errors() {
+        let mut reply = StreamedReply::default();
+        let err = reply
+            .push(b"data: {\"error\":{\"message\":\"server overloaded\"}}\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("server overloaded"));
+
+        let mut reply = StreamedReply::default();
+        reply.push(b"data: [DONE]\n").unwrap();
+        assert!(reply.finish().is_err());
+    }
+
+    #[test]
+    fn test_usage_since() {
+        let mut total = Usage {
+            prompt_tokens: 100,
+            completion_tokens: 20,
+            total_tokens: 120,
+        };
+        let before = total;
+        total.add(Usage {
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+        });
+        assert_eq!(total.since(before).total_tokens, 15);
+        assert_eq!(total.since(before).prompt_tokens, 10);
+    }
+
+    #[test]
+    fn test_parse_chat_response_api_error() {
+        let body = r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#;
+        let err = parse_chat_response(reqwest::StatusCode::UNAUTHORIZED, body).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("Incorrect API key provided"));
+        assert!(message.contains("invalid_request_error"));
+        assert!(!message.contains("\"code\""));
+    }
+
+    #[test]
+    fn test_parse_chat_response_rate_limited() {
+        let body = r#"{"error":{"message":"Rate limit reached for requests","type":"requests"}}"#;
+        let err = parse_chat_response(reqwest::StatusCode::TOO_MANY_REQUESTS, body).unwrap_err();
+        assert!(
+            matches!(err, TranscriptError::RateLimited(ref m) if m.contains("Rate limit reached"))
+        );
+    }
+
+    #[test]
+    fn test_parse_chat_response_unexpected_body() {
+        let body = format!("content filtered {}", "x".repeat(1000));
+        let err = parse_chat_response(reqwest::StatusCode::OK, &body).unwrap_err();
+        assert!(matches!(err, TranscriptError::JsonParseError(_)));
+        let message = err.to_string();
+        assert!(message.contains("content filtered"));
+        assert!(message.len() < 500);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        // 600 per minute is one every 100ms
+        let limiter = RateLimiter::per_minute(600, 1);
+        let started = std::time::Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        // The first request goes straight through, the next three wait their turn
+        assert!(started.elapsed() >= Duration::from_millis(290));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_shared_by_concurrent_callers() {
+        let limiter = std::sync::Arc::new(RateLimiter::per_minute(600, 2));
+        let started = std::time::Instant::now();
+        let calls: Vec<_> = (0..4)
+            .map(|_| {
+                let limiter = std::sync::Arc::clone(&limiter);
+                tokio::spawn(async move { limiter.acquire().await })
+            })
+            .collect();
+        for call in calls {
+            call.await.unwrap();
+        }
+        // Two go out in the burst, the other two 100ms apart after that
+        assert!(started.elapsed() >= Duration::from_millis(190));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_chatgpt_with_timeout() {
+        // The timeout survives a client set either before or after it
+        for client_first in [false, true] {
+            let mut chatgpt = ChatGPT::new(Some("test-key".to_string())).unwrap();
+            if client_first {
+                chatgpt = chatgpt.with_client(reqwest::Client::new());
+            }
+            chatgpt = chatgpt.with_timeout(Duration::from_secs(60));
+            if !client_first {
+                chatgpt = chatgpt.with_client(reqwest::Client::new());
+            }
+            assert_eq!(chatgpt.provider.timeout, Some(Duration::from_secs(60)));
+        }
+    }
+
     #[test]
     fn test_chatgpt_new_with_env_var() {
         std::env::set_var("OPENAI_API_KEY", "test-env-key");
@@ -0,0 +1,95 @@
+use crate::error::{Result, TranscriptError};
+
+/// A single cookie from a Netscape-format jar, ready to hand to `reqwest::cookie::Jar`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetscapeCookie {
+    /// `Set-Cookie`-style header value, e.g. `SID=abc; Domain=.youtube.com; Path=/`
+    pub header: String,
+    /// URL the cookie is scoped to
+    pub url: url::Url,
+}
+
+/// Parse the contents of a Netscape `cookies.txt` file (as exported by browser extensions
+/// and `yt-dlp --cookies`).
+///
+/// Each non-comment line has seven tab-separated fields:
+/// `domain`, `include_subdomains`, `path`, `secure`, `expiry`, `name`, `value`.
+/// Lines prefixed with `#HttpOnly_` are treated as regular cookies.
+pub fn parse_netscape_cookies(content: &str) -> Result<Vec<NetscapeCookie>> {
+    let mut cookies = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            return Err(TranscriptError::InvalidArgument(format!(
+                "cookie file line {}: expected 7 tab-separated fields, found {}",
+                line_number + 1,
+                fields.len()
+            )));
+        }
+
+        let domain = fields[0];
+        let include_subdomains = fields[1].eq_ignore_ascii_case("TRUE");
+        let path = fields[2];
+        let secure = fields[3].eq_ignore_ascii_case("TRUE");
+        let name = fields[5];
+        let value = fields[6];
+
+        let host = domain.trim_start_matches('.');
+        let url = url::Url::parse(&format!("https://{}{}", host, path)).map_err(|e| {
+            TranscriptError::InvalidArgument(format!(
+                "cookie file line {}: invalid domain or path: {}",
+                line_number + 1,
+                e
+            ))
+        })?;
+
+        let mut header = format!("{}={}; Path={}", name, value, path);
+        if include_subdomains {
+            header.push_str(&format!("; Domain={}", host));
+        }
+        if secure {
+            header.push_str("; Secure");
+        }
+
+        cookies.push(NetscapeCookie { header, url });
+    }
+
+    Ok(cookies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netscape_cookies() {
+        let content = "# Netscape HTTP Cookie File\n\
+            \n\
+            .youtube.com\tTRUE\t/\tTRUE\t1799999999\tSID\tabc123\n\
+            #HttpOnly_.youtube.com\tTRUE\t/\tTRUE\t1799999999\t__Secure-3PSID\tsecret\n\
+            www.youtube.com\tFALSE\t/\tFALSE\t0\tPREF\tf6=40000000\n";
+
+        let cookies = parse_netscape_cookies(content).unwrap();
+        assert_eq!(cookies.len(), 3);
+        assert_eq!(
+            cookies[0].header,
+            "SID=abc123; Path=/; Domain=youtube.com; Secure"
+        );
+        assert_eq!(cookies[0].url.as_str(), "https://youtube.com/");
+        assert!(cookies[1].header.starts_with("__Secure-3PSID=secret"));
+        assert_eq!(cookies[2].header, "PREF=f6=40000000; Path=/");
+        assert_eq!(cookies[2].url.as_str(), "https://www.youtube.com/");
+    }
+
+    #[test]
+    fn test_parse_netscape_cookies_invalid_line() {
+        assert!(parse_netscape_cookies(".youtube.com\tTRUE\t/\n").is_err());
+    }
+}
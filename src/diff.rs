@@ -0,0 +1,321 @@
+use crate::TranscriptItem;
+
+/// Cues need at least this word similarity to be aligned unless they overlap in time
+const MIN_SIMILARITY: f64 = 0.5;
+
+/// Cues that start further apart than this are never aligned, so only a band of
+/// cue pairs around the diagonal has to be scored rather than every pair
+const MAX_DRIFT_SECS: f64 = 30.0;
+
+/// One step of a [`diff_transcripts`] result, in transcript order
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Diff {
+    /// A cue in `a` matched to one in `b`; `similarity` is 1.0 when the words are the same
+    Aligned {
+        a: TranscriptItem,
+        b: TranscriptItem,
+        similarity: f64,
+    },
+    /// A cue only in `b`
+    Insert(TranscriptItem),
+    /// A cue only in `a`
+    Delete(TranscriptItem),
+}
+
+impl Diff {
+    /// Aligned cues whose words differ (ignoring case and punctuation)
+    pub fn is_changed(&self) -> bool {
+        matches!(self, Diff::Aligned { similarity, .. } if *similarity < 1.0)
+    }
+}
+
+/// Lowercased words with punctuation removed, so "Hello, world" matches "hello world"
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Dice coefficient of the two word lists: the share of words they have in common
+fn similarity(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let mut unmatched: Vec<&String> = b.iter().collect();
+    let mut common = 0;
+    for word in a {
+        if let Some(index) = unmatched.iter().position(|other| *other == word) {
+            unmatched.swap_remove(index);
+            common += 1;
+        }
+    }
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+fn overlaps(a: &TranscriptItem, b: &TranscriptItem) -> bool {
+    a.start < b.start + b.duration && b.start < a.start + a.duration
+}
+
+/// Align the cues of two transcripts of the same video, e.g. a manual and an
+/// auto-generated track, or two translations
+///
+/// Two cues can be aligned when at least half their words match, or when they
+/// overlap in time and share any word, so reworded and drifted cues both pair up.
+/// Cues starting more than 30 seconds apart are never aligned. Among the alignments
+/// that keep both transcripts in order, the one with the highest total similarity
+/// is chosen; every other cue is a [`Diff::Delete`] (only in `a`) or a
+/// [`Diff::Insert`] (only in `b`). Both transcripts are expected in time order, as
+/// fetched or parsed transcripts are.
+pub fn diff_transcripts(a: &[TranscriptItem], b: &[TranscriptItem]) -> Vec<Diff> {
+    let a_words: Vec<Vec<String>> = a.iter().map(|item| words(&item.text)).collect();
+    let b_words: Vec<Vec<String>> = b.iter().map(|item| words(&item.text)).collect();
+    let band = Band::new(a, b);
+    let score = |i: usize, j: usize| {
+        if !band.contains(i, j) || (a[i].start - b[j].start).abs() > MAX_DRIFT_SECS {
+            return None;
+        }
+        let similarity = similarity(&a_words[i], &b_words[j]);
+        let alignable =
+            similarity >= MIN_SIMILARITY || (similarity > 0.0 && overlaps(&a[i], &b[j]));
+        alignable.then_some(similarity)
+    };
+
+    // best: highest total similarity aligning a[i..] with b[j..], for (i, j) in the band
+    let mut best = vec![0.0f64; band.len()];
+    for i in (0..a.len()).rev() {
+        for j in (band.lo[i]..band.hi[i]).rev() {
+            let skip = band.best(&best, i + 1, j).max(band.best(&best, i, j + 1));
+            best[band.index(i, j)] = match score(i, j) {
+                Some(similarity) => skip.max(similarity + band.best(&best, i + 1, j + 1)),
+                None => skip,
+            };
+        }
+    }
+
+    let mut diffs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        // Left of a's band, b[j] is too early for any cue still in a; right of it,
+        // a[i] is too early for any cue still in b
+        if j < band.lo[i] {
+            diffs.push(Diff::Insert(b[j].clone()));
+            j += 1;
+            continue;
+        }
+        if j >= band.hi[i] {
+            diffs.push(Diff::Delete(a[i].clone()));
+            i += 1;
+            continue;
+        }
+        let here = best[band.index(i, j)];
+        match score(i, j) {
+            Some(similarity) if here == similarity + band.best(&best, i + 1, j + 1) => {
+                diffs.push(Diff::Aligned {
+                    a: a[i].clone(),
+                    b: b[j].clone(),
+                    similarity,
+                });
+                i += 1;
+                j += 1;
+            }
+            _ if here == band.best(&best, i + 1, j) => {
+                diffs.push(Diff::Delete(a[i].clone()));
+                i += 1;
+            }
+            _ => {
+                diffs.push(Diff::Insert(b[j].clone()));
+                j += 1;
+            }
+        }
+    }
+    diffs.extend(a[i..].iter().cloned().map(Diff::Delete));
+    diffs.extend(b[j..].iter().cloned().map(Diff::Insert));
+    diffs
+}
+
+/// For each cue `a[i]`, the range `lo[i]..hi[i]` of cues in `b` starting within
+/// [`MAX_DRIFT_SECS`] of it. Both bounds only move forward from one cue to the
+/// next, so a cue of `b` left of a row's range is out of reach for every later row.
+struct Band {
+    lo: Vec<usize>,
+    hi: Vec<usize>,
+    /// Where each row's cells start in the flattened table
+    offsets: Vec<usize>,
+    b_len: usize,
+}
+
+impl Band {
+    fn new(a: &[TranscriptItem], b: &[TranscriptItem]) -> Self {
+        let (mut lo, mut hi, mut offsets) = (Vec::new(), Vec::new(), Vec::new());
+        let (mut row_lo, mut row_hi, mut offset) = (0, 0, 0);
+        for item in a {
+            row_lo = b
+                .partition_point(|other| other.start < item.start - MAX_DRIFT_SECS)
+                .max(row_lo);
+            row_hi = b
+                .partition_point(|other| other.start <= item.start + MAX_DRIFT_SECS)
+                .max(row_hi)
+                .max(row_lo);
+            lo.push(row_lo);
+            hi.push(row_hi);
+            offsets.push(offset);
+            offset += row_hi - row_lo;
+        }
+        offsets.push(offset);
+        Self {
+            lo,
+            hi,
+            offsets,
+            b_len: b.len(),
+        }
+    }
+
+    /// Number of cells in the table
+    fn len(&self) -> usize {
+        self.offsets[self.lo.len()]
+    }
+
+    fn contains(&self, i: usize, j: usize) -> bool {
+        i < self.lo.len() && (self.lo[i]..self.hi[i]).contains(&j)
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        self.offsets[i] + j - self.lo[i]
+    }
+
+    /// `best` for aligning `a[i..]` with `b[j..]`, also outside the band: cues of
+    /// `b` left of row `i` can't be aligned any more and are skipped, and past its
+    /// right end `a[i]` can't be, so the answer is the next row's
+    fn best(&self, best: &[f64], mut i: usize, mut j: usize) -> f64 {
+        while i < self.lo.len() && j < self.b_len {
+            if j < self.lo[i] {
+                j = self.lo[i];
+            } else if j >= self.hi[i] {
+                i += 1;
+            } else {
+                return best[self.index(i, j)];
+            }
+        }
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, start: f64, duration: f64) -> TranscriptItem {
+        TranscriptItem {
+            text: text.to_string(),
+            start,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_diff_transcripts() {
+        let manual = vec![
+            item("Hello, world!", 0.0, 2.0),
+            item("This is a test.", 2.0, 2.0),
+            item("Goodbye.", 4.0, 1.0),
+        ];
+        let auto = vec![
+            item("hello world", 0.5, 2.0),
+            item("um", 2.0, 0.5),
+            item("this is the test", 2.5, 2.0),
+        ];
+
+        let diffs = diff_transcripts(&manual, &auto);
+        assert_eq!(diffs.len(), 4);
+        assert!(matches!(&diffs[0], Diff::Aligned { similarity, .. } if *similarity == 1.0));
+        assert_eq!(diffs[1], Diff::Insert(auto[1].clone()));
+        assert!(
+            matches!(&diffs[2], Diff::Aligned { a, b, .. } if a.text == "This is a test." && b.text == "this is the test")
+        );
+        assert!(diffs[2].is_changed());
+        assert_eq!(diffs[3], Diff::Delete(manual[2].clone()));
+    }
+
+    #[test]
+    fn test_diff_transcripts_needs_shared_words() {
+        // Overlapping cues with nothing in common are a deletion and an insertion
+        let diffs = diff_transcripts(&[item("apples", 0.0, 2.0)], &[item("oranges", 0.0, 2.0)]);
+        assert!(matches!(
+            diffs.as_slice(),
+            [Diff::Delete(_), Diff::Insert(_)]
+        ));
+        assert!(diff_transcripts(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_diff_transcripts_ignores_distant_cues() {
+        // The same words a minute apart are a deletion and an insertion, not drift
+        let diffs = diff_transcripts(
+            &[item("hello world", 0.0, 2.0)],
+            &[item("hello world", 60.0, 2.0)],
+        );
+        assert!(matches!(
+            diffs.as_slice(),
+            [Diff::Delete(_), Diff::Insert(_)]
+        ));
+
+        let a = vec![
+            item("one", 0.0, 1.0),
+            item("two", 45.0, 1.0),
+            item("three", 90.0, 1.0),
+        ];
+        let b = vec![
+            item("two", 50.0, 1.0),
+            item("three", 91.0, 1.0),
+            item("four", 200.0, 1.0),
+        ];
+        let diffs = diff_transcripts(&a, &b);
+        assert_eq!(diffs.len(), 4);
+        assert_eq!(diffs[0], Diff::Delete(a[0].clone()));
+        assert!(matches!(&diffs[1], Diff::Aligned { a, .. } if a.text == "two"));
+        assert!(matches!(&diffs[2], Diff::Aligned { a, .. } if a.text == "three"));
+        assert_eq!(diffs[3], Diff::Insert(b[2].clone()));
+    }
+
+    #[test]
+    fn test_diff_transcripts_long_transcripts() {
+        // Ten hours of cues each; a table of every pair would need 1.3e9 cells
+        let a: Vec<TranscriptItem> = (0..36_000)
+            .map(|n| item(&format!("cue number {}", n), n as f64, 1.0))
+            .collect();
+        let mut b: Vec<TranscriptItem> = a
+            .iter()
+            .map(|cue| item(&cue.text, cue.start + 2.0, 1.0))
+            .collect();
+        b.remove(100);
+        b[200].text = "something else entirely".to_string();
+
+        let diffs = diff_transcripts(&a, &b);
+        let aligned = diffs
+            .iter()
+            .filter(|diff| matches!(diff, Diff::Aligned { .. }))
+            .count();
+        assert_eq!(aligned, a.len() - 2);
+        assert_eq!(
+            diffs
+                .iter()
+                .filter(|diff| matches!(diff, Diff::Delete(_)))
+                .count(),
+            2
+        );
+        assert_eq!(
+            diffs
+                .iter()
+                .filter(|diff| matches!(diff, Diff::Insert(_)))
+                .count(),
+            1
+        );
+    }
+}
@@ -11,25 +11,31 @@ pub enum TranscriptError {
     #[error("No transcript found for video {0} in languages: {1:?}")]
     NoTranscriptFound(String, Vec<String>),
 
+    #[error("Transcript for video {0} has no text (e.g. music-only or all cues empty)")]
+    EmptyTranscript(String),
+
     #[error("Age restricted video: {0}")]
     AgeRestricted(String),
 
     #[error("IP blocked for video: {0}")]
     IpBlocked(String),
 
-    #[error("Request blocked (bot detected) for video: {0}")]
+    #[error("Rate limited (HTTP 429) on {0}; slow down with --delay or try again later")]
+    RateLimited(String),
+
+    #[error("Request blocked (bot detected) for video: {0}; cookies from a signed-in browser (--cookies) may get past the check")]
     RequestBlocked(String),
 
     #[error("Video unplayable: {0} - {1}")]
     VideoUnplayable(String, String),
 
-    #[error("Failed to create consent cookie for video: {0}")]
+    #[error("Failed to create consent cookie for video: {0}; use cookies from a browser that has accepted YouTube's consent page (--cookies)")]
     FailedToCreateConsentCookie(String),
 
     #[error("YouTube data unparsable for video: {0}")]
     YouTubeDataUnparsable(String),
 
-    #[error("Protected video requires token: {0}")]
+    #[error("Protected video requires a PO token (supply one with --po-token): {0}")]
     PoTokenRequired(String),
 
     #[error("Invalid video ID: {0}")]
@@ -38,12 +44,30 @@ pub enum TranscriptError {
     #[error("HTTP request failed: {0}")]
     HttpError(String),
 
+    /// A request that failed in the HTTP client, keeping the client's error as the `source()`
+    #[error("HTTP request failed: {context}: {}", describe_http_error(.source))]
+    Http {
+        context: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
     #[error("Failed to parse XML: {0}")]
     XmlParseError(String),
 
     #[error("Failed to parse JSON: {0}")]
     JsonParseError(String),
 
+    #[error("Failed to parse subtitles: {0}")]
+    SubtitleParseError(String),
+
+    /// Invalid JSON, keeping the parser's error as the `source()`
+    #[error("Failed to parse JSON: {source}")]
+    Json {
+        #[from]
+        source: serde_json::Error,
+    },
+
     #[error("Translation not available: {0}")]
     NotTranslatable(String),
 
@@ -52,6 +76,9 @@ pub enum TranscriptError {
 
     #[error("IO error: {0}")]
     IoError(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
 }
 
 impl From<std::io::Error> for TranscriptError {
@@ -60,13 +87,44 @@ impl From<std::io::Error> for TranscriptError {
     }
 }
 
-impl From<serde_json::Error> for TranscriptError {
-    fn from(err: serde_json::Error) -> Self {
-        TranscriptError::JsonParseError(err.to_string())
+pub type Result<T> = std::result::Result<T, TranscriptError>;
+
+/// A 429 becomes `RateLimited`; every other client error becomes `Http`, described
+/// by the host and path it was for
+impl From<reqwest::Error> for TranscriptError {
+    fn from(err: reqwest::Error) -> Self {
+        let target = err
+            .url()
+            .map(|url| format!("{}{}", url.host_str().unwrap_or_default(), url.path()));
+        if err.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
+            return TranscriptError::RateLimited(target.unwrap_or_else(|| "request".to_string()));
+        }
+        TranscriptError::Http {
+            context: target
+                .map(|target| format!("request to {}", target))
+                .unwrap_or_else(|| "HTTP client".to_string()),
+            source: err,
+        }
     }
 }
 
-pub type Result<T> = std::result::Result<T, TranscriptError>;
+/// Plain description of what went wrong: a timeout, a connection failure (with its
+/// root cause, e.g. a DNS error), an error status, or reqwest's own message
+fn describe_http_error(err: &reqwest::Error) -> String {
+    if err.is_timeout() {
+        "request timed out".to_string()
+    } else if err.is_connect() {
+        let mut cause: &dyn std::error::Error = err;
+        while let Some(source) = cause.source() {
+            cause = source;
+        }
+        format!("could not connect ({})", cause)
+    } else if let Some(status) = err.status() {
+        format!("server returned {}", status)
+    } else {
+        err.to_string()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -78,6 +136,98 @@ mod tests {
         assert!(err.to_string().contains("test123"));
     }
 
+    #[test]
+    fn test_empty_transcript_display() {
+        let err = TranscriptError::EmptyTranscript("test123".to_string());
+        assert!(err.to_string().contains("test123"));
+        assert!(err.to_string().contains("no text"));
+    }
+
+    #[tokio::test]
+    async fn test_http_error_reports_timeout() {
+        // A server that accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let _conn = listener.accept().await;
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        });
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let err = client
+            .get(format!("http://{}/watch?v=x", addr))
+            .send()
+            .await
+            .unwrap_err();
+        let err = TranscriptError::from(err);
+        assert_eq!(
+            err.to_string(),
+            "HTTP request failed: request to 127.0.0.1/watch: request timed out"
+        );
+
+        // The client's error stays reachable for callers that want the details
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source
+            .downcast_ref::<reqwest::Error>()
+            .unwrap()
+            .is_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_error_from_reqwest_classifies_status() {
+        // Answer every request with the status given in its path
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            while let Ok((mut conn, _)) = listener.accept().await {
+                let mut request = [0u8; 1024];
+                let n = conn.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..n]);
+                let status = request
+                    .split(['/', ' '])
+                    .nth(2)
+                    .unwrap_or("500")
+                    .to_string();
+                let response = format!("HTTP/1.1 {} X\r\ncontent-length: 0\r\n\r\n", status);
+                let _ = conn.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let client = reqwest::Client::new();
+        let fetch = |status: u16| {
+            let request = client.get(format!("http://{}/{}", addr, status));
+            async move {
+                let response = request.send().await.unwrap();
+                TranscriptError::from(response.error_for_status().unwrap_err())
+            }
+        };
+
+        assert!(
+            matches!(fetch(429).await, TranscriptError::RateLimited(target) if target == "127.0.0.1/429")
+        );
+        let err = fetch(503).await;
+        assert!(matches!(err, TranscriptError::Http { .. }));
+        assert!(err
+            .to_string()
+            .ends_with("server returned 503 Service Unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_error_from_reqwest_connect_failure() {
+        // Bind then drop a listener so the port is very likely closed
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let err = reqwest::get(format!("http://{}/", addr)).await.unwrap_err();
+        let message = TranscriptError::from(err).to_string();
+        assert!(message.contains("could not connect"), "{}", message);
+    }
+
     #[test]
     fn test_error_from_io_error() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
@@ -90,8 +240,13 @@ mod tests {
         let json_err = serde_json::from_str::<serde_json::Value>("invalid json");
         assert!(json_err.is_err());
         if let Err(e) = json_err {
+            let message = format!("Failed to parse JSON: {}", e);
             let transcript_err: TranscriptError = e.into();
-            assert!(matches!(transcript_err, TranscriptError::JsonParseError(_)));
+            assert!(matches!(transcript_err, TranscriptError::Json { .. }));
+            assert_eq!(transcript_err.to_string(), message);
+            assert!(std::error::Error::source(&transcript_err)
+                .unwrap()
+                .is::<serde_json::Error>());
         }
     }
 }
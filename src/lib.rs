@@ -1,23 +1,126 @@
+mod builder;
+mod cache;
 pub mod chatgpt;
+mod cookies;
+pub mod diff;
 mod error;
 mod parser;
+pub mod stats;
+pub mod timecode;
+pub mod transform;
 
+pub use builder::YouTubeTranscriptBuilder;
 pub use error::{Result, TranscriptError};
-use parser::TranscriptParser;
+pub use parser::{is_srt, is_vtt, parse_srt, parse_vtt, TranscriptParser};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
 
 const WATCH_URL: &str = "https://www.youtube.com/watch?v={video_id}";
 const PLAYLIST_URL: &str = "https://www.youtube.com/playlist?list={playlist_id}";
 const INNERTUBE_API_URL: &str = "https://www.youtube.com/youtubei/v1/player?key={api_key}";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Browser-like user agent sent by default; the stock reqwest agent is more likely to be flagged as a bot
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+const DEFAULT_ACCEPT_LANGUAGE: &str = "en-US";
+
+/// Base for every HTTP client the crate builds. Each `YouTubeTranscript` and `ChatGPT`
+/// keeps one client for its lifetime, so a playlist run reuses pooled keep-alive
+/// connections instead of doing a TCP and TLS handshake per request. HTTP/2 is
+/// offered during the TLS handshake and used when the server accepts it,
+/// multiplexing requests over one connection per host.
+pub(crate) fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .pool_idle_timeout(std::time::Duration::from_secs(90))
+        .pool_max_idle_per_host(8)
+        .tcp_keepalive(std::time::Duration::from_secs(60))
+        .http2_adaptive_window(true)
+}
+
+/// Give each translated cue the timing of the source cue it translates. Only done,
+/// returning `true`, when the two tracks have the same number of cues.
+fn align_to_source(translated: &mut [TranscriptItem], source: &[TranscriptItem]) -> bool {
+    if translated.len() != source.len() {
+        return false;
+    }
+    for (item, source) in translated.iter_mut().zip(source) {
+        item.start = source.start;
+        item.duration = source.duration;
+    }
+    true
+}
+
+/// `delay_ms` moved by up to `jitter_percent` percent either way, picked by `random`
+fn jittered_delay_ms(delay_ms: u64, jitter_percent: u8, random: u64) -> u64 {
+    let spread = delay_ms * u64::from(jitter_percent.min(100)) / 100;
+    delay_ms - spread + random % (2 * spread + 1)
+}
+
+/// One caption cue. Serializes to `{"text", "start", "duration"}` (the `-f json`
+/// output) and deserializes from the same shape, so saved transcripts can be read back.
+/// Compares field by field, so cues can be checked with `assert_eq!` or deduplicated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TranscriptItem {
     pub text: String,
     pub start: f64,
     pub duration: f64,
 }
 
+/// A single word and the time it is spoken, from tracks with per-word timing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimedWord {
+    pub text: String,
+    pub start: f64,
+}
+
+/// A cue together with the timing of each word in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordTimedItem {
+    pub text: String,
+    pub start: f64,
+    pub duration: f64,
+    /// Empty when the track carries no word-level timing for this cue
+    pub words: Vec<TimedWord>,
+}
+
+/// A transcript fetched with [`YouTubeTranscript::fetch_transcript_words`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordTimedTranscript {
+    pub video_id: String,
+    pub title: Option<String>,
+    pub language: String,
+    pub language_code: String,
+    pub is_generated: bool,
+    pub transcript: Vec<WordTimedItem>,
+}
+
+/// A cue with the quality hints some timedtext formats carry, for `--json-verbose`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerboseItem {
+    pub text: String,
+    pub start: f64,
+    pub duration: f64,
+    /// The recognizer's confidence in the cue from 0.0 to 1.0, averaged over its
+    /// words; only auto-generated tracks carry it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// `"asr"` for a cue from an auto-generated track, `"manual"` otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+/// A transcript fetched with [`YouTubeTranscript::fetch_transcript_verbose`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerboseTranscript {
+    pub video_id: String,
+    pub title: Option<String>,
+    pub language: String,
+    pub language_code: String,
+    pub is_generated: bool,
+    pub transcript: Vec<VerboseItem>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptResponse {
     pub video_id: String,
@@ -27,10 +130,16 @@ pub struct TranscriptResponse {
     pub is_generated: bool,
     pub is_translatable: bool,
     pub transcript: Vec<TranscriptItem>,
+    /// The video's chapters as `(title, start in seconds)`, in order; empty when the
+    /// video has none or the transcript didn't come from a fetch
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<(String, f64)>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TranscriptInfo {
+    /// Video the track belongs to, so the track can be fetched on its own
+    pub video_id: String,
     pub language_code: String,
     pub language: String,
     pub is_generated: bool,
@@ -39,21 +148,65 @@ pub struct TranscriptInfo {
     pub translation_languages: Vec<TranslationLanguage>,
 }
 
-#[derive(Debug, Clone)]
+impl TranscriptInfo {
+    /// Download this track
+    ///
+    /// ```no_run
+    /// # async fn example() -> ytt::Result<()> {
+    /// let api = ytt::YouTubeTranscript::new();
+    /// let list = api.list_transcripts("dQw4w9WgXcQ").await?;
+    /// let transcript = list.generated["en"].fetch(&api).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The track list doesn't carry the video title, so the response's `title` is `None`.
+    pub async fn fetch(&self, api: &YouTubeTranscript) -> Result<TranscriptResponse> {
+        api.fetch_transcript_data(&self.video_id, self, None, None)
+            .await
+    }
+
+    /// Languages YouTube can machine-translate this track into, i.e. the targets
+    /// [`YouTubeTranscript::translate_transcript`] accepts with it as the source;
+    /// `NotTranslatable` when the track can't be translated at all
+    pub fn translation_targets(&self) -> Result<&[TranslationLanguage]> {
+        if !self.is_translatable {
+            return Err(TranscriptError::NotTranslatable(self.video_id.clone()));
+        }
+        Ok(&self.translation_languages)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct TranslationLanguage {
     pub language: String,
     pub language_code: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
 pub struct TranscriptList {
     pub video_id: String,
     pub title: Option<String>,
     pub manually_created: HashMap<String, TranscriptInfo>,
     pub generated: HashMap<String, TranscriptInfo>,
     pub translation_languages: Vec<TranslationLanguage>,
+    /// Language picked when none is requested: the video's default caption track,
+    /// else the first manually created track, else the first generated one
+    pub default_language_code: Option<String>,
+    /// Chapters as `(title, start in seconds)`, from the watch page's chapter markers
+    /// or, failing that, the timestamps in the description
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<(String, f64)>,
 }
 
 impl TranscriptList {
+    /// Transcript to use when no language was requested, falling back to English
+    /// when the video has no default track
+    pub fn find_default_transcript(&self) -> Result<&TranscriptInfo> {
+        let language_code = self.default_language_code.as_deref().unwrap_or("en");
+        self.find_transcript(&[language_code])
+    }
+
     pub fn find_transcript(&self, language_codes: &[&str]) -> Result<&TranscriptInfo> {
         // Try manually created first, then generated
         for lang_code in language_codes {
@@ -70,6 +223,125 @@ impl TranscriptList {
         ))
     }
 
+    /// First track in `source_languages` that YouTube can translate into
+    /// `target_language`, trying each language's manually created track before its
+    /// auto-generated one
+    ///
+    /// When none can, the error is `TranslationLanguageNotAvailable` if some track is
+    /// translatable (just not into `target_language`), `NotTranslatable` if the tracks
+    /// found can't be translated at all, and `NoTranscriptFound` if there are none.
+    pub fn find_translatable_transcript(
+        &self,
+        source_languages: &[&str],
+        target_language: &str,
+    ) -> Result<&TranscriptInfo> {
+        let mut error = None;
+        for track in self.source_tracks(source_languages) {
+            match track.translation_targets() {
+                Ok(targets) if targets.iter().any(|t| t.language_code == target_language) => {
+                    return Ok(track);
+                }
+                Ok(_) => {
+                    error = Some(TranscriptError::TranslationLanguageNotAvailable(
+                        target_language.to_string(),
+                    ))
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        Err(error.unwrap_or_else(|| self.no_transcript_found(source_languages)))
+    }
+
+    /// Every target [`find_translatable_transcript`](Self::find_translatable_transcript)
+    /// can find a source for among `source_languages`, i.e. what
+    /// [`YouTubeTranscript::translate_transcript`] accepts with those sources, in the
+    /// order the sources are tried and without duplicates
+    ///
+    /// The error is `NotTranslatable` when none of the tracks found can be translated,
+    /// and `NoTranscriptFound` when there are none.
+    pub fn translation_targets(
+        &self,
+        source_languages: &[&str],
+    ) -> Result<Vec<&TranslationLanguage>> {
+        let mut targets: Vec<&TranslationLanguage> = Vec::new();
+        let mut error = None;
+        let mut any_translatable = false;
+        for track in self.source_tracks(source_languages) {
+            match track.translation_targets() {
+                Ok(track_targets) => {
+                    any_translatable = true;
+                    for target in track_targets {
+                        if !targets
+                            .iter()
+                            .any(|t| t.language_code == target.language_code)
+                        {
+                            targets.push(target);
+                        }
+                    }
+                }
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match error {
+            Some(e) if !any_translatable => Err(e),
+            None if !any_translatable => Err(self.no_transcript_found(source_languages)),
+            _ => Ok(targets),
+        }
+    }
+
+    /// Tracks in each of `source_languages`, manually created before auto-generated
+    fn source_tracks(&self, source_languages: &[&str]) -> Vec<&TranscriptInfo> {
+        source_languages
+            .iter()
+            .flat_map(|lang_code| {
+                [
+                    self.manually_created.get(*lang_code),
+                    self.generated.get(*lang_code),
+                ]
+            })
+            .flatten()
+            .collect()
+    }
+
+    fn no_transcript_found(&self, source_languages: &[&str]) -> TranscriptError {
+        TranscriptError::NoTranscriptFound(
+            self.video_id.clone(),
+            source_languages.iter().map(|s| s.to_string()).collect(),
+        )
+    }
+
+    /// Best guess at the video's original caption language
+    ///
+    /// YouTube runs speech recognition on the original audio, so a lone auto-generated
+    /// track names the spoken language; otherwise the default track is used. Returns the
+    /// track [`find_transcript`](Self::find_transcript) would pick for that language, so
+    /// a manually created one wins over the auto-generated one.
+    pub fn original_language(&self) -> Option<&TranscriptInfo> {
+        let spoken = match self.generated.len() {
+            1 => self.generated.keys().next().cloned(),
+            _ => None,
+        }
+        .or_else(|| self.default_language_code.clone())?;
+        self.find_transcript(&[spoken.as_str()]).ok()
+    }
+
+    /// Any available transcript, for when the language doesn't matter: the first
+    /// manually created track by language code, else the first generated one
+    pub fn find_any_transcript(&self) -> Result<&TranscriptInfo> {
+        fn first(tracks: &HashMap<String, TranscriptInfo>) -> Option<&TranscriptInfo> {
+            tracks
+                .values()
+                .min_by(|a, b| a.language_code.cmp(&b.language_code))
+        }
+        first(&self.manually_created)
+            .or_else(|| first(&self.generated))
+            .ok_or_else(|| TranscriptError::NoTranscriptFound(self.video_id.clone(), vec![]))
+    }
+
     pub fn find_manually_created(&self, language_codes: &[&str]) -> Result<&TranscriptInfo> {
         for lang_code in language_codes {
             if let Some(transcript) = self.manually_created.get(*lang_code) {
@@ -101,9 +373,129 @@ impl TranscriptList {
     }
 }
 
+/// Chapters listed in a video description as lines like `0:00 Intro` or
+/// `Q&A - 1:02:15`
+///
+/// Following YouTube's own rules, the list only counts when it has at least three
+/// entries, starts at 0:00 and keeps increasing; otherwise it is empty.
+///
+/// ```
+/// let description = "Talk slides: https://example.com\n\n0:00 Intro\n2:15 - Setup\n(1:02:03) Q&A\n";
+/// let chapters = ytt::parse_description_chapters(description);
+/// assert_eq!(chapters[1], ("Setup".to_string(), 135.0));
+/// assert_eq!(chapters[2], ("Q&A".to_string(), 3723.0));
+/// ```
+pub fn parse_description_chapters(description: &str) -> Vec<(String, f64)> {
+    use regex::Regex;
+
+    let timestamp =
+        Regex::new(r"\b(?:(\d{1,2}):)?(\d{1,2}):(\d{2})\b").expect("timestamp pattern is valid");
+    let separators: &[char] = &['-', '–', '—', ':', '|', '(', ')', '[', ']', ' ', '\t'];
+
+    let mut chapters: Vec<(String, f64)> = Vec::new();
+    for line in description.lines() {
+        let Some(caps) = timestamp.captures(line) else {
+            continue;
+        };
+        let field = |i: usize| {
+            caps.get(i)
+                .map_or(0.0, |m| m.as_str().parse::<f64>().unwrap_or(0.0))
+        };
+        let start = field(1) * 3600.0 + field(2) * 60.0 + field(3);
+        let matched = caps.get(0).expect("whole match");
+        let title = format!("{} {}", &line[..matched.start()], &line[matched.end()..]);
+        let title = title
+            .trim_matches(separators)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if title.is_empty() {
+            continue;
+        }
+        chapters.push((title, start));
+    }
+
+    let valid = chapters.len() >= 3
+        && chapters[0].1 == 0.0
+        && chapters.windows(2).all(|pair| pair[0].1 < pair[1].1);
+    if valid {
+        chapters
+    } else {
+        Vec::new()
+    }
+}
+
+/// Check whether a string looks like an 11-character YouTube video ID
+fn is_video_id(s: &str) -> bool {
+    s.len() == 11
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Channel IDs are `UC` followed by 22 URL-safe base64 characters
+fn is_channel_id(s: &str) -> bool {
+    s.len() == 24
+        && s.starts_with("UC")
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Canonical page URL for a channel reference: a `/channel/`, `/@handle`, `/c/` or
+/// `/user/` URL, a bare channel ID, or a bare `@handle`
+fn channel_page_url(url_or_id: &str) -> Option<String> {
+    let input = url_or_id.trim();
+    if is_channel_id(input) {
+        return Some(format!("https://www.youtube.com/channel/{}", input));
+    }
+    if input.starts_with('@') && !input.contains('/') {
+        return Some(format!("https://www.youtube.com/{}", input));
+    }
+
+    let url_str = if input.starts_with("http://") || input.starts_with("https://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+    let url = url::Url::parse(&url_str).ok()?;
+    if !url.host_str()?.contains("youtube.com") {
+        return None;
+    }
+
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        [handle, ..] if handle.starts_with('@') => {
+            Some(format!("https://www.youtube.com/{}", handle))
+        }
+        [kind @ ("channel" | "c" | "user"), name, ..] => {
+            Some(format!("https://www.youtube.com/{}/{}", kind, name))
+        }
+        _ => None,
+    }
+}
+
 pub struct YouTubeTranscript {
     client: reqwest::Client,
+    cookie_jar: Arc<reqwest::cookie::Jar>,
     delay_ms: u64,
+    jitter_percent: u8,
+    retries: u32,
+    /// Retries left for the client's whole lifetime, when capped
+    retry_budget: Option<std::sync::atomic::AtomicU32>,
+    po_token: Option<String>,
+    visitor_data: Option<String>,
+    keep_empty_cues: bool,
+    any_language_fallback: bool,
+    /// Saved timedtext bodies, revalidated instead of downloaded again when set
+    cache: Option<cache::BodyCache>,
+    /// Watch pages already read by `get_video_publish_date`, used up by the next
+    /// `list_transcripts` of the same video
+    watch_pages: std::sync::Mutex<HashMap<String, WatchPage>>,
+}
+
+/// The parts of a watch page `list_transcripts` uses
+struct WatchPage {
+    api_key: String,
+    chapters: Vec<(String, f64)>,
 }
 
 impl Default for YouTubeTranscript {
@@ -117,44 +509,145 @@ impl YouTubeTranscript {
         Self::with_delay(500) // Default 500ms delay
     }
 
+    /// Start configuring a client with [`YouTubeTranscriptBuilder`]
+    pub fn builder() -> YouTubeTranscriptBuilder {
+        YouTubeTranscriptBuilder::new()
+    }
+
+    /// Use a caller-provided client. Cookies loaded with
+    /// [`load_cookies`](Self::load_cookies) and the automatic consent cookie are not
+    /// visible to this client unless it was built with its own cookie store.
     pub fn with_client(client: reqwest::Client) -> Self {
-        Self {
-            client,
-            delay_ms: 500,
-        }
+        Self::builder()
+            .client(client)
+            .build()
+            .expect("Failed to create HTTP client")
     }
 
     pub fn with_delay(delay_ms: u64) -> Self {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::ACCEPT_LANGUAGE,
-            reqwest::header::HeaderValue::from_static("en-US"),
-        );
+        Self::builder()
+            .delay(delay_ms)
+            .build()
+            .expect("Failed to create HTTP client")
+    }
 
-        Self {
-            client: reqwest::Client::builder()
-                .cookie_store(true)
-                .default_headers(headers)
-                .build()
-                .expect("Failed to create HTTP client"),
-            delay_ms,
+    /// Create a client that sends the given `User-Agent` and `Accept-Language` headers
+    ///
+    /// `accept_language` is typically the requested transcript language (e.g. `es`),
+    /// which makes the watch page and track names come back in that language.
+    pub fn with_user_agent(delay_ms: u64, user_agent: &str, accept_language: &str) -> Result<Self> {
+        Self::builder()
+            .delay(delay_ms)
+            .user_agent(user_agent)
+            .accept_language(accept_language)
+            .build()
+    }
+
+    /// Load cookies from a Netscape-format `cookies.txt` file into the client's cookie store
+    ///
+    /// Cookies from a signed-in, consented browser session let the client fetch
+    /// age-restricted videos and skip the EU consent interstitial.
+    pub fn load_cookies(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            TranscriptError::IoError(format!(
+                "Failed to read cookie file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        for cookie in cookies::parse_netscape_cookies(&content)? {
+            self.cookie_jar.add_cookie_str(&cookie.header, &cookie.url);
         }
+
+        Ok(())
     }
 
     async fn delay(&self) {
-        tokio::time::sleep(tokio::time::Duration::from_millis(self.delay_ms)).await;
+        use std::hash::{BuildHasher, Hasher};
+
+        // Each RandomState is keyed differently, which is randomness enough for a delay
+        let random = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        let delay_ms = jittered_delay_ms(self.delay_ms, self.jitter_percent, random);
+        tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+    }
+
+    /// Send a request, retrying connection errors, timeouts, 429 and 5xx responses
+    /// up to `retries` times with exponential backoff
+    async fn send(&self, request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let current = match request.try_clone() {
+                Some(current) => current,
+                None => return request.send().await,
+            };
+            if log::log_enabled!(log::Level::Debug) {
+                if let Some(request) = current.try_clone().and_then(|r| r.build().ok()) {
+                    log::debug!("{} {}", request.method(), request.url());
+                }
+            }
+            let result = current.send().await;
+            let retryable = match &result {
+                Ok(response) => response.status() == 429 || response.status().is_server_error(),
+                Err(e) => e.is_connect() || e.is_timeout(),
+            };
+            if !retryable || attempt >= self.retries || !self.take_retry() {
+                return result;
+            }
+            attempt += 1;
+            let backoff = self.delay_ms.max(500) * 2u64.pow(attempt - 1);
+            match &result {
+                Ok(response) => log::debug!(
+                    "{} returned {}, retry {}/{} in {}ms",
+                    response.url(),
+                    response.status(),
+                    attempt,
+                    self.retries,
+                    backoff
+                ),
+                Err(e) => log::debug!(
+                    "Request failed ({}), retry {}/{} in {}ms",
+                    e,
+                    attempt,
+                    self.retries,
+                    backoff
+                ),
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(backoff)).await;
+        }
+    }
+
+    /// Use up one retry from the budget, if there is one; false once it has run out
+    fn take_retry(&self) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let Some(budget) = &self.retry_budget else {
+            return true;
+        };
+        let taken = budget
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+        if !taken {
+            log::debug!("Retry budget used up, not retrying");
+        }
+        taken
     }
 
     /// Extract video ID from YouTube URL
+    ///
+    /// Accepts bare IDs, `watch?v=` URLs (with any extra query params),
+    /// `youtu.be/ID`, and `/shorts/`, `/live/`, `/embed/`, `/v/` paths on any
+    /// youtube.com host (including `m.` and `music.`).
     pub fn extract_video_id(url_or_id: &str) -> Result<String> {
         let input = url_or_id.trim();
-        
+
         // Check if it's already a video ID (11 characters)
-        if input.len() == 11
-            && input
-                .chars()
-                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-        {
+        if is_video_id(input) {
             return Ok(input.to_string());
         }
 
@@ -178,52 +671,38 @@ impl YouTubeTranscript {
             }
         };
 
-        if url
-            .host_str()
-            .map(|h| h.contains("youtube.com") || h.contains("youtu.be"))
-            .unwrap_or(false)
-        {
-            // Standard watch URL: ?v=VIDEO_ID
+        let host = url.host_str().unwrap_or("");
+        if host.contains("youtube.com") || host.contains("youtu.be") {
+            // Standard watch URL: ?v=VIDEO_ID (other params such as t, si, list are ignored)
             if let Some(video_id) = url
                 .query_pairs()
                 .find(|(k, _)| k == "v")
                 .map(|(_, v)| v.to_string())
             {
-                // Validate extracted ID is 11 characters
-                if video_id.len() == 11
-                    && video_id
-                        .chars()
-                        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-                {
+                if is_video_id(&video_id) {
                     return Ok(video_id);
                 }
             }
-            // Short URL: youtu.be/VIDEO_ID
-            if url.host_str().map(|h| h == "youtu.be").unwrap_or(false) {
-                if let Some(video_id) = url.path_segments().and_then(|mut s| s.next()) {
-                    // Validate extracted ID is 11 characters
-                    if video_id.len() == 11
-                        && video_id
-                            .chars()
-                            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-                    {
-                        return Ok(video_id.to_string());
-                    }
-                }
-            }
-            // Embed URL: youtube.com/embed/VIDEO_ID
+
             if let Some(segments) = url.path_segments() {
-                let segments: Vec<&str> = segments.collect();
-                if segments.len() >= 2 && segments[0] == "embed" {
-                    let video_id = segments[1];
-                    if video_id.len() == 11
-                        && video_id
-                            .chars()
-                            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
-                    {
-                        return Ok(video_id.to_string());
+                let segments: Vec<&str> = segments.filter(|s| !s.is_empty()).collect();
+
+                // Short URL: youtu.be/VIDEO_ID
+                if host == "youtu.be" {
+                    if let Some(video_id) = segments.first() {
+                        if is_video_id(video_id) {
+                            return Ok(video_id.to_string());
+                        }
                     }
                 }
+
+                // Path URLs: youtube.com/{shorts,live,embed,v}/VIDEO_ID
+                if segments.len() >= 2
+                    && matches!(segments[0], "shorts" | "live" | "embed" | "v")
+                    && is_video_id(segments[1])
+                {
+                    return Ok(segments[1].to_string());
+                }
             }
         }
 
@@ -233,10 +712,52 @@ impl YouTubeTranscript {
         )))
     }
 
+    /// Extract the start offset (in seconds) from a URL's `t=` or `start=` parameter
+    ///
+    /// Understands plain seconds (`t=120`, `t=120s`) and the `1h2m3s` form.
+    pub fn extract_start_time(url: &str) -> Option<f64> {
+        let input = url.trim();
+        let url_str = if input.starts_with("http://") || input.starts_with("https://") {
+            input.to_string()
+        } else {
+            format!("https://{}", input)
+        };
+        let url = url::Url::parse(&url_str).ok()?;
+        let value = url
+            .query_pairs()
+            .find(|(k, _)| k == "t" || k == "start")
+            .map(|(_, v)| v.to_string())?;
+
+        if let Ok(seconds) = value.trim_end_matches('s').parse::<f64>() {
+            return Some(seconds);
+        }
+
+        let mut total = 0.0;
+        let mut number = String::new();
+        for c in value.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let n: f64 = number.parse().ok()?;
+            number.clear();
+            total += match c {
+                'h' => n * 3600.0,
+                'm' => n * 60.0,
+                's' => n,
+                _ => return None,
+            };
+        }
+        if !number.is_empty() {
+            return None;
+        }
+        Some(total)
+    }
+
     /// Extract playlist ID from YouTube playlist URL
     pub fn extract_playlist_id(url_or_id: &str) -> Result<String> {
         let input = url_or_id.trim();
-        
+
         // Try parsing as URL (with or without protocol)
         let url_str = if input.starts_with("http://") || input.starts_with("https://") {
             input.to_string()
@@ -277,38 +798,91 @@ impl YouTubeTranscript {
         )))
     }
 
+    /// Extract a channel ID (`UC...`) from a `/channel/UC...` URL or a bare channel ID
+    ///
+    /// `@handle`, `/c/` and `/user/` URLs don't contain the ID; resolve those with
+    /// [`get_channel_uploads_playlist_id`](Self::get_channel_uploads_playlist_id).
+    pub fn extract_channel_id(url_or_id: &str) -> Result<String> {
+        let channel_id = channel_page_url(url_or_id)
+            .and_then(|page| {
+                page.strip_prefix("https://www.youtube.com/channel/")
+                    .map(str::to_string)
+            })
+            .filter(|id| is_channel_id(id));
+
+        channel_id.ok_or_else(|| {
+            TranscriptError::InvalidVideoId(format!(
+                "{} (Could not extract channel ID from URL)",
+                url_or_id
+            ))
+        })
+    }
+
+    /// Whether the input names a channel (`/channel/`, `/@handle`, `/c/` or `/user/`
+    /// URL, bare channel ID or bare `@handle`) rather than a video or playlist
+    pub fn is_channel_url(url_or_id: &str) -> bool {
+        channel_page_url(url_or_id).is_some()
+    }
+
+    /// ID of the playlist YouTube keeps of every upload to a channel
+    pub fn uploads_playlist_id(channel_id: &str) -> String {
+        format!("UU{}", channel_id.strip_prefix("UC").unwrap_or(channel_id))
+    }
+
+    /// Resolve a channel URL to its uploads playlist ID, for
+    /// [`get_playlist_video_ids`](Self::get_playlist_video_ids)
+    ///
+    /// Handles and custom names are looked up by fetching the channel page.
+    pub async fn get_channel_uploads_playlist_id(&self, url_or_id: &str) -> Result<String> {
+        if let Ok(channel_id) = Self::extract_channel_id(url_or_id) {
+            return Ok(Self::uploads_playlist_id(&channel_id));
+        }
+
+        let page_url = channel_page_url(url_or_id).ok_or_else(|| {
+            TranscriptError::InvalidVideoId(format!("{} (Not a YouTube channel URL)", url_or_id))
+        })?;
+
+        self.delay().await;
+
+        let response = self.send(self.client.get(&page_url)).await?;
+
+        self.check_http_errors(&response, url_or_id)?;
+
+        let html = response.text().await?;
+
+        let channel_id = Self::extract_channel_id_from_html(&html).ok_or_else(|| {
+            TranscriptError::YouTubeDataUnparsable(format!(
+                "No channel ID found on channel page: {}",
+                url_or_id
+            ))
+        })?;
+        Ok(Self::uploads_playlist_id(&channel_id))
+    }
+
     /// Fetch all video IDs from a playlist
     pub async fn get_playlist_video_ids(&self, playlist_id: &str) -> Result<Vec<String>> {
         use regex::Regex;
-        
+
         let url = PLAYLIST_URL.replace("{playlist_id}", playlist_id);
-        
+
         // Add delay before request
         self.delay().await;
-        
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| TranscriptError::HttpError(format!("Failed to fetch playlist: {}", e)))?;
+
+        let response = self.send(self.client.get(&url)).await?;
 
         self.check_http_errors(&response, playlist_id)?;
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| TranscriptError::HttpError(format!("Failed to read playlist HTML: {}", e)))?;
+        let html = response.text().await?;
 
         // Extract video IDs from the playlist page
         // YouTube stores video IDs in various places in the HTML
         // We'll look for the pattern "/watch?v=VIDEO_ID" or "videoId":"VIDEO_ID"
         let mut video_ids = Vec::new();
-        
+
         // Pattern 1: "videoId":"VIDEO_ID"
         let re1 = Regex::new(r#""videoId":"([a-zA-Z0-9_-]{11})""#)
             .map_err(|_| TranscriptError::YouTubeDataUnparsable(playlist_id.to_string()))?;
-        
+
         for cap in re1.captures_iter(&html) {
             if let Some(video_id) = cap.get(1) {
                 let id = video_id.as_str().to_string();
@@ -317,12 +891,12 @@ impl YouTubeTranscript {
                 }
             }
         }
-        
+
         // Pattern 2: /watch?v=VIDEO_ID (as fallback)
         if video_ids.is_empty() {
             let re2 = Regex::new(r#"/watch\?v=([a-zA-Z0-9_-]{11})"#)
                 .map_err(|_| TranscriptError::YouTubeDataUnparsable(playlist_id.to_string()))?;
-            
+
             for cap in re2.captures_iter(&html) {
                 if let Some(video_id) = cap.get(1) {
                     let id = video_id.as_str().to_string();
@@ -334,9 +908,10 @@ impl YouTubeTranscript {
         }
 
         if video_ids.is_empty() {
-            return Err(TranscriptError::YouTubeDataUnparsable(
-                format!("No videos found in playlist: {}", playlist_id)
-            ));
+            return Err(TranscriptError::YouTubeDataUnparsable(format!(
+                "No videos found in playlist: {}",
+                playlist_id
+            )));
         }
 
         Ok(video_ids)
@@ -352,33 +927,96 @@ impl YouTubeTranscript {
         self.extract_video_title(video_id, &innertube_data)
     }
 
+    /// Get the date a video was published, as `YYYY-MM-DD`
+    ///
+    /// Returns `Ok(None)` when the watch page doesn't state it. What the transcript
+    /// calls need from the page is kept, so listing or fetching this video's
+    /// transcripts next doesn't download the page again.
+    pub async fn get_video_publish_date(&self, video_id: &str) -> Result<Option<String>> {
+        let html = self.fetch_video_html(video_id).await?;
+        if let Ok(page) = self.parse_watch_page(&html, video_id) {
+            self.watch_pages
+                .lock()
+                .unwrap()
+                .insert(video_id.to_string(), page);
+        }
+        Ok(Self::extract_publish_date(&html))
+    }
+
     /// List all available transcripts for a video
     pub async fn list_transcripts(&self, video_id: &str) -> Result<TranscriptList> {
-        let html = self.fetch_video_html(video_id).await?;
+        let saved = self.watch_pages.lock().unwrap().remove(video_id);
+        let page = match saved {
+            Some(page) => page,
+            None => {
+                let html = self.fetch_video_html(video_id).await?;
+                self.parse_watch_page(&html, video_id)?
+            }
+        };
         // Delay between HTML fetch and API call to avoid rate limiting
         self.delay().await;
-        let api_key = self.extract_innertube_api_key(&html, video_id)?;
-        let innertube_data = self.fetch_innertube_data(video_id, &api_key).await?;
-        self.extract_captions_json(video_id, &innertube_data)
+        let innertube_data = self.fetch_innertube_data(video_id, &page.api_key).await?;
+        let mut transcript_list = self.extract_captions_json(video_id, &innertube_data)?;
+        transcript_list.chapters = if page.chapters.is_empty() {
+            Self::extract_description_chapters(&innertube_data)
+        } else {
+            page.chapters
+        };
+        Ok(transcript_list)
     }
 
     /// Fetch transcript for a specific language
+    ///
+    /// With `languages` set to `None`, the video's own default caption language is
+    /// used (see [`TranscriptList::find_default_transcript`]) rather than English.
     pub async fn fetch_transcript(
         &self,
         video_id: &str,
         languages: Option<Vec<&str>>,
     ) -> Result<TranscriptResponse> {
+        let (_, transcript) = self.fetch_transcript_with_list(video_id, languages).await?;
+        Ok(transcript)
+    }
+
+    /// Like [`fetch_transcript`](Self::fetch_transcript), but also return the track list
+    /// the transcript was picked from
+    ///
+    /// Calling [`list_transcripts`](Self::list_transcripts) and then `fetch_transcript`
+    /// loads the watch page and player data twice; this loads them once.
+    ///
+    /// ```no_run
+    /// # async fn example(api: &ytt::YouTubeTranscript) -> ytt::Result<()> {
+    /// let (list, transcript) = api.fetch_transcript_with_list("dQw4w9WgXcQ", Some(vec!["en"])).await?;
+    /// println!("{} tracks, fetched {}", list.manually_created.len() + list.generated.len(), transcript.language_code);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn fetch_transcript_with_list(
+        &self,
+        video_id: &str,
+        languages: Option<Vec<&str>>,
+    ) -> Result<(TranscriptList, TranscriptResponse)> {
         let transcript_list = self.list_transcripts(video_id).await?;
 
-        let languages = languages.unwrap_or_else(|| vec!["en"]);
         let title = transcript_list.title.clone();
-        let transcript_info = transcript_list.find_transcript(&languages)?;
+        let transcript_info = self.select_transcript(&transcript_list, languages.as_deref())?;
 
-        self.fetch_transcript_data(video_id, transcript_info, None, title)
-            .await
+        let mut transcript = self
+            .fetch_transcript_data(video_id, transcript_info, None, title)
+            .await?;
+        transcript.chapters = transcript_list.chapters.clone();
+        Ok((transcript_list, transcript))
     }
 
     /// Translate a transcript to another language
+    ///
+    /// Uses YouTube's own machine translation of the source track, which comes back cue
+    /// by cue, so subtitle output stays in sync. The source track is downloaded as well:
+    /// when the translation has as many cues as the source, each translated cue takes
+    /// the `start` and `duration` of the source cue it translates. Otherwise YouTube
+    /// merged or split some cues, and the timing YouTube sent with the translation is
+    /// kept. The source is the first of `source_languages` that can be translated into
+    /// `target_language` (see [`TranscriptList::find_translatable_transcript`]).
     pub async fn translate_transcript(
         &self,
         video_id: &str,
@@ -386,65 +1024,233 @@ impl YouTubeTranscript {
         target_language: &str,
     ) -> Result<TranscriptResponse> {
         let transcript_list = self.list_transcripts(video_id).await?;
-        let title = transcript_list.title.clone();
-        let source_transcript = transcript_list.find_transcript(source_languages)?;
-
-        if !source_transcript.is_translatable {
-            return Err(TranscriptError::NotTranslatable(video_id.to_string()));
-        }
-
-        let translation_exists = source_transcript
-            .translation_languages
-            .iter()
-            .any(|t| t.language_code == target_language);
+        let (_, transcript) = self
+            .translate_with_list(&transcript_list, source_languages, target_language)
+            .await?;
+        Ok(transcript)
+    }
 
-        if !translation_exists {
-            return Err(TranscriptError::TranslationLanguageNotAvailable(
-                target_language.to_string(),
-            ));
+    /// Like [`translate_transcript`](Self::translate_transcript), for a track list
+    /// that is already loaded, and also returning the source transcript the
+    /// translation was timed against
+    ///
+    /// ```no_run
+    /// # async fn example(api: &ytt::YouTubeTranscript) -> ytt::Result<()> {
+    /// let list = api.list_transcripts("dQw4w9WgXcQ").await?;
+    /// let (english, spanish) = api.translate_with_list(&list, &["en"], "es").await?;
+    /// println!("{} -> {}", english.transcript[0].text, spanish.transcript[0].text);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn translate_with_list(
+        &self,
+        transcript_list: &TranscriptList,
+        source_languages: &[&str],
+        target_language: &str,
+    ) -> Result<(TranscriptResponse, TranscriptResponse)> {
+        let title = transcript_list.title.clone();
+        let source_transcript =
+            transcript_list.find_translatable_transcript(source_languages, target_language)?;
+
+        let mut source = self
+            .fetch_transcript_data(
+                &transcript_list.video_id,
+                source_transcript,
+                None,
+                title.clone(),
+            )
+            .await?;
+        source.chapters = transcript_list.chapters.clone();
+        let mut transcript = self
+            .fetch_transcript_data(
+                &transcript_list.video_id,
+                source_transcript,
+                Some(target_language),
+                title,
+            )
+            .await?;
+        if !align_to_source(&mut transcript.transcript, &source.transcript) {
+            log::debug!(
+                "{} translation has {} cues against {} in the source, keeping YouTube's timing",
+                target_language,
+                transcript.transcript.len(),
+                source.transcript.len()
+            );
         }
+        transcript.chapters = transcript_list.chapters.clone();
+        Ok((source, transcript))
+    }
 
-        self.fetch_transcript_data(video_id, source_transcript, Some(target_language), title)
+    /// Fetch the timedtext body YouTube sends for a transcript, unparsed
+    ///
+    /// The track is chosen like [`fetch_transcript`](Self::fetch_transcript) does. The
+    /// body is usually XML but can be json3; this is meant for diagnosing transcripts
+    /// that parse empty or wrong.
+    pub async fn fetch_raw_xml(
+        &self,
+        video_id: &str,
+        languages: Option<Vec<&str>>,
+    ) -> Result<String> {
+        let transcript_list = self.list_transcripts(video_id).await?;
+        let transcript_info = self.select_transcript(&transcript_list, languages.as_deref())?;
+        self.fetch_transcript_body(video_id, transcript_info, None)
             .await
     }
 
-    async fn fetch_video_html(&self, video_id: &str) -> Result<String> {
-        // Add initial delay to avoid rate limiting
-        self.delay().await;
-
-        let url = WATCH_URL.replace("{video_id}", video_id);
-        let mut response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| TranscriptError::HttpError(format!("Failed to fetch HTML: {}", e)))?;
+    /// Fetch a transcript with per-word timing
+    ///
+    /// Requests the srv3 timedtext format, whose `<s>` elements carry the start of each
+    /// word. Auto-generated tracks usually have it; manual tracks often have only
+    /// whole cues, in which case every `words` list is empty.
+    pub async fn fetch_transcript_words(
+        &self,
+        video_id: &str,
+        languages: Option<Vec<&str>>,
+    ) -> Result<WordTimedTranscript> {
+        let transcript_list = self.list_transcripts(video_id).await?;
 
-        self.check_http_errors(&response, video_id)?;
+        let transcript_info = self.select_transcript(&transcript_list, languages.as_deref())?;
+        let srv3_info = TranscriptInfo {
+            base_url: format!("{}&fmt=srv3", transcript_info.base_url),
+            ..transcript_info.clone()
+        };
+        let xml_content = self
+            .fetch_transcript_body(video_id, &srv3_info, None)
+            .await?;
 
-        let html = response
-            .text()
-            .await
-            .map_err(|e| TranscriptError::HttpError(format!("Failed to read HTML: {}", e)))?;
+        let transcript = self
+            .parser()
+            .parse_words(&xml_content)
+            .map_err(|e| TranscriptError::XmlParseError(format!("Failed to parse XML: {}", e)))?;
+        if transcript.is_empty() {
+            return Err(TranscriptError::EmptyTranscript(video_id.to_string()));
+        }
 
-        // Handle consent cookie if needed
-        if html.contains("action=\"https://consent.youtube.com/s\"") {
-            self.create_consent_cookie(&html, video_id)?;
-            // Add delay before retry
-            self.delay().await;
-            // Retry request
-            response = self.client.get(&url).send().await.map_err(|e| {
-                TranscriptError::HttpError(format!("Failed to fetch HTML after consent: {}", e))
-            })?;
+        Ok(WordTimedTranscript {
+            video_id: video_id.to_string(),
+            title: transcript_list.title.clone(),
+            language: transcript_info.language.clone(),
+            language_code: transcript_info.language_code.clone(),
+            is_generated: transcript_info.is_generated,
+            transcript,
+        })
+    }
 
-            self.check_http_errors(&response, video_id)?;
+    /// Fetch a transcript with the per-cue metadata the srv3 format carries
+    ///
+    /// The track is chosen like [`fetch_transcript`](Self::fetch_transcript) does. Every
+    /// cue gets its `kind`; `confidence` is only set where the track has recognizer
+    /// scores, which in practice means auto-generated tracks.
+    pub async fn fetch_transcript_verbose(
+        &self,
+        video_id: &str,
+        languages: Option<Vec<&str>>,
+    ) -> Result<VerboseTranscript> {
+        let transcript_list = self.list_transcripts(video_id).await?;
 
-            let html = response
-                .text()
-                .await
-                .map_err(|e| TranscriptError::HttpError(format!("Failed to read HTML: {}", e)))?;
+        let transcript_info = self.select_transcript(&transcript_list, languages.as_deref())?;
+        let srv3_info = TranscriptInfo {
+            base_url: format!("{}&fmt=srv3", transcript_info.base_url),
+            ..transcript_info.clone()
+        };
+        let xml_content = self
+            .fetch_transcript_body(video_id, &srv3_info, None)
+            .await?;
 
-            if html.contains("action=\"https://consent.youtube.com/s\"") {
+        let mut transcript = self
+            .parser()
+            .parse_verbose(&xml_content)
+            .map_err(|e| TranscriptError::XmlParseError(format!("Failed to parse XML: {}", e)))?;
+        if transcript.is_empty() {
+            return Err(TranscriptError::EmptyTranscript(video_id.to_string()));
+        }
+        let kind = if transcript_info.is_generated {
+            "asr"
+        } else {
+            "manual"
+        };
+        for item in &mut transcript {
+            item.kind = Some(kind.to_string());
+        }
+
+        Ok(VerboseTranscript {
+            video_id: video_id.to_string(),
+            title: transcript_list.title.clone(),
+            language: transcript_info.language.clone(),
+            language_code: transcript_info.language_code.clone(),
+            is_generated: transcript_info.is_generated,
+            transcript,
+        })
+    }
+
+    /// Stream the cues of a transcript as they are parsed
+    ///
+    /// Resolves the transcript like [`fetch_transcript`](Self::fetch_transcript), then
+    /// parses the timedtext body on a blocking task and sends each cue through the
+    /// returned channel. A parse failure, or a transcript with no cues, is delivered
+    /// as the final `Err` item.
+    pub async fn stream_transcript(
+        &self,
+        video_id: &str,
+        languages: Option<Vec<&str>>,
+    ) -> Result<tokio::sync::mpsc::Receiver<Result<TranscriptItem>>> {
+        let transcript_list = self.list_transcripts(video_id).await?;
+
+        let transcript_info = self.select_transcript(&transcript_list, languages.as_deref())?;
+        let xml_content = self
+            .fetch_transcript_body(video_id, transcript_info, None)
+            .await?;
+
+        let video_id = video_id.to_string();
+        let parser = self.parser();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        tokio::task::spawn_blocking(move || {
+            let mut sent_any = false;
+            let result = parser.parse_with(&xml_content, |item| {
+                sent_any = true;
+                tx.blocking_send(Ok(item)).is_ok()
+            });
+            if let Err(e) = result {
+                let _ = tx.blocking_send(Err(TranscriptError::XmlParseError(format!(
+                    "Failed to parse XML: {}",
+                    e
+                ))));
+            } else if !sent_any {
+                let _ = tx.blocking_send(Err(TranscriptError::EmptyTranscript(video_id)));
+            }
+        });
+
+        Ok(rx)
+    }
+
+    async fn fetch_video_html(&self, video_id: &str) -> Result<String> {
+        // Add initial delay to avoid rate limiting
+        self.delay().await;
+
+        let url = WATCH_URL.replace("{video_id}", video_id);
+        let mut response = self.send(self.client.get(&url)).await?;
+
+        self.check_http_errors(&response, video_id)?;
+
+        let html = response.text().await?;
+
+        // Handle consent cookie if needed
+        if html.contains("action=\"https://consent.youtube.com/s\"") {
+            log::debug!(
+                "Consent page served for {}, setting CONSENT cookie",
+                video_id
+            );
+            self.create_consent_cookie(&html, video_id)?;
+            // Add delay before retry
+            self.delay().await;
+            // Retry request
+            response = self.send(self.client.get(&url)).await?;
+
+            self.check_http_errors(&response, video_id)?;
+
+            let html = response.text().await?;
+
+            if html.contains("action=\"https://consent.youtube.com/s\"") {
                 return Err(TranscriptError::FailedToCreateConsentCookie(
                     video_id.to_string(),
                 ));
@@ -483,7 +1289,7 @@ impl YouTubeTranscript {
     ) -> Result<serde_json::Value> {
         let url = INNERTUBE_API_URL.replace("{api_key}", api_key);
 
-        let context = serde_json::json!({
+        let mut context = serde_json::json!({
             "context": {
                 "client": {
                     "clientName": "ANDROID",
@@ -493,18 +1299,16 @@ impl YouTubeTranscript {
             "videoId": video_id
         });
 
+        let mut request = self.client.post(&url);
+        if let Some(visitor_data) = &self.visitor_data {
+            context["context"]["client"]["visitorData"] = serde_json::json!(visitor_data);
+            request = request.header("X-Goog-Visitor-Id", visitor_data);
+        }
+
         // Add delay before API request to avoid rate limiting
         self.delay().await;
 
-        let response = self
-            .client
-            .post(&url)
-            .json(&context)
-            .send()
-            .await
-            .map_err(|e| {
-                TranscriptError::HttpError(format!("Failed to fetch InnerTube data: {}", e))
-            })?;
+        let response = self.send(request.json(&context)).await?;
 
         self.check_http_errors(&response, video_id)?;
 
@@ -515,6 +1319,88 @@ impl YouTubeTranscript {
         Ok(data)
     }
 
+    fn extract_publish_date(html: &str) -> Option<String> {
+        use regex::Regex;
+
+        // The player microformat carries "publishDate"; older pages only have the meta tag
+        let patterns = [
+            r#""publishDate":"(\d{4}-\d{2}-\d{2})"#,
+            r#"itemprop="datePublished" content="(\d{4}-\d{2}-\d{2})"#,
+        ];
+        patterns.iter().find_map(|pattern| {
+            Regex::new(pattern)
+                .ok()?
+                .captures(html)
+                .map(|caps| caps[1].to_string())
+        })
+    }
+
+    /// Chapter markers from the watch page, else chapters listed in the description
+    fn parse_watch_page(&self, html: &str, video_id: &str) -> Result<WatchPage> {
+        Ok(WatchPage {
+            api_key: self.extract_innertube_api_key(html, video_id)?,
+            chapters: Self::extract_page_chapters(html),
+        })
+    }
+
+    /// Chapter markers from the watch page's own chapter list
+    fn extract_page_chapters(html: &str) -> Vec<(String, f64)> {
+        use regex::Regex;
+
+        // The page lists the markers more than once; the first run of increasing start
+        // times is one complete copy
+        let marker = Regex::new(
+            r#""chapterRenderer":\{"title":\{"simpleText":("(?:[^"\\]|\\.)*")\},"timeRangeStartMillis":(\d+)"#,
+        )
+        .expect("chapter pattern is valid");
+        let mut chapters: Vec<(String, f64)> = Vec::new();
+        for caps in marker.captures_iter(html) {
+            let (Ok(title), Ok(millis)) = (
+                serde_json::from_str::<String>(&caps[1]),
+                caps[2].parse::<f64>(),
+            ) else {
+                continue;
+            };
+            let start = millis / 1000.0;
+            if chapters
+                .last()
+                .is_some_and(|(_, previous)| start <= *previous)
+            {
+                break;
+            }
+            chapters.push((title, start));
+        }
+        chapters
+    }
+
+    /// Chapters from the timestamps in a video's description, for videos without markers
+    fn extract_description_chapters(innertube_data: &serde_json::Value) -> Vec<(String, f64)> {
+        innertube_data
+            .get("videoDetails")
+            .and_then(|details| details.get("shortDescription"))
+            .and_then(|description| description.as_str())
+            .map(parse_description_chapters)
+            .unwrap_or_default()
+    }
+
+    fn extract_channel_id_from_html(html: &str) -> Option<String> {
+        use regex::Regex;
+
+        // The canonical link and the channel metadata both name the page's own channel;
+        // other "channelId" fields on the page can belong to featured channels
+        let patterns = [
+            r#"<link rel="canonical" href="https://www\.youtube\.com/channel/(UC[A-Za-z0-9_-]{22})""#,
+            r#""externalId":"(UC[A-Za-z0-9_-]{22})""#,
+            r#"itemprop="identifier" content="(UC[A-Za-z0-9_-]{22})""#,
+        ];
+        patterns.iter().find_map(|pattern| {
+            Regex::new(pattern)
+                .ok()?
+                .captures(html)
+                .map(|caps| caps[1].to_string())
+        })
+    }
+
     fn extract_video_title(
         &self,
         video_id: &str,
@@ -543,15 +1429,13 @@ impl YouTubeTranscript {
         // Check playability status
         self.assert_playability(video_id, innertube_data)?;
 
+        // With captions turned off by the uploader, YouTube omits the tracklist
+        // renderer entirely; when it is present, captions are enabled even if no
+        // track has been published yet
         let captions_json = innertube_data
             .get("captions")
-            .and_then(|c| c.get("playerCaptionsTracklistRenderer"));
-
-        if captions_json.is_none() {
-            return Err(TranscriptError::TranscriptsDisabled(video_id.to_string()));
-        }
-
-        let captions_json = captions_json.unwrap();
+            .and_then(|c| c.get("playerCaptionsTracklistRenderer"))
+            .ok_or_else(|| TranscriptError::TranscriptsDisabled(video_id.to_string()))?;
 
         // Extract translation languages
         let translation_languages: Vec<TranslationLanguage> = captions_json
@@ -580,11 +1464,14 @@ impl YouTubeTranscript {
 
         let mut manually_created = HashMap::new();
         let mut generated = HashMap::new();
+        let mut first_manual = None;
+        let mut first_generated = None;
 
-        if let Some(caption_tracks) = captions_json
+        let caption_tracks = captions_json
             .get("captionTracks")
-            .and_then(|ct| ct.as_array())
-        {
+            .and_then(|ct| ct.as_array());
+
+        if let Some(caption_tracks) = caption_tracks {
             for caption in caption_tracks {
                 let language_code = match caption.get("languageCode").and_then(|l| l.as_str()) {
                     Some(lc) => lc.to_string(),
@@ -624,6 +1511,7 @@ impl YouTubeTranscript {
                 };
 
                 let transcript_info = TranscriptInfo {
+                    video_id: video_id.to_string(),
                     language_code: language_code.clone(),
                     language,
                     is_generated,
@@ -633,15 +1521,41 @@ impl YouTubeTranscript {
                 };
 
                 if is_generated {
+                    first_generated.get_or_insert_with(|| language_code.clone());
                     generated.insert(language_code, transcript_info);
                 } else {
+                    first_manual.get_or_insert_with(|| language_code.clone());
                     manually_created.insert(language_code, transcript_info);
                 }
             }
         }
 
+        // The default audio track names the caption track YouTube itself shows first
+        let default_track_language = captions_json.get("audioTracks").and_then(|tracks| {
+            let audio_index = captions_json
+                .get("defaultAudioTrackIndex")
+                .and_then(|i| i.as_u64())
+                .unwrap_or(0) as usize;
+            let caption_index = tracks
+                .get(audio_index)?
+                .get("defaultCaptionTrackIndex")?
+                .as_u64()? as usize;
+            caption_tracks?
+                .get(caption_index)?
+                .get("languageCode")?
+                .as_str()
+                .map(|s| s.to_string())
+        });
+        let default_language_code = default_track_language
+            .filter(|code| manually_created.contains_key(code) || generated.contains_key(code))
+            .or(first_manual)
+            .or(first_generated);
+
         if manually_created.is_empty() && generated.is_empty() {
-            return Err(TranscriptError::TranscriptsDisabled(video_id.to_string()));
+            return Err(TranscriptError::NoTranscriptFound(
+                video_id.to_string(),
+                Vec::new(),
+            ));
         }
 
         // Extract video title
@@ -657,6 +1571,8 @@ impl YouTubeTranscript {
             manually_created,
             generated,
             translation_languages,
+            default_language_code,
+            chapters: Vec::new(),
         })
     }
 
@@ -689,13 +1605,11 @@ impl YouTubeTranscript {
                     return Err(TranscriptError::AgeRestricted(video_id.to_string()));
                 }
             }
-            "ERROR" => {
-                if reason.contains("unavailable") {
-                    if video_id.starts_with("http://") || video_id.starts_with("https://") {
-                        return Err(TranscriptError::InvalidVideoId(video_id.to_string()));
-                    }
-                    return Err(TranscriptError::VideoUnavailable(video_id.to_string()));
+            "ERROR" if reason.contains("unavailable") => {
+                if video_id.starts_with("http://") || video_id.starts_with("https://") {
+                    return Err(TranscriptError::InvalidVideoId(video_id.to_string()));
                 }
+                return Err(TranscriptError::VideoUnavailable(video_id.to_string()));
             }
             _ => {}
         }
@@ -713,10 +1627,14 @@ impl YouTubeTranscript {
 
         if let Some(captures) = re.captures(html) {
             if let Some(value) = captures.get(1) {
-                let _cookie_value = format!("YES+{}", value.as_str());
-                // Note: reqwest handles cookies automatically via cookie_store
-                // We would need to manually set cookies if needed, but for now
-                // the retry after detecting consent should work
+                let cookie = format!(
+                    "CONSENT=YES+{}; Domain=.youtube.com; Path=/",
+                    value.as_str()
+                );
+                let url = url::Url::parse("https://www.youtube.com/").map_err(|_| {
+                    TranscriptError::FailedToCreateConsentCookie(video_id.to_string())
+                })?;
+                self.cookie_jar.add_cookie_str(&cookie, &url);
                 return Ok(());
             }
         }
@@ -726,60 +1644,192 @@ impl YouTubeTranscript {
         ))
     }
 
-    fn check_http_errors(&self, response: &reqwest::Response, video_id: &str) -> Result<()> {
-        if response.status() == 429 {
-            return Err(TranscriptError::IpBlocked(video_id.to_string()));
-        }
-        if !response.status().is_success() {
-            return Err(TranscriptError::HttpError(format!(
-                "HTTP {}: {}",
-                response.status(),
-                response
-                    .status()
-                    .canonical_reason()
-                    .unwrap_or("Unknown error")
-            )));
+    /// Track for `languages` (or the video's default), falling back to any available
+    /// track when that fails and the client was built with
+    /// [`any_language_fallback`](YouTubeTranscriptBuilder::any_language_fallback)
+    fn select_transcript<'a>(
+        &self,
+        transcript_list: &'a TranscriptList,
+        languages: Option<&[&str]>,
+    ) -> Result<&'a TranscriptInfo> {
+        let requested = match languages {
+            Some(languages) => transcript_list.find_transcript(languages),
+            None => transcript_list.find_default_transcript(),
+        };
+        match requested {
+            Err(TranscriptError::NoTranscriptFound(..)) if self.any_language_fallback => {
+                let transcript = transcript_list.find_any_transcript()?;
+                log::info!(
+                    "{}: requested language not available, using {} ({})",
+                    transcript_list.video_id,
+                    transcript.language,
+                    transcript.language_code
+                );
+                Ok(transcript)
+            }
+            result => result,
         }
-        Ok(())
     }
 
-    async fn fetch_transcript_data(
+    /// Timedtext parser configured with this client's cue options
+    fn parser(&self) -> TranscriptParser {
+        TranscriptParser::new(false).keep_empty_cues(self.keep_empty_cues)
+    }
+
+    /// The track's timedtext URL, with the translation target and proof-of-origin
+    /// token added as escaped query parameters
+    fn timedtext_url(
         &self,
         video_id: &str,
         transcript_info: &TranscriptInfo,
         translate_to: Option<&str>,
-        title: Option<String>,
-    ) -> Result<TranscriptResponse> {
-        let mut url = transcript_info.base_url.clone();
+    ) -> Result<url::Url> {
+        let mut url = url::Url::parse(&transcript_info.base_url)
+            .map_err(|_| TranscriptError::YouTubeDataUnparsable(video_id.to_string()))?;
 
-        if let Some(target_lang) = translate_to {
-            url = format!("{}&tlang={}", url, target_lang);
+        // Protected videos need a proof-of-origin token on the timedtext request
+        if self.po_token.is_none()
+            && url
+                .query_pairs()
+                .any(|(key, value)| key == "exp" && value == "xpe")
+        {
+            return Err(TranscriptError::PoTokenRequired(video_id.to_string()));
         }
 
-        // Check for protected video token requirement
-        if url.contains("&exp=xpe") {
-            return Err(TranscriptError::PoTokenRequired(video_id.to_string()));
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(target_lang) = translate_to {
+                query.append_pair("tlang", target_lang);
+            }
+            if let Some(po_token) = &self.po_token {
+                query.append_pair("pot", po_token);
+            }
         }
+        Ok(url)
+    }
+
+    async fn fetch_transcript_body(
+        &self,
+        video_id: &str,
+        transcript_info: &TranscriptInfo,
+        translate_to: Option<&str>,
+    ) -> Result<String> {
+        let url = self.timedtext_url(video_id, transcript_info, translate_to)?;
 
         // Add delay before fetching transcript to avoid rate limiting
         self.delay().await;
 
-        let response = self.client.get(&url).send().await.map_err(|e| {
-            TranscriptError::HttpError(format!("Failed to fetch transcript: {}", e))
-        })?;
+        // Ask for the track only if it changed since it was cached
+        let key = cache::BodyCache::key(video_id, transcript_info, translate_to);
+        let cached = self.cache.as_ref().and_then(|cache| cache.load(&key));
+        let mut request = self.client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = self.send(request).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                log::debug!(
+                    "Transcript for {} not modified, using cached copy",
+                    video_id
+                );
+                return Ok(cached.body);
+            }
+        }
 
         self.check_http_errors(&response, video_id)?;
 
-        let xml_content = response
-            .text()
-            .await
-            .map_err(|e| TranscriptError::HttpError(format!("Failed to read transcript: {}", e)))?;
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+        let body = parser::decode_lossy(
+            &response.bytes().await?,
+            &format!("Caption data for {}", video_id),
+        );
+        Self::check_blocked_body(&body, video_id)?;
+
+        // Without a validator there is nothing to revalidate with next time
+        if let Some(cache) = &self.cache {
+            if etag.is_some() || last_modified.is_some() {
+                let entry = cache::CachedBody {
+                    etag,
+                    last_modified,
+                    body,
+                };
+                if let Err(e) = cache.store(&key, &entry) {
+                    log::warn!("{}", e);
+                }
+                return Ok(entry.body);
+            }
+        }
+        Ok(body)
+    }
+
+    /// Reject an HTML page served in place of captions: the EU consent interstitial
+    /// (`FailedToCreateConsentCookie`) or a bot check (`RequestBlocked`), which would
+    /// otherwise surface as a confusing parse error
+    fn check_blocked_body(body: &str, video_id: &str) -> Result<()> {
+        let start: String = body
+            .trim_start()
+            .chars()
+            .take(14)
+            .collect::<String>()
+            .to_lowercase();
+        if !start.starts_with("<!doctype html") && !start.starts_with("<html") {
+            return Ok(());
+        }
+        if body.contains("consent.youtube.com") || body.contains("consent.google.com") {
+            return Err(TranscriptError::FailedToCreateConsentCookie(
+                video_id.to_string(),
+            ));
+        }
+        Err(TranscriptError::RequestBlocked(video_id.to_string()))
+    }
+
+    /// Turn an error status (still failing after any retries) into an error
+    fn check_http_errors(&self, response: &reqwest::Response, video_id: &str) -> Result<()> {
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(TranscriptError::RateLimited(video_id.to_string()));
+        }
+        response.error_for_status_ref()?;
+        Ok(())
+    }
+
+    async fn fetch_transcript_data(
+        &self,
+        video_id: &str,
+        transcript_info: &TranscriptInfo,
+        translate_to: Option<&str>,
+        title: Option<String>,
+    ) -> Result<TranscriptResponse> {
+        let xml_content = self
+            .fetch_transcript_body(video_id, transcript_info, translate_to)
+            .await?;
 
-        let parser = TranscriptParser::new(false);
-        let transcript_items = parser
+        let transcript_items = self
+            .parser()
             .parse(&xml_content)
             .map_err(|e| TranscriptError::XmlParseError(format!("Failed to parse XML: {}", e)))?;
 
+        // A track that parses to nothing would otherwise be written out as an empty file
+        if transcript_items.is_empty() {
+            return Err(TranscriptError::EmptyTranscript(video_id.to_string()));
+        }
+
         let language = if let Some(target_lang) = translate_to {
             transcript_info
                 .translation_languages
@@ -801,6 +1851,7 @@ impl YouTubeTranscript {
             is_generated: transcript_info.is_generated || translate_to.is_some(),
             is_translatable: transcript_info.is_translatable,
             transcript: transcript_items,
+            chapters: Vec::new(),
         })
     }
 }
@@ -809,6 +1860,113 @@ impl YouTubeTranscript {
 mod tests {
     use super::*;
 
+    fn playable_response(captions: Option<serde_json::Value>) -> serde_json::Value {
+        let mut data = serde_json::json!({
+            "playabilityStatus": { "status": "OK" },
+            "videoDetails": { "title": "Fixture" }
+        });
+        if let Some(captions) = captions {
+            data["captions"] = captions;
+        }
+        data
+    }
+
+    #[test]
+    fn test_extract_captions_disabled_by_uploader() {
+        let data = playable_response(None);
+        let result = YouTubeTranscript::new().extract_captions_json("dQw4w9WgXcQ", &data);
+        assert!(matches!(
+            result,
+            Err(TranscriptError::TranscriptsDisabled(_))
+        ));
+    }
+
+    #[test]
+    fn test_extract_captions_without_tracks() {
+        let data = playable_response(Some(serde_json::json!({
+            "playerCaptionsTracklistRenderer": {
+                "captionTracks": [],
+                "translationLanguages": []
+            }
+        })));
+        let result = YouTubeTranscript::new().extract_captions_json("dQw4w9WgXcQ", &data);
+        assert!(
+            matches!(result, Err(TranscriptError::NoTranscriptFound(_, langs)) if langs.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_extract_captions_with_tracks() {
+        let data = playable_response(Some(serde_json::json!({
+            "playerCaptionsTracklistRenderer": {
+                "captionTracks": [{
+                    "baseUrl": "https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&lang=en",
+                    "languageCode": "en",
+                    "name": { "runs": [{ "text": "English" }] },
+                    "kind": "asr",
+                    "isTranslatable": true
+                }],
+                "translationLanguages": []
+            }
+        })));
+        let list = YouTubeTranscript::new()
+            .extract_captions_json("dQw4w9WgXcQ", &data)
+            .unwrap();
+        assert_eq!(list.title.as_deref(), Some("Fixture"));
+        assert!(list.generated.contains_key("en"));
+        assert!(list.manually_created.is_empty());
+    }
+
+    #[test]
+    fn test_extract_captions_default_language() {
+        let track = |code: &str, kind: Option<&str>| {
+            let mut track = serde_json::json!({
+                "baseUrl": format!("https://www.youtube.com/api/timedtext?lang={}", code),
+                "languageCode": code,
+            });
+            if let Some(kind) = kind {
+                track["kind"] = serde_json::json!(kind);
+            }
+            track
+        };
+
+        // No default audio track: the first manual track wins over earlier generated ones
+        let data = playable_response(Some(serde_json::json!({
+            "playerCaptionsTracklistRenderer": {
+                "captionTracks": [track("de", Some("asr")), track("fr", None), track("en", None)]
+            }
+        })));
+        let list = YouTubeTranscript::new()
+            .extract_captions_json("dQw4w9WgXcQ", &data)
+            .unwrap();
+        assert_eq!(list.default_language_code.as_deref(), Some("fr"));
+        assert_eq!(list.find_default_transcript().unwrap().language_code, "fr");
+
+        // Only generated tracks
+        let data = playable_response(Some(serde_json::json!({
+            "playerCaptionsTracklistRenderer": {
+                "captionTracks": [track("ja", Some("asr"))]
+            }
+        })));
+        let list = YouTubeTranscript::new()
+            .extract_captions_json("dQw4w9WgXcQ", &data)
+            .unwrap();
+        assert_eq!(list.default_language_code.as_deref(), Some("ja"));
+
+        // The default audio track points at its caption track
+        let data = playable_response(Some(serde_json::json!({
+            "playerCaptionsTracklistRenderer": {
+                "captionTracks": [track("fr", None), track("es", None)],
+                "audioTracks": [{ "defaultCaptionTrackIndex": 1 }],
+                "defaultAudioTrackIndex": 0
+            }
+        })));
+        let list = YouTubeTranscript::new()
+            .extract_captions_json("dQw4w9WgXcQ", &data)
+            .unwrap();
+        assert_eq!(list.default_language_code.as_deref(), Some("es"));
+    }
+
     #[test]
     fn test_extract_video_id_direct() {
         assert_eq!(
@@ -837,17 +1995,238 @@ mod tests {
     #[test]
     fn test_extract_video_id_short_url_with_query() {
         assert_eq!(
-            YouTubeTranscript::extract_video_id("https://youtu.be/_NuH3D4SN-c?si=VSFea_rMwtaiR8Q7").unwrap(),
+            YouTubeTranscript::extract_video_id("https://youtu.be/_NuH3D4SN-c?si=VSFea_rMwtaiR8Q7")
+                .unwrap(),
             "_NuH3D4SN-c"
         );
     }
 
+    #[test]
+    fn test_extract_video_id_short_url_with_timestamp() {
+        assert_eq!(
+            YouTubeTranscript::extract_video_id("https://youtu.be/dQw4w9WgXcQ?t=30").unwrap(),
+            "dQw4w9WgXcQ"
+        );
+    }
+
+    #[test]
+    fn test_extract_video_id_watch_url_with_extra_params() {
+        assert_eq!(
+            YouTubeTranscript::extract_video_id(
+                "https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&list=PL123&t=42s&si=abc"
+            )
+            .unwrap(),
+            "dQw4w9WgXcQ"
+        );
+    }
+
+    #[test]
+    fn test_extract_video_id_shorts_url() {
+        assert_eq!(
+            YouTubeTranscript::extract_video_id(
+                "https://www.youtube.com/shorts/dQw4w9WgXcQ?feature=share"
+            )
+            .unwrap(),
+            "dQw4w9WgXcQ"
+        );
+    }
+
+    #[test]
+    fn test_extract_video_id_live_url() {
+        assert_eq!(
+            YouTubeTranscript::extract_video_id("youtube.com/live/dQw4w9WgXcQ?si=xyz").unwrap(),
+            "dQw4w9WgXcQ"
+        );
+    }
+
+    #[test]
+    fn test_extract_video_id_embed_url() {
+        assert_eq!(
+            YouTubeTranscript::extract_video_id(
+                "https://www.youtube.com/embed/dQw4w9WgXcQ?start=10"
+            )
+            .unwrap(),
+            "dQw4w9WgXcQ"
+        );
+    }
+
+    #[test]
+    fn test_extract_video_id_music_url() {
+        assert_eq!(
+            YouTubeTranscript::extract_video_id(
+                "https://music.youtube.com/watch?v=dQw4w9WgXcQ&feature=share"
+            )
+            .unwrap(),
+            "dQw4w9WgXcQ"
+        );
+    }
+
     #[test]
     fn test_extract_video_id_invalid() {
+        assert!(
+            YouTubeTranscript::extract_video_id("https://www.youtube.com/shorts/short").is_err()
+        );
         assert!(YouTubeTranscript::extract_video_id("not-a-valid-id").is_err());
         assert!(YouTubeTranscript::extract_video_id("https://example.com").is_err());
     }
 
+    #[test]
+    fn test_extract_channel_id() {
+        let id = "UCuAXFkgsw1L7xaCfnd5JJOw";
+        for input in [
+            id.to_string(),
+            format!("https://www.youtube.com/channel/{}", id),
+            format!("youtube.com/channel/{}/videos", id),
+        ] {
+            assert_eq!(YouTubeTranscript::extract_channel_id(&input).unwrap(), id);
+        }
+        assert!(
+            YouTubeTranscript::extract_channel_id("https://www.youtube.com/@rustlang").is_err()
+        );
+        assert!(YouTubeTranscript::extract_channel_id("dQw4w9WgXcQ").is_err());
+    }
+
+    #[test]
+    fn test_is_channel_url() {
+        for input in [
+            "https://www.youtube.com/@rustlang",
+            "https://www.youtube.com/@rustlang/videos",
+            "@rustlang",
+            "youtube.com/c/RustVideos",
+            "https://www.youtube.com/user/someone",
+            "https://m.youtube.com/channel/UCuAXFkgsw1L7xaCfnd5JJOw",
+            "UCuAXFkgsw1L7xaCfnd5JJOw",
+        ] {
+            assert!(YouTubeTranscript::is_channel_url(input), "{}", input);
+        }
+        for input in [
+            "dQw4w9WgXcQ",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.youtube.com/playlist?list=PLxyz",
+            "https://example.com/@someone",
+        ] {
+            assert!(!YouTubeTranscript::is_channel_url(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_uploads_playlist_id() {
+        assert_eq!(
+            YouTubeTranscript::uploads_playlist_id("UCuAXFkgsw1L7xaCfnd5JJOw"),
+            "UUuAXFkgsw1L7xaCfnd5JJOw"
+        );
+    }
+
+    #[test]
+    fn test_extract_channel_id_from_html() {
+        let html = r#"<link rel="canonical" href="https://www.youtube.com/channel/UCuAXFkgsw1L7xaCfnd5JJOw">"#;
+        assert_eq!(
+            YouTubeTranscript::extract_channel_id_from_html(html).as_deref(),
+            Some("UCuAXFkgsw1L7xaCfnd5JJOw")
+        );
+
+        let html = r#"..."channelMetadataRenderer":{"title":"Rust","externalId":"UCaYhcUwRBNscFNUKTjgPFiA"}..."#;
+        assert_eq!(
+            YouTubeTranscript::extract_channel_id_from_html(html).as_deref(),
+            Some("UCaYhcUwRBNscFNUKTjgPFiA")
+        );
+
+        assert_eq!(
+            YouTubeTranscript::extract_channel_id_from_html("<html></html>"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_chapters() {
+        // Markers repeat further down the page; only the first copy is used
+        let html = r#"..."chapterRenderer":{"title":{"simpleText":"Intro"},"timeRangeStartMillis":0,"onActiveCommand":{}}},
+            {"chapterRenderer":{"title":{"simpleText":"\"Hello\" & setup"},"timeRangeStartMillis":95500}},
+            ..."chapterRenderer":{"title":{"simpleText":"Intro"},"timeRangeStartMillis":0}..."#;
+        let data =
+            serde_json::json!({"videoDetails": {"shortDescription": "0:00 A\n1:00 B\n2:00 C"}});
+        assert_eq!(
+            YouTubeTranscript::extract_page_chapters(html),
+            vec![
+                ("Intro".to_string(), 0.0),
+                ("\"Hello\" & setup".to_string(), 95.5)
+            ]
+        );
+        assert!(YouTubeTranscript::extract_page_chapters("<html></html>").is_empty());
+
+        // Videos without markers fall back to the description timestamps
+        let chapters = YouTubeTranscript::extract_description_chapters(&data);
+        assert_eq!(
+            chapters.iter().map(|(t, _)| t.as_str()).collect::<Vec<_>>(),
+            vec!["A", "B", "C"]
+        );
+        assert!(YouTubeTranscript::extract_description_chapters(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_parse_description_chapters_rules() {
+        let chapters = parse_description_chapters("00:00 - Intro\n05:30 | Demo\nOutro 1:10:00\n");
+        assert_eq!(
+            chapters,
+            vec![
+                ("Intro".to_string(), 0.0),
+                ("Demo".to_string(), 330.0),
+                ("Outro".to_string(), 4200.0)
+            ]
+        );
+        // Not starting at 0:00, too few entries, or out of order
+        assert!(parse_description_chapters("0:30 A\n1:00 B\n2:00 C").is_empty());
+        assert!(parse_description_chapters("0:00 A\n1:00 B").is_empty());
+        assert!(parse_description_chapters("0:00 A\n2:00 B\n1:00 C").is_empty());
+    }
+
+    #[test]
+    fn test_extract_publish_date() {
+        let html = r#"..."microformat":{"playerMicroformatRenderer":{"publishDate":"2009-10-24T23:57:33-07:00"}}..."#;
+        assert_eq!(
+            YouTubeTranscript::extract_publish_date(html).as_deref(),
+            Some("2009-10-24")
+        );
+
+        let html = r#"<meta itemprop="datePublished" content="2021-03-05">"#;
+        assert_eq!(
+            YouTubeTranscript::extract_publish_date(html).as_deref(),
+            Some("2021-03-05")
+        );
+
+        assert_eq!(
+            YouTubeTranscript::extract_publish_date("<html></html>"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_start_time() {
+        assert_eq!(
+            YouTubeTranscript::extract_start_time("https://youtu.be/dQw4w9WgXcQ?t=30"),
+            Some(30.0)
+        );
+        assert_eq!(
+            YouTubeTranscript::extract_start_time(
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=120s"
+            ),
+            Some(120.0)
+        );
+        assert_eq!(
+            YouTubeTranscript::extract_start_time(
+                "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1h2m3s"
+            ),
+            Some(3723.0)
+        );
+        assert_eq!(
+            YouTubeTranscript::extract_start_time(
+                "https://www.youtube.com/embed/dQw4w9WgXcQ?start=15"
+            ),
+            Some(15.0)
+        );
+        assert_eq!(YouTubeTranscript::extract_start_time("dQw4w9WgXcQ"), None);
+    }
+
     #[test]
     fn test_transcript_list_find_transcript() {
         let mut manually_created = HashMap::new();
@@ -856,6 +2235,7 @@ mod tests {
         manually_created.insert(
             "en".to_string(),
             TranscriptInfo {
+                video_id: "test".to_string(),
                 language_code: "en".to_string(),
                 language: "English".to_string(),
                 is_generated: false,
@@ -868,6 +2248,7 @@ mod tests {
         generated.insert(
             "es".to_string(),
             TranscriptInfo {
+                video_id: "test".to_string(),
                 language_code: "es".to_string(),
                 language: "Spanish".to_string(),
                 is_generated: true,
@@ -883,6 +2264,8 @@ mod tests {
             manually_created,
             generated,
             translation_languages: vec![],
+            default_language_code: None,
+            chapters: vec![],
         };
 
         // Should find manually created first
@@ -890,7 +2273,10 @@ mod tests {
         // Should find generated if manually created not available
         assert_eq!(list.find_transcript(&["es"]).unwrap().language_code, "es");
         // Should prefer manually created over generated
-        assert_eq!(list.find_transcript(&["en", "es"]).unwrap().language_code, "en");
+        assert_eq!(
+            list.find_transcript(&["en", "es"]).unwrap().language_code,
+            "en"
+        );
         // Should error if not found
         assert!(list.find_transcript(&["fr"]).is_err());
     }
@@ -901,6 +2287,7 @@ mod tests {
         manually_created.insert(
             "en".to_string(),
             TranscriptInfo {
+                video_id: "test".to_string(),
                 language_code: "en".to_string(),
                 language: "English".to_string(),
                 is_generated: false,
@@ -916,6 +2303,8 @@ mod tests {
             manually_created,
             generated: HashMap::new(),
             translation_languages: vec![],
+            default_language_code: None,
+            chapters: vec![],
         };
 
         assert_eq!(
@@ -931,6 +2320,7 @@ mod tests {
         generated.insert(
             "es".to_string(),
             TranscriptInfo {
+                video_id: "test".to_string(),
                 language_code: "es".to_string(),
                 language: "Spanish".to_string(),
                 is_generated: true,
@@ -946,21 +2336,653 @@ mod tests {
             manually_created: HashMap::new(),
             generated,
             translation_languages: vec![],
+            default_language_code: None,
+            chapters: vec![],
         };
 
         assert_eq!(list.find_generated(&["es"]).unwrap().language_code, "es");
         assert!(list.find_generated(&["en"]).is_err());
     }
 
+    #[test]
+    fn test_any_language_fallback() {
+        let track = |code: &str, is_generated: bool| TranscriptInfo {
+            video_id: "test".to_string(),
+            language_code: code.to_string(),
+            language: code.to_uppercase(),
+            is_generated,
+            is_translatable: false,
+            base_url: format!("https://example.com/{}", code),
+            translation_languages: vec![],
+        };
+        let mut list = TranscriptList {
+            video_id: "test".to_string(),
+            title: None,
+            manually_created: HashMap::new(),
+            generated: HashMap::from([
+                ("ja".to_string(), track("ja", true)),
+                ("de".to_string(), track("de", true)),
+            ]),
+            translation_languages: vec![],
+            default_language_code: None,
+            chapters: vec![],
+        };
+        assert_eq!(list.find_any_transcript().unwrap().language_code, "de");
+        list.manually_created
+            .insert("pt".to_string(), track("pt", false));
+        assert_eq!(list.find_any_transcript().unwrap().language_code, "pt");
+
+        let strict = YouTubeTranscript::builder().build().unwrap();
+        assert!(matches!(
+            strict.select_transcript(&list, Some(&["en"])),
+            Err(TranscriptError::NoTranscriptFound(..))
+        ));
+        let lenient = YouTubeTranscript::builder()
+            .any_language_fallback(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            lenient
+                .select_transcript(&list, Some(&["en"]))
+                .unwrap()
+                .language_code,
+            "pt"
+        );
+        assert_eq!(
+            lenient
+                .select_transcript(&list, None)
+                .unwrap()
+                .language_code,
+            "pt"
+        );
+        assert_eq!(
+            lenient
+                .select_transcript(&list, Some(&["ja"]))
+                .unwrap()
+                .language_code,
+            "ja"
+        );
+
+        let empty = TranscriptList {
+            manually_created: HashMap::new(),
+            generated: HashMap::new(),
+            ..list
+        };
+        assert!(lenient.select_transcript(&empty, None).is_err());
+    }
+
+    #[test]
+    fn test_original_language() {
+        let track = |code: &str, is_generated: bool| TranscriptInfo {
+            video_id: "test".to_string(),
+            language_code: code.to_string(),
+            language: code.to_uppercase(),
+            is_generated,
+            is_translatable: false,
+            base_url: format!("https://example.com/{}", code),
+            translation_languages: vec![],
+        };
+        let mut list = TranscriptList {
+            video_id: "test".to_string(),
+            title: None,
+            manually_created: HashMap::from([("pt".to_string(), track("pt", false))]),
+            generated: HashMap::from([
+                ("ja".to_string(), track("ja", true)),
+                ("de".to_string(), track("de", true)),
+            ]),
+            translation_languages: vec![],
+            default_language_code: None,
+            chapters: vec![],
+        };
+        // Several auto-generated tracks and no default: no way to tell
+        assert!(list.original_language().is_none());
+
+        // The lone auto-generated track names the spoken language; a manual track wins
+        list.generated.remove("ja");
+        list.manually_created
+            .insert("de".to_string(), track("de", false));
+        let original = list.original_language().unwrap();
+        assert_eq!(
+            (original.language_code.as_str(), original.is_generated),
+            ("de", false)
+        );
+
+        list.generated.clear();
+        list.default_language_code = Some("pt".to_string());
+        assert_eq!(list.original_language().unwrap().language_code, "pt");
+    }
+
+    #[test]
+    fn test_translation_targets() {
+        let mut track = TranscriptInfo {
+            video_id: "test".to_string(),
+            language_code: "en".to_string(),
+            language: "English".to_string(),
+            is_generated: false,
+            is_translatable: true,
+            base_url: "https://example.com/en".to_string(),
+            translation_languages: vec![TranslationLanguage {
+                language: "Spanish".to_string(),
+                language_code: "es".to_string(),
+            }],
+        };
+        assert_eq!(track.translation_targets().unwrap()[0].language_code, "es");
+
+        track.is_translatable = false;
+        assert!(
+            matches!(track.translation_targets(), Err(TranscriptError::NotTranslatable(id)) if id == "test")
+        );
+    }
+
+    #[test]
+    fn test_find_translatable_transcript() {
+        let track = |code: &str, is_translatable: bool, targets: &[&str]| TranscriptInfo {
+            video_id: "test".to_string(),
+            language_code: code.to_string(),
+            language: code.to_uppercase(),
+            is_generated: false,
+            is_translatable,
+            base_url: format!("https://example.com/{}", code),
+            translation_languages: targets
+                .iter()
+                .map(|target| TranslationLanguage {
+                    language: target.to_uppercase(),
+                    language_code: target.to_string(),
+                })
+                .collect(),
+        };
+        let list = TranscriptList {
+            video_id: "test".to_string(),
+            title: None,
+            manually_created: HashMap::from([
+                ("en".to_string(), track("en", false, &[])),
+                ("fr".to_string(), track("fr", true, &["es"])),
+                ("de".to_string(), track("de", true, &["it"])),
+            ]),
+            generated: HashMap::new(),
+            translation_languages: vec![],
+            default_language_code: None,
+            chapters: vec![],
+        };
+
+        // en can't be translated, so the second source is used
+        let source = list
+            .find_translatable_transcript(&["en", "fr"], "es")
+            .unwrap();
+        assert_eq!(source.language_code, "fr");
+
+        assert!(matches!(
+            list.find_translatable_transcript(&["en", "de"], "es"),
+            Err(TranscriptError::TranslationLanguageNotAvailable(lang)) if lang == "es"
+        ));
+        assert!(matches!(
+            list.find_translatable_transcript(&["en"], "es"),
+            Err(TranscriptError::NotTranslatable(_))
+        ));
+        assert!(matches!(
+            list.find_translatable_transcript(&["ja"], "es"),
+            Err(TranscriptError::NoTranscriptFound(..))
+        ));
+
+        // --translate-list offers exactly what the sources above can translate into
+        let codes = |targets: Vec<&TranslationLanguage>| -> Vec<String> {
+            targets.iter().map(|t| t.language_code.clone()).collect()
+        };
+        assert_eq!(
+            codes(list.translation_targets(&["en", "fr"]).unwrap()),
+            vec!["es"]
+        );
+        assert_eq!(
+            codes(list.translation_targets(&["de", "fr"]).unwrap()),
+            vec!["it", "es"]
+        );
+        assert!(matches!(
+            list.translation_targets(&["en"]),
+            Err(TranscriptError::NotTranslatable(_))
+        ));
+        assert!(matches!(
+            list.translation_targets(&["ja"]),
+            Err(TranscriptError::NoTranscriptFound(..))
+        ));
+    }
+
+    #[test]
+    fn test_transcript_item_json_round_trip() {
+        let items = vec![
+            TranscriptItem {
+                text: "Hello \"world\" ♪".to_string(),
+                start: 0.08,
+                duration: 3.839,
+            },
+            TranscriptItem {
+                text: String::new(),
+                start: 3725.9,
+                duration: 0.0,
+            },
+        ];
+
+        let json = serde_json::to_string(&items).unwrap();
+        let parsed: Vec<TranscriptItem> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, items);
+    }
+
+    #[test]
+    fn test_transcript_item_eq() {
+        let item = TranscriptItem {
+            text: "Hello".to_string(),
+            start: 1.0,
+            duration: 2.0,
+        };
+        assert_eq!(item.clone(), item);
+        assert_ne!(
+            TranscriptItem {
+                start: 1.5,
+                ..item.clone()
+            },
+            item
+        );
+        assert_ne!(
+            TranscriptItem {
+                text: "hello".to_string(),
+                ..item.clone()
+            },
+            item
+        );
+    }
+
+    #[test]
+    fn test_transcript_list_serializes_to_json() {
+        let mut generated = HashMap::new();
+        generated.insert(
+            "en".to_string(),
+            TranscriptInfo {
+                video_id: "test".to_string(),
+                language_code: "en".to_string(),
+                language: "English (auto-generated)".to_string(),
+                is_generated: true,
+                is_translatable: true,
+                base_url: "https://example.com/en".to_string(),
+                translation_languages: vec![],
+            },
+        );
+
+        let list = TranscriptList {
+            video_id: "test".to_string(),
+            title: Some("Test video".to_string()),
+            manually_created: HashMap::new(),
+            generated,
+            translation_languages: vec![TranslationLanguage {
+                language: "German".to_string(),
+                language_code: "de".to_string(),
+            }],
+            default_language_code: Some("en".to_string()),
+            chapters: vec![],
+        };
+
+        let json = serde_json::to_value(&list).unwrap();
+        assert_eq!(json["video_id"], "test");
+        assert_eq!(json["generated"]["en"]["is_generated"], true);
+        assert_eq!(json["manually_created"], serde_json::json!({}));
+        assert_eq!(json["translation_languages"][0]["language_code"], "de");
+        assert_eq!(json["default_language_code"], "en");
+    }
+
+    #[test]
+    fn test_create_consent_cookie_sets_cookie() {
+        use reqwest::cookie::CookieStore;
+
+        let api = YouTubeTranscript::with_delay(0);
+        let html = r#"<form action="https://consent.youtube.com/s"><input name="v" value="cb.20210328-17-p0.en+FX+123"></form>"#;
+        api.create_consent_cookie(html, "test").unwrap();
+
+        let url = url::Url::parse("https://www.youtube.com/watch?v=dQw4w9WgXcQ").unwrap();
+        let cookies = api.cookie_jar.cookies(&url).unwrap();
+        assert!(cookies
+            .to_str()
+            .unwrap()
+            .contains("CONSENT=YES+cb.20210328-17-p0.en+FX+123"));
+    }
+
+    #[test]
+    fn test_load_cookies() {
+        use reqwest::cookie::CookieStore;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("cookies.txt");
+        std::fs::write(
+            &path,
+            "# Netscape HTTP Cookie File\n.youtube.com\tTRUE\t/\tTRUE\t1799999999\tSID\tabc123\n",
+        )
+        .unwrap();
+
+        let api = YouTubeTranscript::with_delay(0);
+        api.load_cookies(&path).unwrap();
+
+        let url = url::Url::parse("https://www.youtube.com/watch?v=dQw4w9WgXcQ").unwrap();
+        let cookies = api.cookie_jar.cookies(&url).unwrap();
+        assert!(cookies.to_str().unwrap().contains("SID=abc123"));
+        assert!(api
+            .load_cookies(temp_dir.path().join("missing.txt"))
+            .is_err());
+    }
+
     #[test]
     fn test_youtube_transcript_default() {
         let api = YouTubeTranscript::default();
         assert_eq!(api.delay_ms, 500);
     }
 
+    #[test]
+    fn test_youtube_transcript_with_user_agent() {
+        let api = YouTubeTranscript::with_user_agent(250, "my-agent/1.0", "es").unwrap();
+        assert_eq!(api.delay_ms, 250);
+        assert!(YouTubeTranscript::with_user_agent(0, "bad\nagent", "en").is_err());
+        assert!(YouTubeTranscript::with_user_agent(0, DEFAULT_USER_AGENT, "bad\nlang").is_err());
+    }
+
     #[test]
     fn test_youtube_transcript_with_delay() {
         let api = YouTubeTranscript::with_delay(1000);
         assert_eq!(api.delay_ms, 1000);
     }
+
+    const SOURCE_TRACK: &str = r#"<transcript><text start="0.5" dur="2.0">hello</text><text start="2.5" dur="1.5">good morning</text><text start="4.0" dur="3.25">see you</text></transcript>"#;
+    const SRV3_TRACK: &str = r#"<timedtext format="3"><body><p t="500" d="2000"><s ac="255">hello</s></p></body></timedtext>"#;
+    /// A translation cue for cue with the source, timed a little differently
+    const TRANSLATED_TRACK: &str = r#"<transcript><text start="0.4" dur="2.2">hola</text><text start="2.6" dur="1.4">buenos días</text><text start="4.1" dur="3.0">hasta luego</text></transcript>"#;
+    /// A translation that merged two of the source's cues
+    const RETIMED_TRACK: &str = r#"<transcript><text start="0.6" dur="1.9">hallo</text><text start="2.5" dur="4.75">guten Morgen, bis bald</text></transcript>"#;
+
+    /// Trimmed copy of the consent interstitial YouTube serves to new EU visitors
+    const CONSENT_PAGE: &str = r#"<!DOCTYPE html><html lang="en" dir="ltr"><head><title>Before you continue to YouTube</title></head><body><div class="saveButtonContainer"><form action="https://consent.youtube.com/s" method="POST"><input type="hidden" name="gl" value="DE"><input type="hidden" name="continue" value="https://www.youtube.com/api/timedtext?v=dQw4w9WgXcQ&amp;lang=en"><input type="hidden" name="v" value="cb.20210328-17-p0.en+FX+123"><button>Accept all</button></form></div></body></html>"#;
+
+    /// Trimmed copy of the "unusual traffic" bot check
+    const CAPTCHA_PAGE: &str = r#"<html><head><title>https://www.youtube.com/api/timedtext</title></head><body><div id="infoDiv">Our systems have detected unusual traffic from your computer network.</div><form id="captcha-form" action="index" method="post"><div class="g-recaptcha" data-sitekey="6LfwuyUTAAAAAOAmoS0fdqijC2PbbdH4kjq62Y1b"></div></form></body></html>"#;
+
+    /// Local timedtext stand-in that serves the Spanish translation when `tlang=es` is
+    /// requested, a retimed German one for `tlang=de`, srv3 for `fmt=srv3`, the pages above for `lang=consent` and `lang=captcha`, and the source
+    /// track otherwise, tagged with `ETag: "v1"` and answered with a 304 when the
+    /// request already has that tag. Keeps connections alive and counts
+    /// how many were opened.
+    async fn spawn_timedtext_server() -> (std::net::SocketAddr, Arc<std::sync::atomic::AtomicUsize>)
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    let mut chunk = [0u8; 2048];
+                    loop {
+                        let n = match conn.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => n,
+                        };
+                        buffer.extend_from_slice(&chunk[..n]);
+                        // GET requests have no body, so a blank line ends each one
+                        while let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                            let request = String::from_utf8_lossy(&buffer[..end]).to_string();
+                            buffer.drain(..end + 4);
+                            let body = if request.contains("tlang=es") {
+                                TRANSLATED_TRACK
+                            } else if request.contains("tlang=de") {
+                                RETIMED_TRACK
+                            } else if request.contains("fmt=srv3") {
+                                SRV3_TRACK
+                            } else if request.contains("lang=consent") {
+                                CONSENT_PAGE
+                            } else if request.contains("lang=captcha") {
+                                CAPTCHA_PAGE
+                            } else {
+                                SOURCE_TRACK
+                            };
+                            let response = if request
+                                .to_lowercase()
+                                .contains("if-none-match: \"v1\"")
+                            {
+                                "HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\n\r\n".to_string()
+                            } else {
+                                format!(
+                                    "HTTP/1.1 200 OK\r\ncontent-type: text/xml\r\netag: \"v1\"\r\ncontent-length: {}\r\n\r\n{}",
+                                    body.len(),
+                                    body
+                                )
+                            };
+                            if conn.write_all(response.as_bytes()).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        (addr, connections)
+    }
+
+    fn timedtext_info(addr: std::net::SocketAddr) -> TranscriptInfo {
+        TranscriptInfo {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            language_code: "en".to_string(),
+            language: "English".to_string(),
+            is_generated: false,
+            is_translatable: true,
+            base_url: format!("http://{}/api/timedtext?v=dQw4w9WgXcQ&lang=en", addr),
+            translation_languages: vec![TranslationLanguage {
+                language: "Spanish".to_string(),
+                language_code: "es".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_timedtext_url_escapes_parameters() {
+        let info = timedtext_info("127.0.0.1:8080".parse().unwrap());
+        let api = YouTubeTranscript::builder()
+            .po_token("a+b/c&d=e")
+            .build()
+            .unwrap();
+        let url = api
+            .timedtext_url("dQw4w9WgXcQ", &info, Some("pt BR"))
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "http://127.0.0.1:8080/api/timedtext?v=dQw4w9WgXcQ&lang=en&tlang=pt+BR&pot=a%2Bb%2Fc%26d%3De"
+        );
+        let pot = url
+            .query_pairs()
+            .find(|(key, _)| key == "pot")
+            .map(|(_, value)| value.into_owned());
+        assert_eq!(pot.as_deref(), Some("a+b/c&d=e"));
+
+        // Tracks marked exp=xpe can't be fetched without a token
+        let protected = TranscriptInfo {
+            base_url: format!("{}&exp=xpe", info.base_url),
+            ..info
+        };
+        assert!(matches!(
+            YouTubeTranscript::new().timedtext_url("dQw4w9WgXcQ", &protected, None),
+            Err(TranscriptError::PoTokenRequired(_))
+        ));
+    }
+
+    #[test]
+    fn test_jittered_delay_ms() {
+        assert_eq!(jittered_delay_ms(1000, 0, 12345), 1000);
+        assert_eq!(jittered_delay_ms(1000, 20, 0), 800);
+        assert_eq!(jittered_delay_ms(1000, 20, 400), 1200);
+        assert_eq!(jittered_delay_ms(1000, 20, 401), 800);
+        assert_eq!(jittered_delay_ms(0, 50, 7), 0);
+        for random in [1, 99, u64::MAX] {
+            assert!((800..=1200).contains(&jittered_delay_ms(1000, 20, random)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translated_transcript_keeps_cue_timing() {
+        let (addr, _) = spawn_timedtext_server().await;
+        let mut info = timedtext_info(addr);
+        info.translation_languages.push(TranslationLanguage {
+            language: "German".to_string(),
+            language_code: "de".to_string(),
+        });
+        let list = TranscriptList {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            title: Some("Title".to_string()),
+            manually_created: HashMap::from([("en".to_string(), info.clone())]),
+            generated: HashMap::new(),
+            translation_languages: info.translation_languages.clone(),
+            default_language_code: Some("en".to_string()),
+            chapters: vec![],
+        };
+        let api = YouTubeTranscript::with_delay(0);
+
+        // Cue for cue with the source, the translation takes the source's timing
+        let (source, translated) = api
+            .translate_with_list(&list, &["ja", "en"], "es")
+            .await
+            .unwrap();
+        assert_eq!(source.language_code, "en");
+        assert_eq!(source.transcript[1].text, "good morning");
+        assert_eq!(translated.language_code, "es");
+        assert_eq!(translated.title.as_deref(), Some("Title"));
+        assert_eq!(translated.transcript[1].text, "buenos días");
+        assert_eq!(translated.transcript.len(), source.transcript.len());
+        for (translated, source) in translated.transcript.iter().zip(&source.transcript) {
+            assert_eq!(translated.start, source.start);
+            assert_eq!(translated.duration, source.duration);
+        }
+
+        // With cues merged, YouTube's timing for the translation is kept
+        let (_, translated) = api.translate_with_list(&list, &["en"], "de").await.unwrap();
+        let timing: Vec<(f64, f64)> = translated
+            .transcript
+            .iter()
+            .map(|i| (i.start, i.duration))
+            .collect();
+        assert_eq!(timing, vec![(0.6, 1.9), (2.5, 4.75)]);
+    }
+
+    #[tokio::test]
+    async fn test_html_instead_of_captions() {
+        let (addr, _) = spawn_timedtext_server().await;
+        let api = YouTubeTranscript::with_delay(0);
+        let mut info = timedtext_info(addr);
+
+        info.base_url = format!("http://{}/api/timedtext?v=dQw4w9WgXcQ&lang=consent", addr);
+        let err = api
+            .fetch_transcript_data("dQw4w9WgXcQ", &info, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            TranscriptError::FailedToCreateConsentCookie(_)
+        ));
+        assert!(err.to_string().contains("--cookies"));
+
+        info.base_url = format!("http://{}/api/timedtext?v=dQw4w9WgXcQ&lang=captcha", addr);
+        let err = api
+            .fetch_transcript_data("dQw4w9WgXcQ", &info, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TranscriptError::RequestBlocked(_)));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_transcript_read_from_cache() {
+        let (addr, _) = spawn_timedtext_server().await;
+        let info = timedtext_info(addr);
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let api = YouTubeTranscript::builder()
+            .delay(0)
+            .cache_dir(temp_dir.path())
+            .build()
+            .unwrap();
+
+        let fetched = api
+            .fetch_transcript_data("dQw4w9WgXcQ", &info, None, None)
+            .await
+            .unwrap();
+        assert_eq!(fetched.transcript[0].text, "hello");
+
+        // Edit the cached copy: a 304 means it is what comes back the second time
+        let path = temp_dir.path().join("dQw4w9WgXcQ.en.json");
+        let cached = std::fs::read_to_string(&path).unwrap();
+        assert!(cached.contains(r#"\"v1\""#));
+        std::fs::write(&path, cached.replace(">hello<", ">hello again<")).unwrap();
+
+        let refetched = api
+            .fetch_transcript_data("dQw4w9WgXcQ", &info, None, None)
+            .await
+            .unwrap();
+        assert_eq!(refetched.transcript[0].text, "hello again");
+        assert_eq!(refetched.transcript[1..], fetched.transcript[1..]);
+    }
+
+    #[tokio::test]
+    async fn test_cache_keeps_formats_apart() {
+        let (addr, _) = spawn_timedtext_server().await;
+        let info = timedtext_info(addr);
+        let srv3_info = TranscriptInfo {
+            base_url: format!("{}&fmt=srv3", info.base_url),
+            ..info.clone()
+        };
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let api = YouTubeTranscript::builder()
+            .delay(0)
+            .cache_dir(temp_dir.path())
+            .build()
+            .unwrap();
+
+        // Both bodies carry the same ETag, so a shared entry would get a 304 and the plain body
+        let plain = api
+            .fetch_transcript_body("dQw4w9WgXcQ", &info, None)
+            .await
+            .unwrap();
+        let srv3 = api
+            .fetch_transcript_body("dQw4w9WgXcQ", &srv3_info, None)
+            .await
+            .unwrap();
+        assert_eq!(plain, SOURCE_TRACK);
+        assert_eq!(srv3, SRV3_TRACK);
+
+        let plain = api
+            .fetch_transcript_body("dQw4w9WgXcQ", &info, None)
+            .await
+            .unwrap();
+        assert_eq!(plain, SOURCE_TRACK);
+        assert!(temp_dir
+            .path()
+            .join("dQw4w9WgXcQ.en.fmt-srv3.json")
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn test_requests_reuse_one_connection() {
+        let (addr, connections) = spawn_timedtext_server().await;
+        let info = timedtext_info(addr);
+        let api = YouTubeTranscript::with_delay(0);
+
+        for translate_to in [None, Some("es"), None, Some("es")] {
+            api.fetch_transcript_data("dQw4w9WgXcQ", &info, translate_to, None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // The baseline the docs compare against: a new client for every request
+        for translate_to in [None, Some("es"), None, Some("es")] {
+            YouTubeTranscript::with_delay(0)
+                .fetch_transcript_data("dQw4w9WgXcQ", &info, translate_to, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1 + 4);
+    }
 }
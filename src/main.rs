@@ -1,50 +1,200 @@
-use clap::Parser;
-use std::fs::File;
-use std::io::{self, Write};
+use clap::{ArgAction, Parser};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{error, info, warn, LevelFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::Path;
-use ytt::chatgpt::ChatGPT;
-use ytt::{TranscriptError, TranscriptItem, YouTubeTranscript};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use ytt::chatgpt::{ChatGPT, LlmProvider, Usage};
+use ytt::diff::{diff_transcripts, Diff};
+use ytt::stats::TranscriptStats;
+use ytt::timecode::{self, format_timecode, parse_clip};
+use ytt::transform::{
+    apply_replacements, combine_languages, dedupe_rolling, drop_duplicate_cues, drop_short_cues,
+    fix_case, fix_overlaps, normalize_text, repace, shift_timestamps, slice_by_time,
+    split_by_chapters, strip_annotations, strip_timecodes, BilingualCue, Replacement,
+};
+use ytt::{
+    is_srt, is_vtt, parse_srt, parse_vtt, TranscriptError, TranscriptInfo, TranscriptItem,
+    TranscriptParser, TranscriptResponse, VerboseItem, WordTimedItem, YouTubeTranscript,
+};
 
-#[derive(Parser)]
+#[derive(Parser, Clone)]
 #[command(name = "ytt")]
 #[command(about = "YouTube Transcript API - Fetch transcripts from YouTube videos", long_about = None)]
 struct Args {
     /// YouTube video URL or video ID
-    video: String,
+    #[arg(required_unless_present_any = ["input_file", "self_test"])]
+    video: Option<String>,
+
+    /// Check that fetching still works end to end (video ID extraction, track list,
+    /// transcript download, parsing and SRT output) and print PASS or FAIL. Uses a
+    /// long-lived public video unless one is given.
+    #[arg(long, conflicts_with_all = ["input_file", "playlist", "list", "diff", "detect_language", "stats", "words", "dump_raw"])]
+    self_test: bool,
+
+    /// Convert a caption file already on disk (timedtext XML, srv3, json3, SRT, WebVTT,
+    /// or JSON written by -f json) instead of fetching from YouTube. The usual output and
+    /// processing options apply.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["video", "playlist", "list", "translate", "languages", "words"])]
+    input_file: Option<String>,
 
     /// Language codes (e.g., en, es, fr). Can specify multiple.
     #[arg(short, long)]
     languages: Option<Vec<String>>,
 
+    /// What to do when none of the requested languages (or the default) is available:
+    /// "none" fails, "any" uses the first available transcript, manual ones first
+    #[arg(long, value_name = "MODE", default_value = "none", value_parser = ["none", "any"])]
+    lang_fallback: String,
+
     /// Translate transcript to this language code
     #[arg(short, long)]
     translate: Option<String>,
 
-    /// Output format: json, text, txt, srt, or markdown
-    #[arg(short, long, default_value = "text")]
+    /// Write a bilingual text or markdown transcript: each cue in SRC followed by its
+    /// DST translation, e.g. "en,es". Uses YouTube's translation, or a DST track if there is one.
+    #[arg(long, value_name = "SRC,DST", conflicts_with_all = ["languages", "translate", "input_file", "words", "list", "cleanup", "stats", "dump_raw", "mdbook"])]
+    combine_languages: Option<String>,
+
+    /// How to translate: youtube (native, free) or chatgpt (used only when YouTube
+    /// has no translation into the target language; requires an OpenAI key)
+    #[arg(long, default_value = "youtube", value_parser = ["youtube", "chatgpt"])]
+    translate_via: String,
+
+    /// Output format: json, jsonl (ndjson), text, txt, srt, markdown, or html.
+    /// Several can be given comma-separated (e.g. json,srt,txt), or "all" for json, srt, txt and md
+    #[arg(short, long, alias = "formats", default_value = "text")]
     format: String,
 
     /// Show transcript text with timestamps (deprecated: timestamps removed by default)
     #[arg(long)]
     timestamps: bool,
 
-    /// List available transcripts instead of fetching
+    /// How --timestamps are shown: seconds ([65.00s]), mmss ([01:05]) or hms ([00:01:05])
+    #[arg(long, default_value = "seconds", value_parser = ["seconds", "mmss", "hms"])]
+    timestamps_format: String,
+
+    /// List available transcripts instead of fetching (as JSON with -f json)
     #[arg(long)]
     list: bool,
 
+    /// Print the video's original caption language and whether its captions are
+    /// manual or auto-generated (e.g. "en<TAB>manual<TAB>English"), without fetching them
+    #[arg(long, conflicts_with_all = ["list", "stats", "input_file", "words", "translate", "combine_languages", "dump_raw"])]
+    detect_language: bool,
+
+    /// Print the languages --translate accepts for the source track (the first of -l
+    /// the video has, default: en), one "code<TAB>name" per line, without fetching captions
+    #[arg(long, conflicts_with_all = ["list", "stats", "input_file", "words", "translate", "combine_languages", "detect_language", "dump_raw"])]
+    translate_list: bool,
+
+    /// Split markdown output by the video's chapters, each a "##" heading followed by
+    /// its cues. Chapters come from the video's chapter markers or description timestamps.
+    #[arg(long, conflicts_with_all = ["input_file", "words", "list", "stats", "combine_languages", "dump_raw"])]
+    chapters: bool,
+
+    /// Add a table of contents linking to the "##" sections of markdown output
+    /// (from --chapters or --cleanup), when there are at least two
+    #[arg(long, conflicts_with_all = ["list", "stats", "words", "detect_language", "dump_raw"])]
+    markdown_toc: bool,
+
+    /// Start markdown output with YAML front matter (title, url, date) for static site
+    /// generators such as Hugo and Jekyll
+    #[arg(long, conflicts_with_all = ["list", "stats", "words", "detect_language", "dump_raw", "append"])]
+    front_matter: bool,
+
+    /// Compare the transcript with another one and print a unified-style diff of
+    /// their cues. OTHER is a video ID or URL (fetched with the same -l languages)
+    /// or a transcript file; -o writes the diff to a file.
+    #[arg(long, value_name = "OTHER", conflicts_with_all = ["playlist", "list", "stats", "words", "translate", "combine_languages", "detect_language", "interactive", "cleanup", "dump_raw"])]
+    diff: Option<String>,
+
+    /// Print cue count, duration, word count, words per minute and average cue
+    /// duration as key=value lines instead of writing the transcript
+    #[arg(long, conflicts_with_all = ["list", "output", "name", "output_template", "format", "cleanup", "append", "mdbook", "words", "no_overwrite"])]
+    stats: bool,
+
+    /// Pick the transcript from a numbered menu when the video has several
+    /// (ignored when stdin isn't a terminal)
+    #[arg(short = 'i', long, conflicts_with_all = ["languages", "translate", "words", "list", "input_file"])]
+    interactive: bool,
+
     /// Delay between requests in milliseconds (default: 500ms)
     #[arg(long, default_value = "500")]
     delay: u64,
 
+    /// Randomize each delay by up to this percentage either way (0-100, default: 0),
+    /// so requests don't arrive at a fixed rhythm
+    #[arg(long, value_name = "PERCENT", default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100))]
+    jitter: u8,
+
     /// Clean up transcript using ChatGPT (requires OPENAI_API_KEY env var or --openai-key)
     #[arg(long)]
     cleanup: bool,
 
+    /// With --cleanup, clean each cue separately and keep its timing, so cleaned
+    /// SRT stays in sync (uses more tokens)
+    #[arg(long, requires = "cleanup")]
+    cleanup_preserve_timing: bool,
+
+    /// Stream ChatGPT's replies (--cleanup, --translate-via chatgpt) to stderr as they
+    /// are written, so a long cleanup shows progress
+    #[arg(long)]
+    stream: bool,
+
+    /// With --cleanup, keep filler words and repetitions instead of asking ChatGPT to remove them
+    #[arg(long, requires = "cleanup", conflicts_with_all = ["prompt", "prompt_text"])]
+    no_filler_removal: bool,
+
+    /// With --cleanup, keep mentions of products, websites and courses instead of
+    /// asking ChatGPT to remove them as promotional content
+    #[arg(long, requires = "cleanup", conflicts_with_all = ["prompt", "prompt_text"])]
+    keep_promotional: bool,
+
+    /// With --cleanup, send at most N characters of each transcript to ChatGPT,
+    /// cutting the rest with a warning, so one very long video can't blow the budget
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "cleanup",
+        conflicts_with = "cleanup_preserve_timing",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    max_input_chars: Option<u64>,
+
+    /// Language model service that --cleanup and --translate-via chatgpt use
+    #[arg(long, value_name = "PROVIDER", default_value = "openai", value_parser = ["openai"])]
+    llm_provider: String,
+
     /// OpenAI API key (alternative to OPENAI_API_KEY env var)
     #[arg(long)]
     openai_key: Option<String>,
 
-    /// Output file path (if not specified, outputs to stdout)
+    /// Send at most this many OpenAI requests per minute, evenly spaced, to stay under
+    /// the account's rate limit during long playlist runs
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    openai_rpm: Option<u32>,
+
+    /// Print the OpenAI tokens used to stderr after each video, plus a total for playlists
+    #[arg(long)]
+    show_usage: bool,
+
+    /// File with instructions that replace the built-in --cleanup prompt
+    #[arg(long, requires = "cleanup", conflicts_with = "prompt_text")]
+    prompt: Option<String>,
+
+    /// Instructions that replace the built-in --cleanup prompt
+    #[arg(long, requires = "cleanup")]
+    prompt_text: Option<String>,
+
+    /// Output file path (if not specified, outputs to stdout). "-" always means stdout,
+    /// even with -n or --playlist, which then print every video one after another.
     #[arg(short, long)]
     output: Option<String>,
 
@@ -52,6 +202,12 @@ struct Args {
     #[arg(short = 'n', long)]
     name: bool,
 
+    /// Build each output filename from a template, e.g. "{index:03}-{title}.{ext}".
+    /// Placeholders: {id}, {title}, {index}, {lang}, {ext}, {date}; {name:0N} zero-pads
+    /// to N characters. With -o DIR the file goes in that directory.
+    #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["name", "append", "mdbook"])]
+    output_template: Option<String>,
+
     /// Include video URL at the start of markdown output (only works with -f md/markdown)
     #[arg(short = 'u', long)]
     url: bool,
@@ -63,67 +219,791 @@ struct Args {
     /// Maximum number of videos to process in playlist mode (ignored in normal mode)
     #[arg(short = 'm', long)]
     max: Option<usize>,
+
+    /// Playlist processing order: none (playlist order) or reverse. Applied before --max,
+    /// so --sort reverse -m 5 takes the last five videos
+    #[arg(long, default_value = "none", value_parser = ["none", "reverse"])]
+    sort: String,
+
+    /// More log output: -v shows requests and retries, -vv also logs HTTP internals
+    #[arg(short = 'v', long, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Less log output: -q hides progress messages, -qq hides warnings too
+    #[arg(short = 'q', long, action = ArgAction::Count)]
+    quiet: u8,
+
+    /// User-Agent header to send to YouTube (defaults to a desktop browser agent)
+    #[arg(long)]
+    user_agent: Option<String>,
+
+    /// Proxy URL for YouTube requests (http://, https:// or socks5://)
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Talk to YouTube over HTTP/1.1 only, in case HTTP/2 connections get flagged as a bot
+    #[arg(long)]
+    http1_only: bool,
+
+    /// Keep fetched transcripts in DIR and, on later runs, only download the ones
+    /// YouTube reports as changed (via ETag / Last-Modified)
+    #[arg(long, value_name = "DIR")]
+    since_cache: Option<String>,
+
+    /// Retry failed requests (connection errors, 429, 5xx) this many times
+    #[arg(long, default_value = "0")]
+    retries: u32,
+
+    /// In playlist mode, stop retrying YouTube requests once this many retries have been
+    /// made across the whole run, so a long unattended job has a bounded runtime
+    /// (OpenAI requests aren't retried, so they don't draw on it)
+    #[arg(long, value_name = "N")]
+    max_retries_per_playlist: Option<u32>,
+
+    /// Give up on any network request (YouTube or OpenAI) that takes longer than this many seconds
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Proof-of-origin token for protected videos (appended as pot= to the transcript request)
+    #[arg(long)]
+    po_token: Option<String>,
+
+    /// Visitor data the PO token was generated for (sent with the InnerTube player request)
+    #[arg(long)]
+    visitor_data: Option<String>,
+
+    /// Netscape-format cookies.txt to load (for age-restricted or consent-gated videos)
+    #[arg(long)]
+    cookies: Option<String>,
+
+    /// In playlist mode, stop at the first video that fails and exit with its error code
+    /// (by default failures are logged and the remaining videos are still processed)
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Report progress as one JSON object per line on stderr (video_start, video_done,
+    /// video_error, ...) instead of the human "[i/total]" lines, for front ends.
+    /// Warnings and errors become JSON lines too, and nothing else is written to stderr.
+    #[arg(long, conflicts_with_all = ["stream", "show_usage", "interactive"])]
+    progress_json: bool,
+
+    /// In playlist mode, record finished videos in this JSON file and skip the ones
+    /// it already lists, so an interrupted run can pick up where it stopped
+    #[arg(long, value_name = "STATE.json")]
+    resume: Option<String>,
+
+    /// In playlist mode, only print the video IDs that would be processed (one per line)
+    #[arg(long, alias = "dry-run")]
+    list_playlist: bool,
+
+    /// Character encoding of the output: utf-8, utf-8-bom (UTF-8 with a byte order mark)
+    /// or utf-16le (with a byte order mark), for Windows tools that need them
+    #[arg(long, default_value = "utf-8", value_parser = ["utf-8", "utf-8-bom", "utf-16le"])]
+    encoding: String,
+
+    /// Gzip-compress file outputs, adding .gz to their names (stdout is left uncompressed)
+    #[arg(long, conflicts_with = "append")]
+    gzip: bool,
+
+    /// Write a .meta.json file next to each output with the video ID, title,
+    /// language, formats and fetch time
+    #[arg(long)]
+    sidecar: bool,
+
+    /// Skip a video when its output file already exists instead of overwriting it
+    #[arg(long)]
+    no_overwrite: bool,
+
+    /// In playlist mode, skip videos published before this date (YYYY-MM-DD, inclusive)
+    #[arg(long)]
+    since: Option<String>,
+
+    /// In playlist mode, skip videos published after this date (YYYY-MM-DD, inclusive)
+    #[arg(long)]
+    until: Option<String>,
+
+    /// Write the timedtext body YouTube returns, unparsed, to stdout or -o FILE (for debugging)
+    #[arg(long, hide = true, conflicts_with_all = ["words", "list", "translate", "input_file", "playlist"])]
+    dump_raw: bool,
+
+    /// Write JSON with the start time of every word, for tracks that carry word-level timing.
+    /// Cue processing options (--dedupe, --strip-bracketed, --start/--end, --offset) don't apply.
+    #[arg(long, conflicts_with_all = ["format", "translate", "cleanup", "dedupe", "drop_duplicate_cues", "fix_case", "strip_bracketed", "strip_timestamps_from_text", "replace", "list"])]
+    words: bool,
+
+    /// Write JSON with each cue's metadata: "kind" ("asr" or "manual") and, on
+    /// auto-generated tracks, the recognizer's "confidence" from 0 to 1.
+    /// Cue processing options (--dedupe, --strip-bracketed, --start/--end, --offset) don't apply.
+    #[arg(long, conflicts_with_all = ["format", "words", "translate", "cleanup", "dedupe", "drop_duplicate_cues", "fix_case", "strip_bracketed", "strip_timestamps_from_text", "replace", "list", "stats", "input_file", "dump_raw", "mdbook", "json_index", "combine_languages", "diff", "detect_language", "interactive"])]
+    json_verbose: bool,
+
+    /// Start text/markdown output with a header block: title, URL, duration, cue count, word count
+    #[arg(long)]
+    metadata: bool,
+
+    /// Remove words that rolling auto-captions repeat from the end of the previous cue
+    #[arg(long)]
+    dedupe: bool,
+
+    /// Keep cues that have timing but no text instead of dropping them, for
+    /// forced-alignment and gap-analysis tools that need the silent intervals
+    #[arg(long)]
+    keep_empty_cues: bool,
+
+    /// Drop a cue that repeats the previous cue's text exactly, extending the previous cue instead
+    #[arg(long)]
+    drop_duplicate_cues: bool,
+
+    /// Remove sound-event annotations such as [Music], [Applause] and (laughter),
+    /// dropping cues that contained nothing else
+    #[arg(long)]
+    strip_bracketed: bool,
+
+    /// Remove timecodes like 0:15 or (1:02:03) typed into the caption text, dropping
+    /// cues that contained nothing else. Only bracketed ones or those opening a cue
+    /// count, so "a 16:10 display" and ratios such as 3:1 are left alone.
+    #[arg(long)]
+    strip_timestamps_from_text: bool,
+
+    /// Recase ALL-CAPS auto captions: lowercase them and capitalize sentence starts
+    /// and "I" (a heuristic, so names come out lowercase)
+    #[arg(long)]
+    fix_case: bool,
+
+    /// Collapse repeated whitespace and fix spacing around . ? ! and , in the
+    /// transcript text (and in the text sent to ChatGPT)
+    #[arg(long)]
+    normalize: bool,
+
+    /// Rewrite cue text with a sed-style regex rule, e.g. '/jon smyth/Jon Smith/gi'.
+    /// Repeatable; rules run in order. Flags: g (every match), i, m, s, x.
+    #[arg(long, value_name = "/PATTERN/REPLACEMENT/FLAGS")]
+    replace: Vec<String>,
+
+    /// The --replace rules, compiled before anything is fetched
+    #[arg(skip)]
+    replacements: Vec<Replacement>,
+
+    /// Only keep cues overlapping the window starting at this many seconds
+    /// (defaults to the URL's t= parameter, if present)
+    #[arg(long)]
+    start: Option<f64>,
+
+    /// Only keep cues overlapping the window ending at this many seconds
+    #[arg(long)]
+    end: Option<f64>,
+
+    /// Drop cues shorter than this many seconds, e.g. 0.1 for zero-length flashes (default: 0, keep all)
+    #[arg(long, value_name = "SECONDS", default_value_t = 0.0)]
+    min_duration: f64,
+
+    /// Trim cues straddling --start/--end so they fit inside the window
+    #[arg(long)]
+    clamp: bool,
+
+    /// Only keep cues within a time range, e.g. "1:05-2:30" or "01:02:03-01:05:00"
+    #[arg(long, conflicts_with_all = ["start", "end"])]
+    clip: Option<String>,
+
+    /// In playlist mode, build an mdBook in this directory: one markdown chapter per
+    /// video under src/, headed by its title, and a SUMMARY.md linking them in order
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "format", "name", "append", "gzip", "words"])]
+    mdbook: Option<String>,
+
+    /// In playlist mode, write every video into the single -o FILE, each under a
+    /// section header with its title (text, markdown or jsonl only)
+    #[arg(long)]
+    append: bool,
+
+    /// Split file output into NAME.part01.EXT, NAME.part02.EXT, ... of N cues each,
+    /// or of N minutes of video with an "m" suffix (e.g. 30m). Each part starts with
+    /// a "Part 2 of 5" header in text and markdown output.
+    #[arg(long, value_name = "N|Nm", conflicts_with_all = ["append", "mdbook", "list", "stats", "detect_language"])]
+    split_every: Option<String>,
+
+    /// In markdown output, end each cue with a [▶](...&t=NNs) link that opens the
+    /// video at that moment
+    #[arg(long, conflicts_with = "input_file")]
+    append_url_per_cue: bool,
+
+    /// In SRT output, end each cue where the next one starts instead of letting them overlap
+    #[arg(long)]
+    fix_overlaps: bool,
+
+    /// Recompute cue durations from their text length at the video's speaking rate,
+    /// never running past the next cue, for auto captions with zero or uniform durations
+    #[arg(long)]
+    repace: bool,
+
+    /// In JSON and JSON Lines output, give each cue its 1-based "index" and an "id"
+    /// such as "dQw4w9WgXcQ-000123" that annotation tools and databases can key on
+    #[arg(long, conflicts_with = "words")]
+    json_index: bool,
+
+    /// Shift every timestamp by this many seconds (may be negative; cues are clamped at zero)
+    #[arg(long, allow_negative_numbers = true)]
+    offset: Option<f64>,
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    init_logging(args.verbose, args.quiet, args.progress_json);
 
+    let progress_json = args.progress_json;
     if let Err(e) = run(args).await {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+        if progress_json {
+            emit_progress(&ProgressEvent::Error {
+                error: e.to_string(),
+                exit_code: exit_code(&e),
+            });
+        } else {
+            eprintln!("Error: {}", e);
+        }
+        std::process::exit(exit_code(&e));
+    }
+}
+
+/// Process exit code for each error category, so scripts can branch on the failure type
+fn exit_code(error: &TranscriptError) -> i32 {
+    match error {
+        TranscriptError::VideoUnavailable(_)
+        | TranscriptError::VideoUnplayable(_, _)
+        | TranscriptError::AgeRestricted(_) => 2,
+        TranscriptError::TranscriptsDisabled(_)
+        | TranscriptError::NoTranscriptFound(_, _)
+        | TranscriptError::EmptyTranscript(_)
+        | TranscriptError::NotTranslatable(_)
+        | TranscriptError::TranslationLanguageNotAvailable(_) => 3,
+        TranscriptError::IpBlocked(_)
+        | TranscriptError::RateLimited(_)
+        | TranscriptError::RequestBlocked(_)
+        | TranscriptError::FailedToCreateConsentCookie(_)
+        | TranscriptError::PoTokenRequired(_) => 4,
+        TranscriptError::IoError(_) => 5,
+        TranscriptError::InvalidVideoId(_) | TranscriptError::InvalidArgument(_) => 6,
+        TranscriptError::HttpError(_)
+        | TranscriptError::Http { .. }
+        | TranscriptError::Json { .. }
+        | TranscriptError::XmlParseError(_)
+        | TranscriptError::JsonParseError(_)
+        | TranscriptError::SubtitleParseError(_)
+        | TranscriptError::YouTubeDataUnparsable(_) => 1,
     }
 }
 
-async fn run(args: Args) -> Result<(), TranscriptError> {
-    let api = YouTubeTranscript::with_delay(args.delay);
+/// Log to stderr: progress (info) as plain lines, everything else prefixed with its level
+///
+/// With `--progress-json`, each record is a `log` event instead, and the info lines
+/// are left out unless `-v` asks for them, since the progress events replace them.
+fn init_logging(verbose: u8, quiet: u8, progress_json: bool) {
+    let level = match (verbose, quiet) {
+        (0, 0) if progress_json => LevelFilter::Warn,
+        (0, 0) => LevelFilter::Info,
+        (0, 1) => LevelFilter::Warn,
+        (0, _) => LevelFilter::Error,
+        (1, _) => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    // Dependencies (reqwest, hyper) only get verbose at -vv
+    let dependency_level = if verbose >= 2 {
+        level
+    } else {
+        LevelFilter::Warn
+    };
+
+    env_logger::Builder::new()
+        .filter_level(dependency_level)
+        .filter_module("ytt", level)
+        .format(move |buf, record| {
+            if progress_json {
+                let event = ProgressEvent::Log {
+                    level: record.level().as_str().to_lowercase(),
+                    message: record.args().to_string(),
+                };
+                writeln!(buf, "{}", serde_json::to_string(&event).unwrap_or_default())
+            } else if record.level() == log::Level::Info {
+                writeln!(buf, "{}", record.args())
+            } else {
+                writeln!(buf, "[{}] {}", record.level(), record.args())
+            }
+        })
+        .init();
+}
+
+async fn run(mut args: Args) -> Result<(), TranscriptError> {
+    // A channel is fetched through its uploads playlist
+    if let (false, Some(video)) = (args.playlist, &args.video) {
+        args.playlist = YouTubeTranscript::is_channel_url(video);
+    }
+    // Checked here rather than with clap's `requires`, which runs before a channel
+    // URL has switched on playlist mode
+    if !args.playlist {
+        if let Some(flag) = playlist_only_flag(&args) {
+            return Err(TranscriptError::InvalidArgument(format!(
+                "{} only applies to a playlist or channel; add --playlist",
+                flag
+            )));
+        }
+    }
+    if let Some(clip) = &args.clip {
+        let (start, end) = parse_clip(clip)?;
+        args.start = Some(start);
+        args.end = Some(end);
+    }
+    args.replacements = args
+        .replace
+        .iter()
+        .map(|spec| Replacement::parse(spec))
+        .collect::<Result<_, _>>()?;
+    if requested_formats(&args.format).len() > 1 {
+        let separate_files = match &args.output {
+            Some(output_path) => output_is_directory(output_path),
+            None => args.name || args.playlist || args.output_template.is_some(),
+        };
+        if !separate_files {
+            return Err(TranscriptError::InvalidArgument(
+                "writing several formats needs -o DIRECTORY or -n so each format gets its own file"
+                    .to_string(),
+            ));
+        }
+        if args
+            .output_template
+            .as_deref()
+            .is_some_and(|template| !template.contains("{ext"))
+        {
+            return Err(TranscriptError::InvalidArgument(
+                "writing several formats with --output-template needs {ext} in the template so each format gets its own file"
+                    .to_string(),
+            ));
+        }
+    }
+    if args.append {
+        validate_append(&args)?;
+    }
+    // Whole srt, vtt, json or html documents printed back to back don't parse as one
+    if args.playlist && args.output.as_deref() == Some("-") {
+        let sectioned = ["text", "txt", "markdown", "md", "jsonl", "ndjson"];
+        if !requested_formats(&args.format)
+            .iter()
+            .all(|f| sectioned.contains(&f.as_str()))
+        {
+            return Err(TranscriptError::InvalidArgument(
+                "a playlist printed with -o - supports text, markdown or jsonl".to_string(),
+            ));
+        }
+    }
+    if let Some(pair) = &args.combine_languages {
+        parse_language_pair(pair)?;
+        let bilingual = ["text", "txt", "markdown", "md"];
+        if !requested_formats(&args.format)
+            .iter()
+            .all(|f| bilingual.contains(&f.as_str()))
+        {
+            return Err(TranscriptError::InvalidArgument(
+                "--combine-languages writes text or markdown only".to_string(),
+            ));
+        }
+    }
+    if let Some(split_every) = &args.split_every {
+        SplitEvery::parse(split_every)?;
+        let no_files =
+            args.output.is_none() && !args.name && !args.playlist && args.output_template.is_none();
+        if no_files || args.output.as_deref() == Some("-") {
+            return Err(TranscriptError::InvalidArgument(
+                "--split-every writes several files; add -o FILE or -n".to_string(),
+            ));
+        }
+    }
+    if args.stream && !args.cleanup && args.translate_via != "chatgpt" {
+        return Err(TranscriptError::InvalidArgument(
+            "--stream only applies to ChatGPT replies; add --cleanup or --translate-via chatgpt"
+                .to_string(),
+        ));
+    }
+    if args.markdown_toc
+        && !requested_formats(&args.format)
+            .iter()
+            .any(|f| f == "markdown" || f == "md")
+    {
+        return Err(TranscriptError::InvalidArgument(
+            "--markdown-toc only applies to markdown output; add -f md".to_string(),
+        ));
+    }
+    if args.append_url_per_cue
+        && !requested_formats(&args.format)
+            .iter()
+            .any(|f| f == "markdown" || f == "md")
+    {
+        return Err(TranscriptError::InvalidArgument(
+            "--append-url-per-cue only applies to markdown output; add -f md".to_string(),
+        ));
+    }
+    if args.front_matter
+        && !requested_formats(&args.format)
+            .iter()
+            .any(|f| f == "markdown" || f == "md")
+    {
+        return Err(TranscriptError::InvalidArgument(
+            "--front-matter only applies to markdown output; add -f md".to_string(),
+        ));
+    }
+    if args.chapters {
+        if !requested_formats(&args.format)
+            .iter()
+            .any(|f| f == "markdown" || f == "md")
+        {
+            return Err(TranscriptError::InvalidArgument(
+                "--chapters only applies to markdown output; add -f md".to_string(),
+            ));
+        }
+        if args.cleanup && !args.cleanup_preserve_timing {
+            return Err(TranscriptError::InvalidArgument(
+                "--chapters needs cue timing, so with --cleanup it also needs --cleanup-preserve-timing"
+                    .to_string(),
+            ));
+        }
+    }
+    if let Some(template) = &args.output_template {
+        if args
+            .output
+            .as_deref()
+            .is_some_and(|path| !output_is_directory(path))
+        {
+            return Err(TranscriptError::InvalidArgument(
+                "--output-template can only be combined with -o DIRECTORY".to_string(),
+            ));
+        }
+        // Catch unknown placeholders before anything is fetched
+        expand_output_template(template, &TemplateFields::example())?;
+    }
+    if let Some(book_dir) = &args.mdbook {
+        args.format = "md".to_string();
+        let src_dir = Path::new(book_dir).join("src");
+        std::fs::create_dir_all(&src_dir).map_err(|e| {
+            TranscriptError::IoError(format!("Failed to create {}: {}", src_dir.display(), e))
+        })?;
+    }
+    if let Some(path) = &args.prompt {
+        let prompt = std::fs::read_to_string(path).map_err(|e| {
+            TranscriptError::IoError(format!("Failed to read prompt file {}: {}", path, e))
+        })?;
+        args.prompt_text = Some(prompt);
+    }
+    for date in [&args.since, &args.until].into_iter().flatten() {
+        validate_date(date)?;
+    }
+    if let (None, false, Some(video)) = (args.start, args.playlist, &args.video) {
+        args.start = YouTubeTranscript::extract_start_time(video);
+    }
+    if let (Some(start), Some(end)) = (args.start, args.end) {
+        if start >= end {
+            return Err(TranscriptError::InvalidArgument(format!(
+                "--start ({}) must be before --end ({})",
+                start, end
+            )));
+        }
+    }
+
+    let accept_language = args
+        .languages
+        .as_ref()
+        .and_then(|langs| langs.first())
+        .map(|lang| lang.as_str())
+        .unwrap_or("en-US");
+    let mut builder = YouTubeTranscript::builder()
+        .delay(args.delay)
+        .jitter(args.jitter)
+        .retries(args.retries)
+        .keep_empty_cues(args.keep_empty_cues)
+        .any_language_fallback(args.lang_fallback == "any")
+        .http1_only(args.http1_only)
+        .accept_language(accept_language);
+    if let Some(budget) = args.max_retries_per_playlist {
+        builder = builder.retry_budget(budget);
+    }
+    if let Some(user_agent) = &args.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+    if let Some(proxy) = &args.proxy {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(cookies) = &args.cookies {
+        builder = builder.cookies(cookies);
+    }
+    if let Some(dir) = &args.since_cache {
+        builder = builder.cache_dir(dir);
+    }
+    if let Some(timeout) = args.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout));
+    }
+    if let Some(po_token) = &args.po_token {
+        builder = builder.po_token(po_token);
+    }
+    if let Some(visitor_data) = &args.visitor_data {
+        builder = builder.visitor_data(visitor_data);
+    }
+    let api = builder.build()?;
+
+    let chatgpt = ChatGptSlot::default();
+    if args.cleanup {
+        chatgpt.get_or_build(&args)?;
+    }
+    let chatgpt = &chatgpt;
+
+    if args.self_test {
+        return self_test(&api, args.video.as_deref().unwrap_or(SELF_TEST_VIDEO)).await;
+    }
+
+    if let Some(other) = &args.diff {
+        let this = args
+            .input_file
+            .as_deref()
+            .or(args.video.as_deref())
+            .unwrap_or_default();
+        let (this_label, this_items) = diff_source(&api, &args, this).await?;
+        let (other_label, other_items) = diff_source(&api, &args, other).await?;
+        let diffs = diff_transcripts(&this_items, &other_items);
+        let mut writer = single_output(&args).writer()?;
+        write!(writer, "{}", format_diff(&this_label, &other_label, &diffs))?;
+        return Ok(());
+    }
+
+    if let Some(input_file) = &args.input_file {
+        let transcript = read_input_file(input_file, args.keep_empty_cues)?;
+        let video_id = transcript.video_id.clone();
+        let usage_before = chatgpt.usage();
+        return process_transcript(
+            chatgpt.get(),
+            &args,
+            transcript,
+            &video_id,
+            None,
+            usage_before,
+        )
+        .await;
+    }
+    let video = args.video.clone().unwrap_or_default();
 
     // Handle playlist mode
     if args.playlist {
-        let playlist_id = YouTubeTranscript::extract_playlist_id(&args.video)?;
-        eprintln!("Fetching video IDs from playlist: {}", playlist_id);
+        let playlist_id = if YouTubeTranscript::is_channel_url(&video) {
+            info!("Resolving channel: {}", video);
+            api.get_channel_uploads_playlist_id(&video).await?
+        } else {
+            YouTubeTranscript::extract_playlist_id(&video)?
+        };
+        info!("Fetching video IDs from playlist: {}", playlist_id);
         let video_ids = api.get_playlist_video_ids(&playlist_id).await?;
-        eprintln!("Found {} videos in playlist", video_ids.len());
+        info!("Found {} videos in playlist", video_ids.len());
 
-        // Limit to max number if specified
-        let videos_to_process: Vec<&String> = if let Some(max) = args.max {
-            let limit = max.min(video_ids.len());
-            if limit < video_ids.len() {
-                eprintln!("Processing first {} videos (limited by --max)", limit);
-            }
-            video_ids.iter().take(limit).collect()
+        let mut resume_state = match &args.resume {
+            Some(path) => Some(ResumeState::load(path)?),
+            None => None,
+        };
+
+        let mut video_ids = if args.since.is_some() || args.until.is_some() {
+            let date_filter = DateFilter {
+                since: args.since.as_deref(),
+                until: args.until.as_deref(),
+                // An uploads playlist lists the newest video first
+                newest_first: playlist_id.starts_with("UU"),
+                // Reversed, the --max videos come from the end of the playlist
+                max: args.max.filter(|_| args.sort != "reverse"),
+            };
+            filter_by_publish_date(&api, video_ids, &date_filter, resume_state.as_ref()).await
         } else {
-            video_ids.iter().collect()
+            video_ids
         };
+        if args.sort == "reverse" {
+            video_ids.reverse();
+        }
+        let playlist_len = video_ids.len();
+        let videos_to_process = select_playlist_videos(video_ids, resume_state.as_ref(), args.max);
+
+        if args.list_playlist {
+            for (_, video_id) in &videos_to_process {
+                println!("{}", video_id);
+            }
+            return Ok(());
+        }
+
+        if args.append && args.resume.is_none() {
+            // Start from an empty file; each video is then appended to it
+            if let Some(output_path) = &args.output {
+                File::create(output_path).map_err(|e| {
+                    TranscriptError::IoError(format!(
+                        "Failed to create file {}: {}",
+                        output_path, e
+                    ))
+                })?;
+            }
+        }
 
         let total = videos_to_process.len();
-        for (index, video_id) in videos_to_process.iter().enumerate() {
-            eprintln!("\n[{}/{}] Processing video: {}", index + 1, total, video_id);
-            if let Err(e) = process_single_video(&api, &args, video_id, Some(index + 1), Some(total)).await {
-                eprintln!("Error processing video {}: {}", video_id, e);
+        let mut progress = PlaylistProgress::new(total);
+        if args.progress_json {
+            emit_progress(&ProgressEvent::RunStart { total });
+        }
+        for (index, (position, video_id)) in videos_to_process.iter().enumerate() {
+            if args.progress_json {
+                emit_progress(&ProgressEvent::VideoStart {
+                    index: index + 1,
+                    total,
+                    id: video_id,
+                });
+            } else {
+                info!(
+                    "[{}/{}] Processing video: {} ({})",
+                    index + 1,
+                    total,
+                    video_id,
+                    progress.status()
+                );
+            }
+            let video_started = Instant::now();
+            let result = process_single_video(
+                &api,
+                chatgpt,
+                &args,
+                video_id,
+                Some(*position),
+                Some(playlist_len),
+            )
+            .await;
+            progress.record(video_started.elapsed(), result.is_ok());
+            if args.progress_json {
+                emit_progress(&ProgressEvent::finished(
+                    index + 1,
+                    total,
+                    video_id,
+                    video_started,
+                    &result,
+                ));
+            }
+            if let Err(e) = result {
+                if args.fail_fast {
+                    if args.progress_json {
+                        emit_progress(&ProgressEvent::RunStopped {
+                            index: index + 1,
+                            total,
+                            succeeded: progress.succeeded,
+                        });
+                    } else {
+                        error!(
+                            "Stopping at video {} of {} ({} succeeded before it failed)",
+                            index + 1,
+                            total,
+                            progress.succeeded
+                        );
+                    }
+                    return Err(e);
+                }
+                if !args.progress_json {
+                    error!("Error processing video {}: {}", video_id, e);
+                }
                 // Continue with next video instead of failing completely
+                progress.record_skipped(video_id, e);
                 continue;
             }
+            if let Some(state) = resume_state.as_mut() {
+                state.mark_completed(video_id)?;
+            }
+        }
+        if args.progress_json {
+            emit_progress(&ProgressEvent::RunDone {
+                total,
+                succeeded: progress.succeeded,
+                failed: progress.failed,
+                seconds: progress.started.elapsed().as_secs_f64(),
+            });
+        } else {
+            info!("{}", progress.summary());
+            if let Some(report) = progress.skipped_report() {
+                eprintln!("{}", report);
+            }
+        }
+        if let Some(book_dir) = &args.mdbook {
+            write_mdbook_summary(Path::new(book_dir), &playlist_id)?;
+        }
+        if let (true, Some(chatgpt)) = (args.show_usage, chatgpt.get()) {
+            eprintln!("ChatGPT usage total: {}", chatgpt.usage());
         }
         return Ok(());
     }
 
     // Single video mode
-    let video_id = YouTubeTranscript::extract_video_id(&args.video)?;
-    process_single_video(&api, &args, &video_id, None, None).await
+    let video_id = YouTubeTranscript::extract_video_id(&video)?;
+    if !args.progress_json {
+        return process_single_video(&api, chatgpt, &args, &video_id, None, None).await;
+    }
+    emit_progress(&ProgressEvent::VideoStart {
+        index: 1,
+        total: 1,
+        id: &video_id,
+    });
+    let video_started = Instant::now();
+    let result = process_single_video(&api, chatgpt, &args, &video_id, None, None).await;
+    emit_progress(&ProgressEvent::finished(
+        1,
+        1,
+        &video_id,
+        video_started,
+        &result,
+    ));
+    result
 }
 
 async fn process_single_video(
     api: &YouTubeTranscript,
+    chatgpt: &ChatGptSlot,
     args: &Args,
     video_id: &str,
     video_index: Option<usize>,
     total_videos: Option<usize>,
 ) -> Result<(), TranscriptError> {
+    if args.detect_language {
+        let transcript_list = api.list_transcripts(video_id).await?;
+        let track = transcript_list
+            .original_language()
+            .ok_or_else(|| TranscriptError::NoTranscriptFound(video_id.to_string(), vec![]))?;
+        let line = format_detected_language(track);
+        match video_index {
+            Some(_) => println!("{}\t{}", video_id, line),
+            None => println!("{}", line),
+        }
+        return Ok(());
+    }
+    if args.translate_list {
+        let transcript_list = api.list_transcripts(video_id).await?;
+        let source_langs = translation_sources(args);
+        let targets = transcript_list.translation_targets(&source_langs)?;
+        if requested_formats(&args.format) == ["json"] {
+            println!("{}", serde_json::to_string_pretty(&targets)?);
+            return Ok(());
+        }
+        for target in targets {
+            match video_index {
+                Some(_) => println!(
+                    "{}\t{}\t{}",
+                    video_id, target.language_code, target.language
+                ),
+                None => println!("{}\t{}", target.language_code, target.language),
+            }
+        }
+        return Ok(());
+    }
     if args.list {
         let transcript_list = api.list_transcripts(video_id).await?;
+        if requested_formats(&args.format) == ["json"] {
+            println!("{}", serde_json::to_string_pretty(&transcript_list)?);
+            return Ok(());
+        }
         if let (Some(idx), Some(total)) = (video_index, total_videos) {
-            println!("[{}/{}] Available transcripts for video: {}", idx, total, video_id);
+            println!(
+                "[{}/{}] Available transcripts for video: {}",
+                idx, total, video_id
+            );
         } else {
             println!("Available transcripts for video: {}", video_id);
         }
@@ -144,19 +1024,168 @@ async fn process_single_video(
         return Ok(());
     }
 
+    // When the output paths don't depend on the fetched title or language, an
+    // existing output can be detected without fetching anything
+    let template_needs_fetch = args
+        .output_template
+        .as_deref()
+        .is_some_and(|template| template.contains("{title") || template.contains("{lang"));
+    if args.no_overwrite
+        && !args.name
+        && !template_needs_fetch
+        && args.mdbook.is_none()
+        && args.translate.is_none()
+        && args.languages.is_none()
+    {
+        let format = if args.words || args.json_verbose {
+            "json".to_string()
+        } else {
+            args.format.clone()
+        };
+        let mut all_exist = true;
+        for format in requested_formats(&format) {
+            let output_dest = output_destination(args, video_id, None, "", video_index, &format)?;
+            all_exist &= output_exists(&output_dest);
+        }
+        if all_exist {
+            info!("Skipping video {}: output already exists", video_id);
+            return Ok(());
+        }
+    }
+
     if video_index.is_none() {
-        println!("Fetching transcript for video: {}", video_id);
+        info!("Fetching transcript for video: {}", video_id);
     }
 
-    let transcript = if let Some(target_lang) = &args.translate {
-        let source_langs: Vec<&str> = args
+    if args.dump_raw {
+        let lang_codes: Option<Vec<&str>> = args
+            .languages
+            .as_ref()
+            .map(|v| v.iter().map(|s| s.as_str()).collect());
+        let body = api.fetch_raw_xml(video_id, lang_codes).await?;
+        let mut writer = single_output(args).writer()?;
+        writer.write_all(body.as_bytes())?;
+        return Ok(());
+    }
+
+    let usage_before = chatgpt.usage();
+
+    if args.words {
+        let lang_codes: Option<Vec<&str>> = args
+            .languages
+            .as_ref()
+            .map(|v| v.iter().map(|s| s.as_str()).collect());
+        let transcript = api.fetch_transcript_words(video_id, lang_codes).await?;
+        if transcript
+            .transcript
+            .iter()
+            .all(|item| item.words.is_empty())
+        {
+            warn!(
+                "The {} transcript has no word-level timing",
+                transcript.language_code
+            );
+        }
+        let output_dest = output_destination(
+            args,
+            video_id,
+            transcript.title.as_deref(),
+            &transcript.language_code,
+            video_index,
+            "json",
+        )?;
+        if args.no_overwrite && output_exists(&output_dest) {
+            info!("Skipping video {}: output already exists", video_id);
+            return Ok(());
+        }
+        let encoding = OutputEncoding::from_name(&args.encoding);
+        output_words_json(&transcript.transcript, &output_dest.encoded(encoding))?;
+        if args.sidecar {
+            let sidecar = Sidecar::new(
+                video_id,
+                transcript.title.as_deref(),
+                &transcript.language,
+                &transcript.language_code,
+                transcript.is_generated,
+            );
+            write_sidecars(
+                &sidecar_targets(&[("json".to_string(), output_dest)]),
+                sidecar,
+            )?;
+        }
+        return Ok(());
+    }
+
+    if args.json_verbose {
+        let lang_codes: Option<Vec<&str>> = args
             .languages
             .as_ref()
-            .map(|v| v.iter().map(|s| s.as_str()).collect())
-            .unwrap_or_else(|| vec!["en"]);
-        api.translate_transcript(video_id, &source_langs, target_lang)
-            .await?
+            .map(|v| v.iter().map(|s| s.as_str()).collect());
+        let transcript = api.fetch_transcript_verbose(video_id, lang_codes).await?;
+        let output_dest = output_destination(
+            args,
+            video_id,
+            transcript.title.as_deref(),
+            &transcript.language_code,
+            video_index,
+            "json",
+        )?;
+        if args.no_overwrite && output_exists(&output_dest) {
+            info!("Skipping video {}: output already exists", video_id);
+            return Ok(());
+        }
+        let encoding = OutputEncoding::from_name(&args.encoding);
+        output_verbose_json(&transcript.transcript, &output_dest.encoded(encoding))?;
+        if args.sidecar {
+            let sidecar = Sidecar::new(
+                video_id,
+                transcript.title.as_deref(),
+                &transcript.language,
+                &transcript.language_code,
+                transcript.is_generated,
+            );
+            write_sidecars(
+                &sidecar_targets(&[("json".to_string(), output_dest)]),
+                sidecar,
+            )?;
+        }
+        return Ok(());
+    }
+
+    if let Some(pair) = &args.combine_languages {
+        let (source_lang, target_lang) = parse_language_pair(pair)?;
+        return write_combined_languages(
+            api,
+            args,
+            video_id,
+            video_index,
+            source_lang,
+            target_lang,
+        )
+        .await;
+    }
+
+    let transcript = if let Some(target_lang) = &args.translate {
+        let source_langs = translation_sources(args);
+        match api
+            .translate_transcript(video_id, &source_langs, target_lang)
+            .await
+        {
+            Err(
+                TranscriptError::TranslationLanguageNotAvailable(_)
+                | TranscriptError::NotTranslatable(_),
+            ) if args.translate_via == "chatgpt" => {
+                let chatgpt = chatgpt.get_or_build(args)?;
+                translate_with_chatgpt(api, chatgpt, video_id, &source_langs, target_lang).await?
+            }
+            result => result?,
+        }
+    } else if args.interactive && io::stdin().is_terminal() {
+        fetch_selected_transcript(api, video_id).await?
     } else {
+        if args.interactive {
+            warn!("stdin is not a terminal, using the default transcript");
+        }
         let lang_codes: Option<Vec<&str>> = args
             .languages
             .as_ref()
@@ -164,311 +1193,2626 @@ async fn process_single_video(
         api.fetch_transcript(video_id, lang_codes).await?
     };
 
-    // Determine if we need markdown formatting from ChatGPT
-    let format_markdown = args.cleanup
-        && (args.format.to_lowercase() == "markdown" || args.format.to_lowercase() == "md");
+    process_transcript(
+        chatgpt.get(),
+        args,
+        transcript,
+        video_id,
+        video_index,
+        usage_before,
+    )
+    .await
+}
 
-    // If cleanup is requested, send to ChatGPT first
-    let transcript_items = if args.cleanup {
-        if video_index.is_none() {
-            eprintln!("Cleaning up transcript with ChatGPT...");
+/// The run's ChatGPT client, shared by every video so token usage and the
+/// `--openai-rpm` limit cover the whole run. `--cleanup` builds it before anything
+/// is fetched, so a missing key is reported up front; `--translate-via chatgpt`
+/// builds it the first time YouTube can't translate, so runs YouTube handles
+/// need no key.
+#[derive(Default)]
+struct ChatGptSlot {
+    client: OnceLock<AnyChatGPT>,
+}
+
+impl ChatGptSlot {
+    /// The client, if it has been built
+    fn get(&self) -> Option<&AnyChatGPT> {
+        self.client.get()
+    }
+
+    fn get_or_build(&self, args: &Args) -> Result<&AnyChatGPT, TranscriptError> {
+        if let Some(chatgpt) = self.client.get() {
+            return Ok(chatgpt);
         }
-        let transcript_text: String = transcript
-            .transcript
-            .iter()
-            .map(|item| item.text.as_str())
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        let chatgpt = ChatGPT::new(args.openai_key.clone())?;
-        let cleaned_text = chatgpt
-            .cleanup_transcript(&transcript_text, format_markdown)
-            .await?;
-
-        // For cleanup, output the cleaned text directly as a single item
-        // This preserves the cleaned flow better than trying to split it back
-        vec![TranscriptItem {
-            text: cleaned_text,
-            start: transcript
-                .transcript
-                .first()
-                .map(|i| i.start)
-                .unwrap_or(0.0),
-            duration: transcript.transcript.iter().map(|i| i.duration).sum(),
-        }]
-    } else {
-        transcript.transcript
-    };
+        let chatgpt = build_chatgpt(args)?;
+        Ok(self.client.get_or_init(|| chatgpt))
+    }
 
-    // Determine output destination
-    // For playlists, if -o is a directory or -n is used, each video gets its own file
-    let output_dest = if let Some(ref output_path) = args.output {
-        let path = Path::new(output_path);
-        
-        // Check if the path is a directory:
-        // 1. If it exists and is a directory
-        // 2. If it ends with a path separator (directory-like)
-        let is_directory = if path.exists() {
-            path.is_dir()
-        } else {
-            // If path doesn't exist, check if it ends with a separator (directory-like)
-            let sep = std::path::MAIN_SEPARATOR;
-            output_path.ends_with(sep) || output_path.ends_with('/')
-        };
-        
-        if is_directory && args.name {
-            // Combine directory with title as filename
-            let title = transcript.title.as_ref()
-                .ok_or_else(|| TranscriptError::YouTubeDataUnparsable(
-                    "Failed to extract video title".to_string()
-                ))?;
-            let sanitized_title = sanitize_filename(title);
-            let extension = match args.format.to_lowercase().as_str() {
-                "json" => "json",
-                "srt" => "srt",
-                "markdown" | "md" => "md",
-                "text" | "txt" => "txt",
-                _ => "txt",
-            };
-            let filename = format!("{}.{}", sanitized_title, extension);
-            let combined_path = path.join(filename);
-            OutputDestination::File(combined_path.to_string_lossy().to_string())
-        } else if is_directory && video_index.is_some() {
-            // For playlist mode with directory output, use video_id as filename
-            let extension = match args.format.to_lowercase().as_str() {
-                "json" => "json",
-                "srt" => "srt",
-                "markdown" | "md" => "md",
-                "text" | "txt" => "txt",
-                _ => "txt",
-            };
-            let filename = format!("{}.{}", video_id, extension);
-            let combined_path = path.join(filename);
-            OutputDestination::File(combined_path.to_string_lossy().to_string())
-        } else {
-            // Use the path as-is (either it's a file path, or -n wasn't specified)
-            // For playlist mode, this would overwrite, so we should handle it differently
-            if video_index.is_some() {
-                // In playlist mode with a file path, append video_id
-                let path_buf = Path::new(output_path);
-                let stem = path_buf.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
-                let extension = path_buf.extension().and_then(|s| s.to_str()).unwrap_or("txt");
-                let parent = path_buf.parent().unwrap_or(Path::new("."));
-                let new_filename = format!("{}_{}.{}", stem, video_id, extension);
-                let combined_path = parent.join(new_filename);
-                OutputDestination::File(combined_path.to_string_lossy().to_string())
-            } else {
-                OutputDestination::File(output_path.clone())
-            }
+    /// Tokens used so far, zero before the client is built
+    fn usage(&self) -> Usage {
+        self.get().map(AnyChatGPT::usage).unwrap_or_default()
+    }
+}
+
+/// A ChatGPT client for whichever `--llm-provider` was chosen
+type AnyChatGPT = ChatGPT<Box<dyn LlmProvider>>;
+
+fn build_chatgpt(args: &Args) -> Result<AnyChatGPT, TranscriptError> {
+    let mut chatgpt = match args.llm_provider.as_str() {
+        "openai" => build_openai(args)?.boxed(),
+        other => {
+            return Err(TranscriptError::InvalidArgument(format!(
+                "unknown --llm-provider '{}'",
+                other
+            )))
         }
-    } else if args.name {
-        // Use video title as basename in current directory
-        let title = transcript.title.as_ref()
-            .ok_or_else(|| TranscriptError::YouTubeDataUnparsable(
-                "Failed to extract video title".to_string()
-            ))?;
-        let sanitized_title = sanitize_filename(title);
-        let extension = match args.format.to_lowercase().as_str() {
-            "json" => "json",
-            "srt" => "srt",
-            "markdown" | "md" => "md",
-            "text" | "txt" => "txt",
-            _ => "txt",
-        };
-        let output_path = format!("{}.{}", sanitized_title, extension);
-        OutputDestination::File(output_path)
-    } else if video_index.is_some() {
-        // Playlist mode without -o or -n: use video_id as filename
-        let extension = match args.format.to_lowercase().as_str() {
-            "json" => "json",
-            "srt" => "srt",
-            "markdown" | "md" => "md",
-            "text" | "txt" => "txt",
-            _ => "txt",
-        };
-        let output_path = format!("{}.{}", video_id, extension);
-        OutputDestination::File(output_path)
-    } else {
-        OutputDestination::Stdout
     };
+    if let Some(prompt) = &args.prompt_text {
+        chatgpt = chatgpt.with_cleanup_prompt(prompt);
+    }
+    Ok(chatgpt
+        .with_filler_removal(!args.no_filler_removal)
+        .with_promotional_removal(!args.keep_promotional))
+}
 
-    let video_url = if args.url {
-        Some(format!("https://www.youtube.com/watch?v={}", video_id))
-    } else {
-        None
-    };
-    let video_title = if args.url {
-        transcript.title.as_ref().map(|s| s.as_str())
+/// The OpenAI client, with the `--timeout`, `--openai-rpm` and `--stream` settings
+fn build_openai(args: &Args) -> Result<ChatGPT, TranscriptError> {
+    let mut chatgpt = ChatGPT::new(args.openai_key.clone())?;
+    if let Some(timeout) = args.timeout {
+        chatgpt = chatgpt.with_timeout(Duration::from_secs(timeout));
+    }
+    if let Some(rpm) = args.openai_rpm {
+        chatgpt = chatgpt.with_requests_per_minute(rpm);
+    }
+    if args.stream {
+        chatgpt = chatgpt.with_streaming(|delta| eprint!("{}", delta));
+    }
+    Ok(chatgpt)
+}
+
+/// Video `--self-test` checks by default: public for many years, with manual English captions
+const SELF_TEST_VIDEO: &str = "dQw4w9WgXcQ";
+
+/// `--self-test`: run one video through the whole pipeline, printing each step as it
+/// passes and a final PASS, or FAIL with the error of the step that broke
+async fn self_test(api: &YouTubeTranscript, video: &str) -> Result<(), TranscriptError> {
+    let check = |step: &str, detail: &str| println!("ok  {:<8} {}", step, detail);
+    let result = async {
+        let video_id = YouTubeTranscript::extract_video_id(video)?;
+        check("extract", &video_id);
+
+        let (list, transcript) = api.fetch_transcript_with_list(&video_id, None).await?;
+        let tracks = list.manually_created.len() + list.generated.len();
+        check("list", &format!("{} caption tracks", tracks));
+        check(
+            "fetch",
+            &format!(
+                "{} ({}), {} cues",
+                transcript.language,
+                transcript.language_code,
+                transcript.transcript.len()
+            ),
+        );
+
+        // Write SRT and read it back, so formatting and parsing are checked together
+        let path = std::env::temp_dir().join(format!("ytt-self-test-{}.srt", std::process::id()));
+        output_srt(
+            &transcript.transcript,
+            &OutputDestination::File(path.to_string_lossy().to_string()),
+        )?;
+        let srt = std::fs::read_to_string(&path);
+        let _ = std::fs::remove_file(&path);
+        let round_trip = parse_srt(&srt?).map_err(TranscriptError::SubtitleParseError)?;
+        if round_trip.len() != transcript.transcript.len() {
+            return Err(TranscriptError::SubtitleParseError(format!(
+                "SRT round trip kept {} of {} cues",
+                round_trip.len(),
+                transcript.transcript.len()
+            )));
+        }
+        check("format", "SRT written and parsed back");
+        Ok(())
+    }
+    .await;
+
+    match &result {
+        Ok(()) => println!("PASS"),
+        Err(e) => println!("FAIL: {}", e),
+    }
+    result
+}
+
+/// One side of a `--diff`: a transcript file, or else a video fetched with `-l`,
+/// with the label it is shown under
+async fn diff_source(
+    api: &YouTubeTranscript,
+    args: &Args,
+    source: &str,
+) -> Result<(String, Vec<TranscriptItem>), TranscriptError> {
+    if Path::new(source).is_file() {
+        let transcript = read_input_file(source, args.keep_empty_cues)?;
+        return Ok((source.to_string(), transcript.transcript));
+    }
+    let video_id = YouTubeTranscript::extract_video_id(source)?;
+    let lang_codes: Option<Vec<&str>> = args
+        .languages
+        .as_ref()
+        .map(|v| v.iter().map(|s| s.as_str()).collect());
+    let transcript = api.fetch_transcript(&video_id, lang_codes).await?;
+    let kind = if transcript.is_generated {
+        "auto"
     } else {
-        None
+        "manual"
     };
+    let label = format!("{} ({}, {})", video_id, transcript.language_code, kind);
+    Ok((label, transcript.transcript))
+}
 
-    match args.format.to_lowercase().as_str() {
-        "json" => output_json(&transcript_items, &output_dest)?,
-        "srt" => output_srt(&transcript_items, &output_dest)?,
-        "text" | "txt" => {
-            if args.timestamps {
-                output_text(&transcript_items, &output_dest, video_url.as_deref(), video_title)?;
-            } else {
-                output_text_only(&transcript_items, &output_dest, video_url.as_deref(), video_title)?;
+/// `--diff` output: `---`/`+++` headers naming the transcripts, then every cue
+/// prefixed with ` ` (the same words in both), `-` (only in the first, or its side
+/// of a reworded cue) or `+` (only in the second). Unchanged cues that start a
+/// second or more apart are marked with the drift.
+fn format_diff(a_label: &str, b_label: &str, diffs: &[Diff]) -> String {
+    let line = |prefix: char, item: &TranscriptItem| {
+        format!(
+            "{} [{}] {}\n",
+            prefix,
+            format_timecode(item.start, '.', false),
+            item.text
+        )
+    };
+    let mut output = format!("--- {}\n+++ {}\n", a_label, b_label);
+    for diff in diffs {
+        match diff {
+            Diff::Aligned { a, b, .. } if diff.is_changed() => {
+                output.push_str(&line('-', a));
+                output.push_str(&line('+', b));
+            }
+            Diff::Aligned { a, b, .. } => {
+                let drift = b.start - a.start;
+                output.push_str(line(' ', a).trim_end());
+                if drift.abs() >= 1.0 {
+                    output.push_str(&format!(" ({:+.1}s)", drift));
+                }
+                output.push('\n');
             }
+            Diff::Delete(item) => output.push_str(&line('-', item)),
+            Diff::Insert(item) => output.push_str(&line('+', item)),
         }
-        "markdown" | "md" => {
-            let video_title = if args.url {
-                transcript.title.as_ref().map(|s| s.as_str())
-            } else {
-                None
-            };
-            output_markdown(&transcript_items, &output_dest, args.timestamps, video_url.as_deref(), video_title)?;
+    }
+    output
+}
+
+/// Source languages for `--translate`: the `-l` languages, or English
+fn translation_sources(args: &Args) -> Vec<&str> {
+    args.languages
+        .as_ref()
+        .map(|v| v.iter().map(|s| s.as_str()).collect())
+        .unwrap_or_else(|| vec!["en"])
+}
+
+/// `--detect-language` answer: language code, `manual` or `auto`, and language name,
+/// tab-separated so the code can be cut out for `-l`
+fn format_detected_language(track: &TranscriptInfo) -> String {
+    let kind = if track.is_generated { "auto" } else { "manual" };
+    format!("{}\t{}\t{}", track.language_code, kind, track.language)
+}
+
+/// Let the user pick one of the video's transcripts from a numbered menu (`--interactive`)
+async fn fetch_selected_transcript(
+    api: &YouTubeTranscript,
+    video_id: &str,
+) -> Result<TranscriptResponse, TranscriptError> {
+    let transcript_list = api.list_transcripts(video_id).await?;
+    let mut tracks: Vec<&TranscriptInfo> = transcript_list.manually_created.values().collect();
+    tracks.sort_by(|a, b| a.language_code.cmp(&b.language_code));
+    let mut generated: Vec<&TranscriptInfo> = transcript_list.generated.values().collect();
+    generated.sort_by(|a, b| a.language_code.cmp(&b.language_code));
+    tracks.extend(generated);
+
+    let track = match tracks.as_slice() {
+        [] => {
+            return Err(TranscriptError::NoTranscriptFound(
+                video_id.to_string(),
+                vec![],
+            ));
         }
+        [only] => only,
         _ => {
-            eprintln!("Unknown format: '{}'. Using 'text' format.", args.format);
-            eprintln!("Supported formats: json, text, txt, srt, markdown, md");
-            if args.timestamps {
-                output_text(&transcript_items, &output_dest, video_url.as_deref(), video_title)?;
-            } else {
-                output_text_only(&transcript_items, &output_dest, video_url.as_deref(), video_title)?;
+            let mut stderr = io::stderr();
+            writeln!(stderr, "Available transcripts for video: {}", video_id)?;
+            for (index, track) in tracks.iter().enumerate() {
+                let kind = if track.is_generated {
+                    " [auto-generated]"
+                } else {
+                    ""
+                };
+                writeln!(
+                    stderr,
+                    "  {}) {} ({}){}",
+                    index + 1,
+                    track.language,
+                    track.language_code,
+                    kind
+                )?;
             }
+            let choice = prompt_for_choice(&mut io::stdin().lock(), &mut stderr, tracks.len())?;
+            tracks[choice]
         }
-    }
+    };
 
-    Ok(())
+    let mut transcript = track.fetch(api).await?;
+    transcript.title = transcript_list.title.clone();
+    transcript.chapters = transcript_list.chapters.clone();
+    Ok(transcript)
 }
 
-enum OutputDestination {
-    Stdout,
-    File(String),
+/// Ask for a number between 1 and `count` until a valid one is entered, returning
+/// its zero-based index
+fn prompt_for_choice(
+    input: &mut impl BufRead,
+    prompt: &mut impl Write,
+    count: usize,
+) -> Result<usize, TranscriptError> {
+    loop {
+        write!(prompt, "Select a transcript [1-{}]: ", count)?;
+        prompt.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Err(TranscriptError::InvalidArgument(
+                "no transcript selected".to_string(),
+            ));
+        }
+        match line.trim().parse::<usize>() {
+            Ok(choice) if (1..=count).contains(&choice) => return Ok(choice - 1),
+            _ => writeln!(prompt, "Please enter a number from 1 to {}", count)?,
+        }
+    }
 }
 
-impl OutputDestination {
-    fn writer(&self) -> Result<Box<dyn Write>, TranscriptError> {
-        match self {
-            OutputDestination::Stdout => Ok(Box::new(io::stdout())),
-            OutputDestination::File(path) => {
-                let file = File::create(path).map_err(|e| {
-                    TranscriptError::IoError(format!("Failed to create file {}: {}", path, e))
-                })?;
-                Ok(Box::new(file))
-            }
+/// Split a `--combine-languages` value such as `en,es` into its two language codes
+fn parse_language_pair(pair: &str) -> Result<(&str, &str), TranscriptError> {
+    match pair.split_once(',') {
+        Some((source, target)) if !source.trim().is_empty() && !target.trim().is_empty() => {
+            Ok((source.trim(), target.trim()))
         }
+        _ => Err(TranscriptError::InvalidArgument(format!(
+            "{} (expected SRC,DST, e.g. en,es)",
+            pair
+        ))),
     }
 }
 
-fn output_json(items: &[TranscriptItem], dest: &OutputDestination) -> Result<(), TranscriptError> {
-    let json = serde_json::to_string_pretty(items)?;
-    let mut writer = dest.writer()?;
-    writeln!(writer, "{}", json)?;
+/// `--combine-languages`: fetch the source track and its translation (or a track
+/// uploaded in the target language), pair the cues up by time and write them together
+async fn write_combined_languages(
+    api: &YouTubeTranscript,
+    args: &Args,
+    video_id: &str,
+    video_index: Option<usize>,
+    source_lang: &str,
+    target_lang: &str,
+) -> Result<(), TranscriptError> {
+    // Both tracks come from one track list, so the watch page is loaded once
+    let transcript_list = api.list_transcripts(video_id).await?;
+    let (source, target) = match api
+        .translate_with_list(&transcript_list, &[source_lang], target_lang)
+        .await
+    {
+        Err(
+            TranscriptError::TranslationLanguageNotAvailable(_)
+            | TranscriptError::NotTranslatable(_),
+        ) => {
+            info!(
+                "No translation to '{}', looking for a '{}' track",
+                target_lang, target_lang
+            );
+            let mut source = transcript_list
+                .find_transcript(&[source_lang])?
+                .fetch(api)
+                .await?;
+            source.title = transcript_list.title.clone();
+            let target = transcript_list
+                .find_transcript(&[target_lang])?
+                .fetch(api)
+                .await?;
+            (source, target)
+        }
+        result => result?,
+    };
+
+    let (source_items, target_items) = if args.start.is_some() || args.end.is_some() {
+        (
+            slice_by_time(&source.transcript, args.start, args.end, args.clamp),
+            slice_by_time(&target.transcript, args.start, args.end, args.clamp),
+        )
+    } else {
+        (source.transcript, target.transcript)
+    };
+    let cues = combine_languages(&source_items, &target_items);
+
+    let language_pair = format!("{}-{}", source_lang, target_lang);
+    let encoding = OutputEncoding::from_name(&args.encoding);
+    for format in requested_formats(&args.format) {
+        let output_dest = output_destination(
+            args,
+            video_id,
+            source.title.as_deref(),
+            &language_pair,
+            video_index,
+            &format,
+        )?;
+        if args.no_overwrite && output_exists(&output_dest) {
+            info!("Skipping {}: output already exists", video_id);
+            continue;
+        }
+        let timestamps = args.timestamps.then_some(args.timestamps_format.as_str());
+        let markdown = matches!(format.as_str(), "markdown" | "md");
+        output_bilingual(
+            &cues,
+            &output_dest.encoded(encoding),
+            markdown,
+            timestamps,
+            source.title.as_deref(),
+        )?;
+    }
     Ok(())
 }
 
-fn output_srt(items: &[TranscriptItem], dest: &OutputDestination) -> Result<(), TranscriptError> {
+/// Bilingual transcript: the original line of each cue with its translation below it.
+/// In markdown the translation is italicized.
+fn output_bilingual(
+    cues: &[BilingualCue],
+    dest: &OutputDestination,
+    markdown: bool,
+    timestamps: Option<&str>,
+    title: Option<&str>,
+) -> Result<(), TranscriptError> {
     let mut writer = dest.writer()?;
 
-    for (index, item) in items.iter().enumerate() {
-        writeln!(writer, "{}", index + 1)?;
-
-        let start_time = format_srt_time(item.start);
-        let end_time = format_srt_time(item.start + item.duration);
+    if markdown && !dest.is_append() {
+        writeln!(writer, "# {}\n", title.unwrap_or("Transcript"))?;
+    }
 
-        writeln!(writer, "{} --> {}", start_time, end_time)?;
-        writeln!(writer, "{}", item.text)?;
+    for cue in cues {
+        let prefix = match (timestamps, markdown) {
+            (Some(format), true) => format!("**[{}]** ", format_timestamp(cue.start, format)),
+            (Some(format), false) => format!("[{}] ", format_timestamp(cue.start, format)),
+            (None, _) => String::new(),
+        };
+        if cue.translated.is_empty() {
+            writeln!(writer, "{}{}", prefix, cue.original)?;
+        } else if markdown {
+            // Two trailing spaces break the line, keeping the translation under the original
+            writeln!(writer, "{}{}  ", prefix, cue.original)?;
+            writeln!(writer, "*{}*", cue.translated)?;
+        } else {
+            writeln!(writer, "{}{}", prefix, cue.original)?;
+            writeln!(
+                writer,
+                "{}{}",
+                " ".repeat(prefix.chars().count()),
+                cue.translated
+            )?;
+        }
         writeln!(writer)?;
     }
 
     Ok(())
 }
 
-fn output_text(items: &[TranscriptItem], dest: &OutputDestination, video_url: Option<&str>, video_title: Option<&str>) -> Result<(), TranscriptError> {
-    let mut writer = dest.writer()?;
-
-    // Prefix with title and URL if provided
-    if let (Some(url), Some(title)) = (video_url, video_title) {
-        writeln!(writer, "{}: {}", title, url)?;
-        writeln!(writer)?;
+/// Parse a caption file from disk into a transcript named after the file.
+/// `keep_empty_cues` applies to YouTube caption formats; SRT and WebVTT blocks
+/// without text are always skipped.
+fn read_input_file(
+    path: &str,
+    keep_empty_cues: bool,
+) -> Result<TranscriptResponse, TranscriptError> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        TranscriptError::IoError(format!("Failed to read input file {}: {}", path, e))
+    })?;
+    // A few malformed bytes shouldn't cost the whole file
+    let content = String::from_utf8_lossy(&bytes);
+    if let std::borrow::Cow::Owned(_) = content {
+        warn!("{} is not valid UTF-8; replacing the malformed bytes", path);
     }
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("transcript")
+        .to_string();
 
-    for item in items {
-        writeln!(writer, "[{:.2}s] {}", item.start, item.text)?;
+    let has_extension = |expected: &str| {
+        Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(expected))
+    };
+    let transcript = if content
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('[')
+    {
+        // A JSON array of cues, as written by `-f json`
+        serde_json::from_str::<Vec<TranscriptItem>>(content.trim_start_matches('\u{feff}'))
+            .map_err(|e| TranscriptError::JsonParseError(format!("{}: {}", path, e)))?
+    } else if has_extension("vtt") || is_vtt(&content) {
+        parse_vtt(&content)
+            .map_err(|e| TranscriptError::SubtitleParseError(format!("{}: {}", path, e)))?
+    } else if has_extension("srt") || is_srt(&content) {
+        parse_srt(&content)
+            .map_err(|e| TranscriptError::SubtitleParseError(format!("{}: {}", path, e)))?
+    } else {
+        TranscriptParser::new(false)
+            .keep_empty_cues(keep_empty_cues)
+            .parse(&content)
+            .map_err(|e| {
+                TranscriptError::XmlParseError(format!("Failed to parse {}: {}", path, e))
+            })?
+    };
+    if transcript.is_empty() {
+        return Err(TranscriptError::EmptyTranscript(path.to_string()));
     }
 
-    Ok(())
+    Ok(TranscriptResponse {
+        video_id: stem.clone(),
+        title: Some(stem),
+        language: String::new(),
+        language_code: String::new(),
+        is_generated: false,
+        is_translatable: false,
+        transcript,
+        chapters: Vec::new(),
+    })
 }
 
-fn output_text_only(
-    items: &[TranscriptItem],
-    dest: &OutputDestination,
-    video_url: Option<&str>,
-    video_title: Option<&str>,
+/// Run a fetched transcript through slicing, dedupe, offset and cleanup, then
+/// write every requested format. `usage_before` is the ChatGPT usage when work
+/// on this video began, for `--show-usage`.
+async fn process_transcript(
+    chatgpt: Option<&AnyChatGPT>,
+    args: &Args,
+    transcript: TranscriptResponse,
+    video_id: &str,
+    video_index: Option<usize>,
+    usage_before: Usage,
 ) -> Result<(), TranscriptError> {
-    let mut writer = dest.writer()?;
+    let mut outputs = Vec::new();
+    for format in requested_formats(&args.format) {
+        let output_dest = output_destination(
+            args,
+            video_id,
+            transcript.title.as_deref(),
+            &transcript.language_code,
+            video_index,
+            &format,
+        )?;
+        if args.no_overwrite && output_exists(&output_dest) {
+            if let OutputDestination::File(path) = &output_dest {
+                info!("{} already exists, not overwriting", path);
+            }
+            continue;
+        }
+        outputs.push((format, output_dest));
+    }
+    if outputs.is_empty() {
+        info!("Skipping video {}: output already exists", video_id);
+        return Ok(());
+    }
 
-    // Prefix with title and URL if provided
-    if let (Some(url), Some(title)) = (video_url, video_title) {
-        writeln!(writer, "{}: {}", title, url)?;
-        writeln!(writer)?;
+    // Restrict to the requested time window, if any
+    let transcript_items = if args.start.is_some() || args.end.is_some() {
+        slice_by_time(&transcript.transcript, args.start, args.end, args.clamp)
+    } else {
+        transcript.transcript
+    };
+
+    let transcript_items = if args.min_duration > 0.0 {
+        drop_short_cues(&transcript_items, args.min_duration)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.strip_bracketed {
+        strip_annotations(&transcript_items)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.strip_timestamps_from_text {
+        strip_timecodes(&transcript_items)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.drop_duplicate_cues {
+        drop_duplicate_cues(&transcript_items)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.dedupe {
+        dedupe_rolling(&transcript_items)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.fix_case {
+        fix_case(&transcript_items)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.replacements.is_empty() {
+        transcript_items
+    } else {
+        apply_replacements(&transcript_items, &args.replacements)
+    };
+
+    let transcript_items = if args.normalize {
+        transcript_items
+            .into_iter()
+            .map(|item| TranscriptItem {
+                text: normalize_text(&item.text),
+                ..item
+            })
+            .collect()
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = if args.repace {
+        repace(&transcript_items)
+    } else {
+        transcript_items
+    };
+
+    let transcript_items = match args.offset {
+        Some(offset) => shift_timestamps(&transcript_items, offset),
+        None => transcript_items,
+    };
+
+    if args.stats {
+        print_stats(
+            video_id,
+            &transcript.language_code,
+            &TranscriptStats::from_items(&transcript_items),
+        );
+        return Ok(());
     }
 
-    for item in items {
-        writeln!(writer, "{}", item.text)?;
+    // Determine if we need markdown formatting from ChatGPT
+    let format_markdown = args.cleanup
+        && !args.cleanup_preserve_timing
+        && requested_formats(&args.format)
+            .iter()
+            .any(|f| f == "markdown" || f == "md");
+
+    // If cleanup is requested, send to ChatGPT first
+    let transcript_items = if let (true, Some(chatgpt)) = (args.cleanup, chatgpt) {
+        if video_index.is_none() {
+            info!("Cleaning up transcript with ChatGPT...");
+        }
+        if args.cleanup_preserve_timing {
+            chatgpt.cleanup_cues(&transcript_items).await?
+        } else {
+            let transcript_text: String = transcript_items
+                .iter()
+                .map(|item| item.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            // Cue boundaries can still leave a stray space before punctuation
+            let transcript_text = if args.normalize {
+                normalize_text(&transcript_text)
+            } else {
+                transcript_text
+            };
+            let transcript_text = match args.max_input_chars {
+                Some(max_chars) => {
+                    let truncated = truncate_chars(&transcript_text, max_chars as usize);
+                    if truncated.len() < transcript_text.len() {
+                        warn!(
+                            "{}: transcript has {} characters; sending only the first {} to ChatGPT (--max-input-chars)",
+                            video_id,
+                            transcript_text.chars().count(),
+                            truncated.chars().count()
+                        );
+                    }
+                    truncated.to_string()
+                }
+                None => transcript_text,
+            };
+
+            let cleaned_text = chatgpt
+                .cleanup_transcript(&transcript_text, format_markdown)
+                .await?;
+
+            // For cleanup, output the cleaned text directly as a single item
+            // This preserves the cleaned flow better than trying to split it back
+            vec![TranscriptItem {
+                text: cleaned_text,
+                start: transcript_items.first().map(|i| i.start).unwrap_or(0.0),
+                duration: transcript_items.iter().map(|i| i.duration).sum(),
+            }]
+        }
+    } else {
+        transcript_items
+    };
+
+    if let (true, Some(chatgpt)) = (args.show_usage, chatgpt) {
+        let used = chatgpt.usage().since(usage_before);
+        if used.total_tokens > 0 {
+            eprintln!("ChatGPT usage for {}: {}", video_id, used);
+        }
     }
 
+    let sidecars = if args.sidecar {
+        sidecar_targets(&outputs)
+    } else {
+        Vec::new()
+    };
+    if args.chapters && transcript.chapters.is_empty() {
+        warn!(
+            "{} has no chapters; writing the transcript without chapter headings",
+            video_id
+        );
+    }
+    write_outputs(
+        args,
+        outputs,
+        transcript_items,
+        video_id,
+        transcript.title.clone(),
+        transcript.chapters.clone(),
+    )
+    .await?;
+    if !sidecars.is_empty() {
+        let sidecar = Sidecar::new(
+            video_id,
+            transcript.title.as_deref(),
+            &transcript.language,
+            &transcript.language_code,
+            transcript.is_generated,
+        );
+        write_sidecars(&sidecars, sidecar)?;
+    }
     Ok(())
 }
 
-fn output_markdown(
-    items: &[TranscriptItem],
-    dest: &OutputDestination,
-    timestamps: bool,
-    video_url: Option<&str>,
-    video_title: Option<&str>,
-) -> Result<(), TranscriptError> {
-    let mut writer = dest.writer()?;
-
-    // If URL and title are provided, prepend the markdown link
-    if let (Some(url), Some(title)) = (video_url, video_title) {
-        writeln!(writer, "![{}]({})\n", title, url)?;
-    }
+/// Fetch metadata written next to the output files with `--sidecar`
+#[derive(Serialize)]
+struct Sidecar<'a> {
+    video_id: &'a str,
+    title: Option<&'a str>,
+    language: &'a str,
+    language_code: &'a str,
+    is_generated: bool,
+    /// UTC time the sidecar was written, e.g. `2024-05-01T12:34:56Z`
+    fetched_at: String,
+    formats: Vec<String>,
+}
 
-    // If there's only one item and it contains markdown (from ChatGPT cleanup),
-    // output it directly without adding extra formatting
-    if items.len() == 1
-        && (items[0].text.contains("**")
-            || items[0].text.contains("##")
-            || items[0].text.contains("*"))
-    {
-        // Already formatted by ChatGPT, just add heading if not present
-        if !items[0].text.trim_start().starts_with("#") {
-            writeln!(writer, "# Transcript\n")?;
+impl<'a> Sidecar<'a> {
+    fn new(
+        video_id: &'a str,
+        title: Option<&'a str>,
+        language: &'a str,
+        language_code: &'a str,
+        is_generated: bool,
+    ) -> Self {
+        Self {
+            video_id,
+            title,
+            language,
+            language_code,
+            is_generated,
+            fetched_at: format_utc_timestamp(SystemTime::now()),
+            formats: Vec::new(),
         }
-        writeln!(writer, "{}", items[0].text)?;
-    } else {
-        // Regular markdown output
-        writeln!(writer, "# Transcript\n")?;
+    }
+}
 
-        for item in items {
-            if timestamps {
-                writeln!(writer, "**[{:.2}s]** {}", item.start, item.text)?;
-            } else {
-                writeln!(writer, "{}", item.text)?;
-            }
-            writeln!(writer)?;
+/// Sidecar path for each file output, with the formats it describes. Formats
+/// written to files with the same basename (`talk.srt`, `talk.json`) share one
+/// sidecar (`talk.meta.json`); stdout and `--append` outputs get none.
+fn sidecar_targets(outputs: &[(String, OutputDestination)]) -> Vec<(String, Vec<String>)> {
+    let mut targets: Vec<(String, Vec<String>)> = Vec::new();
+    for (format, output_dest) in outputs {
+        let path = match output_dest {
+            OutputDestination::File(path) => path.as_str(),
+            OutputDestination::GzipFile(path) => path.strip_suffix(".gz").unwrap_or(path),
+            // Encoded destinations only exist while a file is being written
+            OutputDestination::Stdout
+            | OutputDestination::Append(_)
+            | OutputDestination::Encoded(..)
+            | OutputDestination::Part(..) => continue,
+        };
+        let sidecar_path = Path::new(path)
+            .with_extension("meta.json")
+            .to_string_lossy()
+            .to_string();
+        match targets
+            .iter_mut()
+            .find(|(existing, _)| *existing == sidecar_path)
+        {
+            Some((_, formats)) => formats.push(format.clone()),
+            None => targets.push((sidecar_path, vec![format.clone()])),
         }
     }
+    targets
+}
 
+fn write_sidecars(
+    targets: &[(String, Vec<String>)],
+    mut sidecar: Sidecar,
+) -> Result<(), TranscriptError> {
+    for (path, formats) in targets {
+        sidecar.formats = formats.clone();
+        let json = serde_json::to_string_pretty(&sidecar)?;
+        std::fs::write(path, format!("{}\n", json)).map_err(|e| {
+            TranscriptError::IoError(format!("Failed to write sidecar {}: {}", path, e))
+        })?;
+    }
     Ok(())
 }
 
-fn format_srt_time(seconds: f64) -> String {
-    let hours = (seconds / 3600.0) as u32;
-    let minutes = ((seconds % 3600.0) / 60.0) as u32;
-    let secs = seconds % 60.0;
-    let secs_int = secs as u32;
-    let millis = ((secs - secs_int as f64) * 1000.0) as u32;
+/// ISO 8601 UTC timestamp with second precision, e.g. `2024-05-01T12:34:56Z`
+fn format_utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
 
-    format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs_int, millis)
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
 }
 
-fn sanitize_filename(title: &str) -> String {
+/// Write every requested format from the one set of items
+///
+/// Serializing a long transcript is CPU-bound, so when several formats are
+/// requested each is written on the blocking pool concurrently. All writers run
+/// to completion and the first failure in `--format` order is returned, so the
+/// reported error doesn't depend on which writer happened to finish first.
+async fn write_outputs(
+    args: &Args,
+    outputs: Vec<(String, OutputDestination)>,
+    transcript_items: Vec<TranscriptItem>,
+    video_id: &str,
+    title: Option<String>,
+    chapters: Vec<(String, f64)>,
+) -> Result<(), TranscriptError> {
+    if let [(format, output_dest)] = outputs.as_slice() {
+        return write_output(
+            args,
+            format,
+            &transcript_items,
+            output_dest,
+            video_id,
+            title.as_deref(),
+            &chapters,
+        );
+    }
+
+    let args = Arc::new(args.clone());
+    let transcript_items = Arc::new(transcript_items);
+    let video_id: Arc<str> = Arc::from(video_id);
+    let title: Option<Arc<str>> = title.map(Arc::from);
+    let chapters = Arc::new(chapters);
+
+    let handles: Vec<_> = outputs
+        .into_iter()
+        .map(|(format, output_dest)| {
+            let args = Arc::clone(&args);
+            let transcript_items = Arc::clone(&transcript_items);
+            let video_id = Arc::clone(&video_id);
+            let title = title.clone();
+            let chapters = Arc::clone(&chapters);
+            let task_format = format.clone();
+            let task = tokio::task::spawn_blocking(move || {
+                write_output(
+                    &args,
+                    &task_format,
+                    &transcript_items,
+                    &output_dest,
+                    &video_id,
+                    title.as_deref(),
+                    &chapters,
+                )
+            });
+            (format, task)
+        })
+        .collect();
+
+    let mut first_error = None;
+    for (format, task) in handles {
+        let result = task.await.unwrap_or_else(|e| {
+            Err(TranscriptError::IoError(format!(
+                "Writing {} output failed: {}",
+                format, e
+            )))
+        });
+        if let Err(e) = result {
+            first_error.get_or_insert(e);
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Check a `--since`/`--until` value is a `YYYY-MM-DD` date
+fn validate_date(date: &str) -> Result<(), TranscriptError> {
+    let invalid = || TranscriptError::InvalidArgument(format!("{} (expected YYYY-MM-DD)", date));
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return Err(invalid());
+    }
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    let day: u32 = day.parse().map_err(|_| invalid())?;
+    year.parse::<u32>().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+/// Whether a `YYYY-MM-DD` publish date lies within the inclusive `--since`/`--until` range
+fn in_date_range(published: &str, since: Option<&str>, until: Option<&str>) -> bool {
+    // Zero-padded ISO dates order the same as strings
+    since.is_none_or(|since| published >= since) && until.is_none_or(|until| published <= until)
+}
+
+/// The `--since`/`--until` range, and what lets checking it stop early
+struct DateFilter<'a> {
+    since: Option<&'a str>,
+    until: Option<&'a str>,
+    /// The playlist is in newest-first order, so checking stops at the first video
+    /// published before `since`
+    newest_first: bool,
+    /// Checking stops once this many videos not yet completed are in range
+    max: Option<usize>,
+}
+
+/// Drop playlist videos published outside `--since`/`--until`. Videos whose date
+/// can't be determined are kept, with a warning.
+///
+/// Each date takes a watch page download, so videos already completed in an
+/// earlier `--resume` run are kept without one (they passed the same check then),
+/// and the pages of kept videos are reused when their transcripts are fetched.
+async fn filter_by_publish_date(
+    api: &YouTubeTranscript,
+    video_ids: Vec<String>,
+    filter: &DateFilter<'_>,
+    resume_state: Option<&ResumeState>,
+) -> Vec<String> {
+    info!("Checking publish dates of {} videos", video_ids.len());
+    let mut kept = Vec::new();
+    let mut pending = 0;
+    for video_id in video_ids {
+        if resume_state.is_some_and(|state| state.is_completed(&video_id)) {
+            kept.push(video_id);
+            continue;
+        }
+        if filter.max.is_some_and(|max| pending >= max) {
+            info!(
+                "Found {} videos within the date range, not checking the rest (--max)",
+                pending
+            );
+            break;
+        }
+        match api.get_video_publish_date(&video_id).await {
+            Ok(Some(published)) if in_date_range(&published, filter.since, filter.until) => {
+                pending += 1;
+                kept.push(video_id);
+            }
+            Ok(Some(published))
+                if filter.newest_first
+                    && filter.since.is_some_and(|since| published.as_str() < since) =>
+            {
+                info!(
+                    "Video {} was published {}, before --since; the rest of the uploads are older",
+                    video_id, published
+                );
+                break;
+            }
+            Ok(Some(published)) => {
+                info!("Skipping video {} (published {})", video_id, published);
+            }
+            Ok(None) => {
+                warn!("No publish date found for video {}, keeping it", video_id);
+                pending += 1;
+                kept.push(video_id);
+            }
+            Err(e) => {
+                warn!(
+                    "Could not get publish date for video {} ({}), keeping it",
+                    video_id, e
+                );
+                pending += 1;
+                kept.push(video_id);
+            }
+        }
+    }
+    info!("{} videos within the date range", kept.len());
+    kept
+}
+
+/// Write `SUMMARY.md` (linking every chapter in `src/`, in filename order) and,
+/// if there isn't one yet, a minimal `book.toml` for `--mdbook`
+///
+/// Each chapter's title is taken from its first `# ` heading.
+fn write_mdbook_summary(book_dir: &Path, playlist_id: &str) -> Result<(), TranscriptError> {
+    let src_dir = book_dir.join("src");
+    let io_error = |path: &Path, e: io::Error| {
+        TranscriptError::IoError(format!("Failed to write mdBook {}: {}", path.display(), e))
+    };
+
+    let mut chapters: Vec<String> = std::fs::read_dir(&src_dir)
+        .map_err(|e| io_error(&src_dir, e))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".md") && name != "SUMMARY.md")
+        .collect();
+    chapters.sort();
+
+    let mut summary = String::from("# Summary\n\n");
+    for chapter in &chapters {
+        let content = std::fs::read_to_string(src_dir.join(chapter)).unwrap_or_default();
+        let title = content
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(|title| title.trim().to_string())
+            .unwrap_or_else(|| chapter.trim_end_matches(".md").to_string());
+        summary.push_str(&format!(
+            "- [{}](./{})\n",
+            escape_link_text(&title),
+            chapter
+        ));
+    }
+    let summary_path = src_dir.join("SUMMARY.md");
+    std::fs::write(&summary_path, summary).map_err(|e| io_error(&summary_path, e))?;
+
+    let book_toml = book_dir.join("book.toml");
+    if !book_toml.exists() {
+        let config = format!("[book]\ntitle = \"{}\"\nsrc = \"src\"\n", playlist_id);
+        std::fs::write(&book_toml, config).map_err(|e| io_error(&book_toml, e))?;
+    }
+
+    info!(
+        "Wrote mdBook with {} chapters to {}",
+        chapters.len(),
+        book_dir.display()
+    );
+    Ok(())
+}
+
+/// Backslash-escape `\`, `[` and `]` so a video title can be a markdown link's text
+fn escape_link_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The playlist videos to process, each with its 1-based position in the playlist.
+/// Positions are taken before videos completed in an earlier run (`--resume`) are
+/// dropped, so chapter numbers and `{index}` stay the same across resumed runs.
+fn select_playlist_videos(
+    video_ids: Vec<String>,
+    resume_state: Option<&ResumeState>,
+    max: Option<usize>,
+) -> Vec<(usize, String)> {
+    let mut videos: Vec<(usize, String)> = video_ids
+        .into_iter()
+        .enumerate()
+        .map(|(index, video_id)| (index + 1, video_id))
+        .collect();
+
+    if let Some(state) = resume_state {
+        let before = videos.len();
+        videos.retain(|(_, video_id)| !state.is_completed(video_id));
+        if videos.len() < before {
+            info!(
+                "Skipping {} videos already completed according to {}",
+                before - videos.len(),
+                state.path
+            );
+        }
+    }
+
+    // Limit to max number if specified
+    if let Some(max) = max {
+        if max < videos.len() {
+            info!("Processing first {} videos (limited by --max)", max);
+            videos.truncate(max);
+        }
+    }
+    videos
+}
+
+/// Video IDs a playlist run has finished (`--resume`), saved as
+/// `{"completed": [...]}` and rewritten after every video
+struct ResumeState {
+    path: String,
+    completed: HashSet<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ResumeFile {
+    completed: Vec<String>,
+}
+
+impl ResumeState {
+    /// Read the state file, starting empty when it doesn't exist yet
+    fn load(path: &str) -> Result<Self, TranscriptError> {
+        let completed = match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let file: ResumeFile = serde_json::from_str(&content).map_err(|e| {
+                    TranscriptError::InvalidArgument(format!("Invalid resume file {}: {}", path, e))
+                })?;
+                file.completed.into_iter().collect()
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => {
+                return Err(TranscriptError::IoError(format!(
+                    "Failed to read resume file {}: {}",
+                    path, e
+                )))
+            }
+        };
+        Ok(Self {
+            path: path.to_string(),
+            completed,
+        })
+    }
+
+    fn is_completed(&self, video_id: &str) -> bool {
+        self.completed.contains(video_id)
+    }
+
+    /// Record a finished video. The file is replaced through a rename so an
+    /// interrupted write can't leave it truncated.
+    fn mark_completed(&mut self, video_id: &str) -> Result<(), TranscriptError> {
+        if !self.completed.insert(video_id.to_string()) {
+            return Ok(());
+        }
+
+        // Sorted, so the file doesn't reorder itself on every write
+        let mut completed: Vec<String> = self.completed.iter().cloned().collect();
+        completed.sort();
+        let json = serde_json::to_string_pretty(&ResumeFile { completed })?;
+        let temp_path = format!("{}.tmp", self.path);
+        let write_error = |e: io::Error| {
+            TranscriptError::IoError(format!("Failed to write resume file {}: {}", self.path, e))
+        };
+        let mut file = File::create(&temp_path).map_err(write_error)?;
+        writeln!(file, "{}", json).map_err(write_error)?;
+        file.sync_all().map_err(write_error)?;
+        std::fs::rename(&temp_path, &self.path).map_err(write_error)?;
+        Ok(())
+    }
+}
+
+/// Timing and outcome of each video in a playlist run, for the ETA and final summary
+struct PlaylistProgress {
+    started: Instant,
+    total: usize,
+    durations: Vec<Duration>,
+    succeeded: usize,
+    failed: usize,
+    /// Videos that were skipped, with the error each one failed with
+    skipped: Vec<(String, TranscriptError)>,
+}
+
+impl PlaylistProgress {
+    /// Number of most recent videos the ETA averages over
+    const ETA_WINDOW: usize = 5;
+
+    fn new(total: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            total,
+            durations: Vec::new(),
+            succeeded: 0,
+            failed: 0,
+            skipped: Vec::new(),
+        }
+    }
+
+    fn record_skipped(&mut self, video_id: &str, error: TranscriptError) {
+        self.skipped.push((video_id.to_string(), error));
+    }
+
+    /// Skipped videos grouped by the kind of failure (the exit code categories), so a
+    /// long run's failures can be audited at a glance. `None` when nothing was skipped.
+    fn skipped_report(&self) -> Option<String> {
+        if self.skipped.is_empty() {
+            return None;
+        }
+        let categories = [
+            (2, "Unavailable, unplayable or age-restricted"),
+            (3, "No usable transcript"),
+            (4, "Blocked or rate limited"),
+            (5, "I/O error"),
+            (6, "Invalid video ID or argument"),
+            (1, "Other failure"),
+        ];
+
+        let mut report = format!("Skipped {} of {} videos:", self.skipped.len(), self.total);
+        for (code, label) in categories {
+            let videos: Vec<_> = self
+                .skipped
+                .iter()
+                .filter(|(_, error)| exit_code(error) == code)
+                .collect();
+            if videos.is_empty() {
+                continue;
+            }
+            report.push_str(&format!("\n  {} ({}):", label, videos.len()));
+            for (video_id, error) in videos {
+                report.push_str(&format!("\n    {}: {}", video_id, error));
+            }
+        }
+        Some(report)
+    }
+
+    fn record(&mut self, duration: Duration, succeeded: bool) {
+        self.durations.push(duration);
+        if succeeded {
+            self.succeeded += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    /// Estimated time left, from the average of the last few videos
+    fn eta(&self) -> Option<Duration> {
+        if self.durations.is_empty() {
+            return None;
+        }
+        let recent = &self.durations[self.durations.len().saturating_sub(Self::ETA_WINDOW)..];
+        let average = recent.iter().sum::<Duration>() / recent.len() as u32;
+        let remaining = self.total.saturating_sub(self.durations.len());
+        Some(average * remaining as u32)
+    }
+
+    fn status(&self) -> String {
+        let elapsed = format_duration(self.started.elapsed());
+        match self.eta() {
+            Some(eta) => format!("elapsed {}, ETA {}", elapsed, format_duration(eta)),
+            None => format!("elapsed {}", elapsed),
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "Finished {} videos in {}: {} succeeded, {} failed",
+            self.durations.len(),
+            format_duration(self.started.elapsed()),
+            self.succeeded,
+            self.failed
+        )
+    }
+}
+
+/// One `--progress-json` line, e.g. `{"event":"video_start","index":1,"total":3,"id":"..."}`
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ProgressEvent<'a> {
+    RunStart {
+        total: usize,
+    },
+    VideoStart {
+        index: usize,
+        total: usize,
+        id: &'a str,
+    },
+    VideoDone {
+        index: usize,
+        total: usize,
+        id: &'a str,
+        seconds: f64,
+    },
+    VideoError {
+        index: usize,
+        total: usize,
+        id: &'a str,
+        error: String,
+        exit_code: i32,
+    },
+    RunDone {
+        total: usize,
+        succeeded: usize,
+        failed: usize,
+        seconds: f64,
+    },
+    /// `--fail-fast` stopped the run at video `index`
+    RunStopped {
+        index: usize,
+        total: usize,
+        succeeded: usize,
+    },
+    /// The error the run exits with
+    Error {
+        error: String,
+        exit_code: i32,
+    },
+    /// A warning or other log record
+    Log {
+        level: String,
+        message: String,
+    },
+}
+
+impl<'a> ProgressEvent<'a> {
+    /// `video_done` or `video_error` for a video that started at `started`
+    fn finished(
+        index: usize,
+        total: usize,
+        id: &'a str,
+        started: Instant,
+        result: &Result<(), TranscriptError>,
+    ) -> Self {
+        let seconds = started.elapsed().as_secs_f64();
+        match result {
+            Ok(()) => ProgressEvent::VideoDone {
+                index,
+                total,
+                id,
+                seconds,
+            },
+            Err(e) => ProgressEvent::VideoError {
+                index,
+                total,
+                id,
+                error: e.to_string(),
+                exit_code: exit_code(e),
+            },
+        }
+    }
+}
+
+fn emit_progress(event: &ProgressEvent) {
+    if let Ok(line) = serde_json::to_string(event) {
+        eprintln!("{}", line);
+    }
+}
+
+/// Human-friendly duration such as `45s`, `3m05s` or `1h02m00s`
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if hours > 0 {
+        format!("{}h{:02}m{:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m{:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Whether a destination is a file that is already on disk (`--no-overwrite`)
+fn output_exists(output_dest: &OutputDestination) -> bool {
+    matches!(
+        output_dest,
+        OutputDestination::File(path) | OutputDestination::GzipFile(path) if Path::new(path).exists()
+    )
+}
+
+/// Formats selected by `--format`: a single name, a comma-separated list, or `all`
+fn requested_formats(format: &str) -> Vec<String> {
+    if format.eq_ignore_ascii_case("all") {
+        return ["json", "srt", "txt", "md"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+    }
+    format
+        .split(',')
+        .map(|f| f.trim().to_lowercase())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// `--append` needs exactly one output file and a format that can be concatenated
+fn validate_append(args: &Args) -> Result<(), TranscriptError> {
+    match &args.output {
+        Some(output_path) if output_path != "-" && !output_is_directory(output_path) => {}
+        _ => {
+            return Err(TranscriptError::InvalidArgument(
+                "--append needs -o FILE to collect the playlist into".to_string(),
+            ))
+        }
+    }
+    let formats = requested_formats(&args.format);
+    let appendable = ["text", "txt", "markdown", "md", "jsonl", "ndjson"];
+    if formats.len() != 1 || !appendable.contains(&formats[0].as_str()) {
+        return Err(TranscriptError::InvalidArgument(
+            "--append supports a single text, markdown or jsonl format".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The first flag given that only applies in playlist mode
+fn playlist_only_flag(args: &Args) -> Option<&'static str> {
+    [
+        ("--sort", args.sort != "none"),
+        (
+            "--max-retries-per-playlist",
+            args.max_retries_per_playlist.is_some(),
+        ),
+        ("--fail-fast", args.fail_fast),
+        ("--resume", args.resume.is_some()),
+        ("--list-playlist", args.list_playlist),
+        ("--since", args.since.is_some()),
+        ("--until", args.until.is_some()),
+        ("--mdbook", args.mdbook.is_some()),
+        ("--append", args.append),
+    ]
+    .into_iter()
+    .find_map(|(flag, given)| given.then_some(flag))
+}
+
+/// Where `--dump-raw` and `--diff` write: `-o FILE`, or stdout without `-o` or with `-o -`
+fn single_output(args: &Args) -> OutputDestination {
+    match args.output.as_deref() {
+        Some("-") | None => OutputDestination::Stdout,
+        Some(path) => OutputDestination::File(path.to_string()),
+    }
+}
+
+/// Whether an `-o` value names a directory: it exists as one, or ends with a path separator
+fn output_is_directory(output_path: &str) -> bool {
+    let path = Path::new(output_path);
+    if path.exists() {
+        path.is_dir()
+    } else {
+        let sep = std::path::MAIN_SEPARATOR;
+        output_path.ends_with(sep) || output_path.ends_with('/')
+    }
+}
+
+/// Decide where the output for one video in one format goes
+fn output_destination(
+    args: &Args,
+    video_id: &str,
+    title: Option<&str>,
+    language_code: &str,
+    video_index: Option<usize>,
+    format: &str,
+) -> Result<OutputDestination, TranscriptError> {
+    // For playlists, if -o is a directory or -n is used, each video gets its own file
+    // Generated filenames carry the language code when one was chosen explicitly,
+    // so fetching the original and a translation doesn't overwrite one with the other
+    let language_suffix = if args.translate.is_some() || args.languages.is_some() {
+        format!(".{}", language_code)
+    } else {
+        String::new()
+    };
+    let generated_filename = |basename: &str| {
+        format!(
+            "{}{}.{}",
+            basename,
+            language_suffix,
+            format_extension(format)
+        )
+    };
+    let require_title = || {
+        title.ok_or_else(|| {
+            TranscriptError::YouTubeDataUnparsable("Failed to extract video title".to_string())
+        })
+    };
+
+    let output_dest = if args.output.as_deref() == Some("-") {
+        OutputDestination::Stdout
+    } else if let Some(template) = &args.output_template {
+        let fields = TemplateFields {
+            id: video_id,
+            title,
+            index: video_index.unwrap_or(1),
+            lang: language_code,
+            ext: format_extension(format),
+            date: &format_utc_timestamp(SystemTime::now())[..10],
+        };
+        let filename = expand_output_template(template, &fields)?;
+        match &args.output {
+            Some(dir) => {
+                OutputDestination::File(Path::new(dir).join(filename).to_string_lossy().to_string())
+            }
+            None => OutputDestination::File(filename),
+        }
+    } else if let Some(book_dir) = &args.mdbook {
+        // Chapters are numbered by playlist position, which a resumed run keeps,
+        // so they sort in playlist order and SUMMARY.md lists them that way
+        let filename = format!(
+            "{:03}_{}.md",
+            video_index.unwrap_or(1),
+            sanitize_filename(require_title()?)
+        );
+        OutputDestination::File(
+            Path::new(book_dir)
+                .join("src")
+                .join(filename)
+                .to_string_lossy()
+                .to_string(),
+        )
+    } else if let (true, Some(output_path)) = (args.append, &args.output) {
+        OutputDestination::Append(output_path.clone())
+    } else if let Some(ref output_path) = args.output {
+        let path = Path::new(output_path);
+        let is_directory = output_is_directory(output_path);
+
+        if is_directory && args.name {
+            // Combine directory with title as filename
+            let filename = generated_filename(&sanitize_filename(require_title()?));
+            let combined_path = path.join(filename);
+            OutputDestination::File(combined_path.to_string_lossy().to_string())
+        } else if is_directory {
+            // Without -n, files in a directory are named after the video ID, so a
+            // playlist or several formats each get a file of their own
+            let filename = generated_filename(video_id);
+            let combined_path = path.join(filename);
+            OutputDestination::File(combined_path.to_string_lossy().to_string())
+        } else {
+            // Use the path as-is (either it's a file path, or -n wasn't specified)
+            // For playlist mode, this would overwrite, so we should handle it differently
+            if video_index.is_some() {
+                // In playlist mode with a file path, append video_id
+                let path_buf = Path::new(output_path);
+                let stem = path_buf
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("output");
+                let extension = path_buf
+                    .extension()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("txt");
+                let parent = path_buf.parent().unwrap_or(Path::new("."));
+                let new_filename = format!("{}_{}.{}", stem, video_id, extension);
+                let combined_path = parent.join(new_filename);
+                OutputDestination::File(combined_path.to_string_lossy().to_string())
+            } else {
+                OutputDestination::File(output_path.clone())
+            }
+        }
+    } else if args.name {
+        // Use video title as basename in current directory
+        OutputDestination::File(generated_filename(&sanitize_filename(require_title()?)))
+    } else if video_index.is_some() {
+        // Playlist mode without -o or -n: use video_id as filename
+        OutputDestination::File(generated_filename(video_id))
+    } else {
+        OutputDestination::Stdout
+    };
+
+    let output_dest = match output_dest {
+        OutputDestination::File(path) if args.gzip => {
+            let path = if path.ends_with(".gz") {
+                path
+            } else {
+                format!("{}.gz", path)
+            };
+            OutputDestination::GzipFile(path)
+        }
+        output_dest => output_dest,
+    };
+
+    Ok(output_dest)
+}
+
+/// The first `max_chars` characters of `text`, cut back to the last word boundary
+/// when there is one so the final word isn't sent half-finished
+fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    let Some((cut, _)) = text.char_indices().nth(max_chars) else {
+        return text;
+    };
+    let head = &text[..cut];
+    match head.rfind(char::is_whitespace) {
+        Some(space) if !text[cut..].starts_with(char::is_whitespace) => head[..space].trim_end(),
+        _ => head.trim_end(),
+    }
+}
+
+/// `--stats` block for one video; in a playlist the blocks are separated by blank lines
+fn print_stats(video_id: &str, language_code: &str, stats: &TranscriptStats) {
+    println!("{}", format_stats(video_id, language_code, stats));
+}
+
+fn format_stats(video_id: &str, language_code: &str, stats: &TranscriptStats) -> String {
+    format!(
+        "video_id={}\nlanguage={}\ncues={}\nduration={:.2}\nwords={}\nwords_per_minute={:.1}\naverage_cue_duration={:.2}\n",
+        video_id,
+        language_code,
+        stats.cues,
+        stats.duration,
+        stats.words,
+        stats.words_per_minute,
+        stats.average_cue_duration
+    )
+}
+
+/// Values substituted into an `--output-template`
+struct TemplateFields<'a> {
+    id: &'a str,
+    title: Option<&'a str>,
+    /// Position in the playlist, 1 for a single video
+    index: usize,
+    lang: &'a str,
+    ext: &'a str,
+    /// Date the transcript was fetched (UTC, `YYYY-MM-DD`)
+    date: &'a str,
+}
+
+impl TemplateFields<'_> {
+    /// Stand-in values for checking a template up front
+    fn example() -> Self {
+        TemplateFields {
+            id: "dQw4w9WgXcQ",
+            title: Some("Title"),
+            index: 1,
+            lang: "en",
+            ext: "txt",
+            date: "2000-01-01",
+        }
+    }
+}
+
+/// Expand `{placeholder}` and `{placeholder:0N}` in an `--output-template`
+///
+/// The title is sanitized like `-n` does, so it can't introduce path separators.
+fn expand_output_template(
+    template: &str,
+    fields: &TemplateFields,
+) -> Result<String, TranscriptError> {
+    let invalid = |reason: String| {
+        TranscriptError::InvalidArgument(format!("--output-template {}: {}", template, reason))
+    };
+
+    let mut result = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        result.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| invalid("unclosed {".to_string()))?;
+        let placeholder = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+
+        let (name, spec) = match placeholder.split_once(':') {
+            Some((name, spec)) => (name, Some(spec)),
+            None => (placeholder, None),
+        };
+        let value = match name {
+            "id" => fields.id.to_string(),
+            "title" => sanitize_filename(fields.title.ok_or_else(|| {
+                TranscriptError::YouTubeDataUnparsable("Failed to extract video title".to_string())
+            })?),
+            "index" => fields.index.to_string(),
+            "lang" => fields.lang.to_string(),
+            "ext" => fields.ext.to_string(),
+            "date" => fields.date.to_string(),
+            _ => return Err(invalid(format!("unknown placeholder {{{}}}", name))),
+        };
+        match spec {
+            None => result.push_str(&value),
+            Some(spec) => {
+                let width: usize = spec
+                    .strip_prefix('0')
+                    .and_then(|width| width.parse().ok())
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "unsupported format {{{}}} (expected {{{}:0N}})",
+                            placeholder, name
+                        ))
+                    })?;
+                result.push_str(&format!("{:0>width$}", value, width = width));
+            }
+        }
+    }
+    result.push_str(rest);
+
+    if result.is_empty() {
+        return Err(invalid("expands to an empty filename".to_string()));
+    }
+    Ok(result)
+}
+
+/// Write the items in one format
+fn write_output(
+    args: &Args,
+    format: &str,
+    transcript_items: &[TranscriptItem],
+    output_dest: &OutputDestination,
+    video_id: &str,
+    title: Option<&str>,
+    chapters: &[(String, f64)],
+) -> Result<(), TranscriptError> {
+    if let Some(split_every) = &args.split_every {
+        let parts = SplitEvery::parse(split_every)?.split(transcript_items);
+        if parts.len() > 1 {
+            for (index, part) in parts.iter().enumerate() {
+                let part_dest = output_dest.part(index + 1, parts.len())?;
+                write_output(args, format, part, &part_dest, video_id, title, chapters)?;
+            }
+            return Ok(());
+        }
+    }
+
+    let video_url = if args.url || args.metadata {
+        Some(format!("https://www.youtube.com/watch?v={}", video_id))
+    } else {
+        None
+    };
+    let video_title = if args.url || args.metadata {
+        title
+    } else {
+        None
+    };
+
+    let encoding = OutputEncoding::from_name(&args.encoding);
+    // Videos collected into one file, or printed one after another with `-o -`,
+    // are told apart by a header
+    let section = output_dest.is_append()
+        || (args.playlist && matches!(output_dest, OutputDestination::Stdout));
+    if section {
+        write_section_header(output_dest, format, title.unwrap_or(video_id), encoding)?;
+    }
+
+    let output_dest = &output_dest.encoded(encoding);
+
+    match format {
+        "json" if args.json_index => {
+            output_json(&indexed_cues(transcript_items, video_id), output_dest)?
+        }
+        "json" => output_json(transcript_items, output_dest)?,
+        "jsonl" | "ndjson" if args.json_index => {
+            output_jsonl(&indexed_cues(transcript_items, video_id), output_dest)?
+        }
+        "jsonl" | "ndjson" => output_jsonl(transcript_items, output_dest)?,
+        "srt" if args.fix_overlaps => output_srt(&fix_overlaps(transcript_items), output_dest)?,
+        "srt" => output_srt(transcript_items, output_dest)?,
+        "html" => output_html(transcript_items, output_dest, video_id, title)?,
+        "text" | "txt" => {
+            if args.timestamps {
+                output_text(
+                    transcript_items,
+                    output_dest,
+                    &args.timestamps_format,
+                    video_url.as_deref(),
+                    video_title,
+                    args.metadata,
+                )?;
+            } else {
+                output_text_only(
+                    transcript_items,
+                    output_dest,
+                    video_url.as_deref(),
+                    video_title,
+                    args.metadata,
+                )?;
+            }
+        }
+        "markdown" | "md" => {
+            let timestamps = args.timestamps.then_some(args.timestamps_format.as_str());
+            // mdBook chapters are headed by the video title
+            let heading = match (&args.mdbook, title) {
+                (Some(_), Some(title)) => title,
+                _ => "Transcript",
+            };
+            let front_matter = args.front_matter.then(|| FrontMatter {
+                title: title.unwrap_or(video_id),
+                url: format!("https://www.youtube.com/watch?v={}", video_id),
+                date: format_utc_timestamp(SystemTime::now())[..10].to_string(),
+            });
+            let watch_url = format!("https://www.youtube.com/watch?v={}", video_id);
+            let options = MarkdownOptions {
+                timestamps,
+                cue_links: args.append_url_per_cue.then_some(watch_url.as_str()),
+                video_url: video_url.as_deref(),
+                video_title,
+                metadata: args.metadata,
+                toc: args.markdown_toc,
+                front_matter,
+                section,
+                ..MarkdownOptions::new(heading)
+            };
+            if args.chapters && !chapters.is_empty() {
+                let sections = split_by_chapters(transcript_items, chapters);
+                output_markdown_chapters(&sections, output_dest, &options)?;
+            } else {
+                output_markdown(transcript_items, output_dest, &options)?;
+            }
+        }
+        _ => {
+            warn!("Unknown format: '{}'. Using 'text' format.", format);
+            warn!("Supported formats: json, jsonl, ndjson, text, txt, srt, markdown, md, html");
+            if args.timestamps {
+                output_text(
+                    transcript_items,
+                    output_dest,
+                    &args.timestamps_format,
+                    video_url.as_deref(),
+                    video_title,
+                    args.metadata,
+                )?;
+            } else {
+                output_text_only(
+                    transcript_items,
+                    output_dest,
+                    video_url.as_deref(),
+                    video_title,
+                    args.metadata,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch the source transcript and translate it with ChatGPT, for when YouTube
+/// offers no native translation into `target_lang`
+async fn translate_with_chatgpt(
+    api: &YouTubeTranscript,
+    chatgpt: &AnyChatGPT,
+    video_id: &str,
+    source_langs: &[&str],
+    target_lang: &str,
+) -> Result<TranscriptResponse, TranscriptError> {
+    info!(
+        "YouTube has no translation to '{}', translating with ChatGPT...",
+        target_lang
+    );
+    let source = api
+        .fetch_transcript(video_id, Some(source_langs.to_vec()))
+        .await?;
+
+    let source_text = source
+        .transcript
+        .iter()
+        .map(|item| item.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let translated_text = chatgpt.translate(&source_text, target_lang).await?;
+
+    // Like cleanup, the translation comes back as one block of text
+    let translated_item = TranscriptItem {
+        text: translated_text,
+        start: source.transcript.first().map(|i| i.start).unwrap_or(0.0),
+        duration: source.transcript.iter().map(|i| i.duration).sum(),
+    };
+
+    Ok(TranscriptResponse {
+        language: target_lang.to_string(),
+        language_code: target_lang.to_string(),
+        is_generated: true,
+        transcript: vec![translated_item],
+        ..source
+    })
+}
+
+/// Set once anything is written to stdout, so later playlist videos printed there
+/// (`-o -`) are separated from earlier ones and don't repeat the byte order mark
+static STDOUT_STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone)]
+enum OutputDestination {
+    Stdout,
+    File(String),
+    /// Compressed file (`--gzip`)
+    GzipFile(String),
+    /// Added to the end of an existing file (`--append`)
+    Append(String),
+    /// Another destination written in a non-default encoding (`--encoding`)
+    Encoded(Box<OutputDestination>, OutputEncoding),
+    /// One file of a `--split-every` output: the file, its part number and the part count
+    Part(Box<OutputDestination>, usize, usize),
+}
+
+impl OutputDestination {
+    fn writer(&self) -> Result<Box<dyn Write>, TranscriptError> {
+        match self {
+            OutputDestination::Encoded(inner, encoding) => {
+                // An appended section only gets a byte order mark if it starts the file
+                let at_start = inner.at_start();
+                let mut writer = inner.writer()?;
+                if at_start {
+                    writer.write_all(encoding.byte_order_mark())?;
+                }
+                match encoding {
+                    OutputEncoding::Utf16Le => Ok(Box::new(Utf16LeWriter::new(writer))),
+                    _ => Ok(writer),
+                }
+            }
+            OutputDestination::Part(inner, ..) => inner.writer(),
+            OutputDestination::Stdout => {
+                STDOUT_STARTED.store(true, Ordering::SeqCst);
+                Ok(Box::new(io::stdout()))
+            }
+            OutputDestination::File(path) => {
+                let file = File::create(path).map_err(|e| {
+                    TranscriptError::IoError(format!("Failed to create file {}: {}", path, e))
+                })?;
+                Ok(Box::new(file))
+            }
+            OutputDestination::GzipFile(path) => {
+                let file = File::create(path).map_err(|e| {
+                    TranscriptError::IoError(format!("Failed to create file {}: {}", path, e))
+                })?;
+                // The gzip trailer is written when the encoder is dropped
+                Ok(Box::new(GzEncoder::new(file, Compression::default())))
+            }
+            OutputDestination::Append(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| {
+                        TranscriptError::IoError(format!("Failed to open file {}: {}", path, e))
+                    })?;
+                Ok(Box::new(file))
+            }
+        }
+    }
+
+    /// This destination written in `encoding`
+    fn encoded(&self, encoding: OutputEncoding) -> OutputDestination {
+        match encoding {
+            OutputEncoding::Utf8 => self.clone(),
+            encoding => OutputDestination::Encoded(Box::new(self.clone()), encoding),
+        }
+    }
+
+    /// Whether nothing has been written here yet: an empty or missing file to append
+    /// to, or stdout before the first output. Other destinations start out empty.
+    fn at_start(&self) -> bool {
+        match self {
+            OutputDestination::Append(path) => std::fs::metadata(path)
+                .map(|m| m.len() == 0)
+                .unwrap_or(true),
+            OutputDestination::Stdout => !STDOUT_STARTED.load(Ordering::SeqCst),
+            OutputDestination::Encoded(inner, _) | OutputDestination::Part(inner, ..) => {
+                inner.at_start()
+            }
+            _ => true,
+        }
+    }
+
+    fn is_append(&self) -> bool {
+        match self {
+            OutputDestination::Append(_) => true,
+            OutputDestination::Encoded(inner, _) => inner.is_append(),
+            _ => false,
+        }
+    }
+
+    /// Part `number` of `total` of this file, named `NAME.partNN.EXT`
+    fn part(&self, number: usize, total: usize) -> Result<OutputDestination, TranscriptError> {
+        let part_path = |path: &str| {
+            let (path, gz) = match path.strip_suffix(".gz") {
+                Some(path) => (path, ".gz"),
+                None => (path, ""),
+            };
+            let path = Path::new(path);
+            let extension = path
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            let part_name = format!("part{:02}{}{}", number, extension, gz);
+            path.with_extension(part_name).to_string_lossy().to_string()
+        };
+        let inner = match self {
+            OutputDestination::File(path) => OutputDestination::File(part_path(path)),
+            OutputDestination::GzipFile(path) => OutputDestination::GzipFile(part_path(path)),
+            _ => {
+                return Err(TranscriptError::InvalidArgument(
+                    "--split-every only applies to file output".to_string(),
+                ))
+            }
+        };
+        Ok(OutputDestination::Part(Box::new(inner), number, total))
+    }
+
+    /// Part number and part count, for one file of a `--split-every` output
+    fn part_number(&self) -> Option<(usize, usize)> {
+        match self {
+            OutputDestination::Part(_, number, total) => Some((*number, *total)),
+            OutputDestination::Encoded(inner, _) => inner.part_number(),
+            _ => None,
+        }
+    }
+}
+
+/// How `--split-every` divides the cues between files
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SplitEvery {
+    Cues(usize),
+    Minutes(u64),
+}
+
+impl SplitEvery {
+    /// Parse `N` (cues) or `Nm` (minutes)
+    fn parse(value: &str) -> Result<Self, TranscriptError> {
+        let invalid = || {
+            TranscriptError::InvalidArgument(format!(
+                "{} (expected a cue count like 500 or minutes like 30m)",
+                value
+            ))
+        };
+        let split = match value.trim().strip_suffix('m') {
+            Some(minutes) => SplitEvery::Minutes(minutes.parse().map_err(|_| invalid())?),
+            None => SplitEvery::Cues(value.trim().parse().map_err(|_| invalid())?),
+        };
+        if matches!(split, SplitEvery::Cues(0) | SplitEvery::Minutes(0)) {
+            return Err(invalid());
+        }
+        Ok(split)
+    }
+
+    /// Consecutive runs of `items`, one per output file
+    fn split(self, items: &[TranscriptItem]) -> Vec<&[TranscriptItem]> {
+        match self {
+            SplitEvery::Cues(count) => items.chunks(count).collect(),
+            SplitEvery::Minutes(minutes) => {
+                let window = minutes as f64 * 60.0;
+                let mut parts = Vec::new();
+                let mut part_start = 0;
+                for (index, item) in items.iter().enumerate() {
+                    if item.start - items[part_start].start >= window {
+                        parts.push(&items[part_start..index]);
+                        part_start = index;
+                    }
+                }
+                if part_start < items.len() {
+                    parts.push(&items[part_start..]);
+                }
+                parts
+            }
+        }
+    }
+}
+
+/// "Part 2 of 5, continued from part 1 at 01:00:00" for one file of a `--split-every` output
+fn part_header(dest: &OutputDestination, items: &[TranscriptItem]) -> Option<String> {
+    let (number, total) = dest.part_number()?;
+    let mut header = format!("Part {} of {}", number, total);
+    if number > 1 {
+        let start = items.first().map_or(0.0, |item| item.start);
+        header.push_str(&format!(
+            ", continued from part {} at {}",
+            number - 1,
+            format_timecode(start, '.', false)
+        ));
+    }
+    Some(header)
+}
+
+/// Character encoding for written output (`--encoding`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputEncoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+}
+
+impl OutputEncoding {
+    /// Parse an `--encoding` value; clap has already restricted it to the known names
+    fn from_name(name: &str) -> Self {
+        match name {
+            "utf-8-bom" => OutputEncoding::Utf8Bom,
+            "utf-16le" => OutputEncoding::Utf16Le,
+            _ => OutputEncoding::Utf8,
+        }
+    }
+
+    fn byte_order_mark(self) -> &'static [u8] {
+        match self {
+            OutputEncoding::Utf8 => b"",
+            OutputEncoding::Utf8Bom => b"\xEF\xBB\xBF",
+            OutputEncoding::Utf16Le => b"\xFF\xFE",
+        }
+    }
+}
+
+/// Re-encodes the UTF-8 written to it as UTF-16LE
+///
+/// A character split across two writes is held back until the rest of it arrives.
+struct Utf16LeWriter {
+    inner: Box<dyn Write>,
+    pending: Vec<u8>,
+}
+
+impl Utf16LeWriter {
+    fn new(inner: Box<dyn Write>) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Write for Utf16LeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text,
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&self.pending[..e.valid_up_to()]).unwrap_or_default()
+            }
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let encoded: Vec<u8> = complete.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let consumed = complete.len();
+        self.inner.write_all(&encoded)?;
+        self.pending.drain(..consumed);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Separate one video's section from the previous one in an `--append` file or a playlist printed with `-o -`
+fn write_section_header(
+    dest: &OutputDestination,
+    format: &str,
+    title: &str,
+    encoding: OutputEncoding,
+) -> Result<(), TranscriptError> {
+    let header = match format {
+        "markdown" | "md" => format!("## {}", title),
+        "text" | "txt" => format!("=== {} ===", title),
+        // jsonl lines carry no header
+        _ => return Ok(()),
+    };
+    let is_first = dest.at_start();
+    let mut writer = dest.encoded(encoding).writer()?;
+    if !is_first {
+        writeln!(writer)?;
+    }
+    writeln!(writer, "{}\n", header)?;
+    Ok(())
+}
+
+/// A cue with its position in the transcript (`--json-index`), serialized as the
+/// cue's own fields preceded by `index` and `id`
+#[derive(Serialize)]
+struct IndexedCue<'a> {
+    index: usize,
+    id: String,
+    #[serde(flatten)]
+    item: &'a TranscriptItem,
+}
+
+fn indexed_cues<'a>(items: &'a [TranscriptItem], video_id: &str) -> Vec<IndexedCue<'a>> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(position, item)| IndexedCue {
+            index: position + 1,
+            id: format!("{}-{:06}", video_id, position + 1),
+            item,
+        })
+        .collect()
+}
+
+fn output_json<T: Serialize>(items: &[T], dest: &OutputDestination) -> Result<(), TranscriptError> {
+    let json = serde_json::to_string_pretty(items)?;
+    let mut writer = dest.writer()?;
+    writeln!(writer, "{}", json)?;
+    Ok(())
+}
+
+/// Cues with their per-word timing (`--words`)
+fn output_words_json(
+    items: &[WordTimedItem],
+    dest: &OutputDestination,
+) -> Result<(), TranscriptError> {
+    let json = serde_json::to_string_pretty(items)?;
+    let mut writer = dest.writer()?;
+    writeln!(writer, "{}", json)?;
+    Ok(())
+}
+
+/// Cues with their confidence and kind (`--json-verbose`)
+fn output_verbose_json(
+    items: &[VerboseItem],
+    dest: &OutputDestination,
+) -> Result<(), TranscriptError> {
+    let json = serde_json::to_string_pretty(items)?;
+    let mut writer = dest.writer()?;
+    writeln!(writer, "{}", json)?;
+    Ok(())
+}
+
+/// One JSON object per line, without an enclosing array
+fn output_jsonl<T: Serialize>(
+    items: &[T],
+    dest: &OutputDestination,
+) -> Result<(), TranscriptError> {
+    let mut writer = dest.writer()?;
+    for item in items {
+        writeln!(writer, "{}", serde_json::to_string(item)?)?;
+    }
+    Ok(())
+}
+
+fn output_srt(items: &[TranscriptItem], dest: &OutputDestination) -> Result<(), TranscriptError> {
+    let mut writer = dest.writer()?;
+
+    for (index, item) in items.iter().enumerate() {
+        writeln!(writer, "{}", index + 1)?;
+
+        let start_time = format_srt_time(item.start);
+        let end_time = format_srt_time(item.start + item.duration);
+
+        writeln!(writer, "{} --> {}", start_time, end_time)?;
+        writeln!(writer, "{}", item.text)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+fn output_text(
+    items: &[TranscriptItem],
+    dest: &OutputDestination,
+    timestamps_format: &str,
+    video_url: Option<&str>,
+    video_title: Option<&str>,
+    metadata: bool,
+) -> Result<(), TranscriptError> {
+    let mut writer = dest.writer()?;
+
+    // Prefix with the metadata block, or title and URL if provided
+    if metadata {
+        write_metadata_header(&mut writer, items, video_url, video_title, false)?;
+    } else if let (Some(url), Some(title)) = (video_url, video_title) {
+        writeln!(writer, "{}: {}", title, url)?;
+        writeln!(writer)?;
+    }
+    if let Some(header) = part_header(dest, items) {
+        writeln!(writer, "{}\n", header)?;
+    }
+
+    for item in items {
+        writeln!(
+            writer,
+            "[{}] {}",
+            format_timestamp(item.start, timestamps_format),
+            item.text
+        )?;
+    }
+
+    Ok(())
+}
+
+fn output_text_only(
+    items: &[TranscriptItem],
+    dest: &OutputDestination,
+    video_url: Option<&str>,
+    video_title: Option<&str>,
+    metadata: bool,
+) -> Result<(), TranscriptError> {
+    let mut writer = dest.writer()?;
+
+    // Prefix with the metadata block, or title and URL if provided
+    if metadata {
+        write_metadata_header(&mut writer, items, video_url, video_title, false)?;
+    } else if let (Some(url), Some(title)) = (video_url, video_title) {
+        writeln!(writer, "{}: {}", title, url)?;
+        writeln!(writer)?;
+    }
+    if let Some(header) = part_header(dest, items) {
+        writeln!(writer, "{}\n", header)?;
+    }
+
+    for item in items {
+        writeln!(writer, "{}", item.text)?;
+    }
+
+    Ok(())
+}
+
+/// How markdown output is laid out
+struct MarkdownOptions<'a> {
+    /// Title heading, written unless the text already starts with one
+    heading: &'a str,
+    /// `--timestamps-format` to prefix each cue with, if any
+    timestamps: Option<&'a str>,
+    /// Watch URL each cue links back into at its start time (`--append-url-per-cue`)
+    cue_links: Option<&'a str>,
+    video_url: Option<&'a str>,
+    video_title: Option<&'a str>,
+    /// Start with the `--metadata` block
+    metadata: bool,
+    /// List the `##` sections after the title (`--markdown-toc`)
+    toc: bool,
+    /// Start with YAML front matter (`--front-matter`)
+    front_matter: Option<FrontMatter<'a>>,
+    /// One video's section of a multi-video output, which already has a header with
+    /// its title, so no title or front matter of its own
+    section: bool,
+}
+
+/// YAML front matter for static site generators (`--front-matter`)
+struct FrontMatter<'a> {
+    /// Video title, or its ID when the title is unknown
+    title: &'a str,
+    url: String,
+    /// `YYYY-MM-DD` the transcript was fetched
+    date: String,
+}
+
+impl FrontMatter<'_> {
+    fn write(&self, writer: &mut dyn Write) -> Result<(), TranscriptError> {
+        // A JSON string is also a valid double-quoted YAML scalar, so titles with
+        // colons or quotes need no further escaping
+        writeln!(writer, "---")?;
+        writeln!(writer, "title: {}", serde_json::to_string(self.title)?)?;
+        writeln!(writer, "url: {}", self.url)?;
+        writeln!(writer, "date: {}", self.date)?;
+        writeln!(writer, "---\n")?;
+        Ok(())
+    }
+}
+
+impl<'a> MarkdownOptions<'a> {
+    fn new(heading: &'a str) -> Self {
+        Self {
+            heading,
+            timestamps: None,
+            cue_links: None,
+            video_url: None,
+            video_title: None,
+            metadata: false,
+            toc: false,
+            front_matter: None,
+            section: false,
+        }
+    }
+}
+
+fn output_markdown(
+    items: &[TranscriptItem],
+    dest: &OutputDestination,
+    options: &MarkdownOptions,
+) -> Result<(), TranscriptError> {
+    write_markdown(dest, items, options, |body| {
+        // If there's only one item and it contains markdown (from ChatGPT cleanup),
+        // output it directly without adding extra formatting
+        if items.len() == 1
+            && (items[0].text.contains("**")
+                || items[0].text.contains("##")
+                || items[0].text.contains("*"))
+        {
+            // Already formatted by ChatGPT, just add heading if not present
+            // (appended sections already have their own)
+            if !options.section && !items[0].text.trim_start().starts_with("#") {
+                write_markdown_title(body, options.heading, dest, items)?;
+            }
+            writeln!(body, "{}", items[0].text)?;
+        } else {
+            // Regular markdown output
+            if !options.section {
+                write_markdown_title(body, options.heading, dest, items)?;
+            }
+
+            for item in items {
+                write_markdown_cue(body, item, options)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Markdown with a `##` section per chapter (`--chapters`)
+fn output_markdown_chapters(
+    sections: &[(&str, Vec<&TranscriptItem>)],
+    dest: &OutputDestination,
+    options: &MarkdownOptions,
+) -> Result<(), TranscriptError> {
+    let items: Vec<TranscriptItem> = sections
+        .iter()
+        .flat_map(|(_, cues)| cues.iter().map(|cue| (*cue).clone()))
+        .collect();
+    write_markdown(dest, &items, options, |body| {
+        if !options.section {
+            write_markdown_title(body, options.heading, dest, &items)?;
+        }
+
+        for (title, cues) in sections {
+            writeln!(body, "## {}\n", title)?;
+            for cue in cues {
+                write_markdown_cue(body, cue, options)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// The `#` title, followed by the part header when this is one file of a `--split-every` output
+fn write_markdown_title(
+    body: &mut dyn Write,
+    heading: &str,
+    dest: &OutputDestination,
+    items: &[TranscriptItem],
+) -> Result<(), TranscriptError> {
+    writeln!(body, "# {}\n", heading)?;
+    if let Some(header) = part_header(dest, items) {
+        writeln!(body, "*{}*\n", header)?;
+    }
+    Ok(())
+}
+
+/// Write the metadata block or video link, then the body `write_body` produces, with
+/// its table of contents
+fn write_markdown(
+    dest: &OutputDestination,
+    items: &[TranscriptItem],
+    options: &MarkdownOptions,
+    write_body: impl FnOnce(&mut dyn Write) -> Result<(), TranscriptError>,
+) -> Result<(), TranscriptError> {
+    let mut writer = dest.writer()?;
+    if let (Some(front_matter), false) = (&options.front_matter, options.section) {
+        front_matter.write(&mut writer)?;
+    }
+    if options.metadata {
+        write_metadata_header(
+            &mut writer,
+            items,
+            options.video_url,
+            options.video_title,
+            true,
+        )?;
+    } else if let (Some(url), Some(title)) = (options.video_url, options.video_title) {
+        writeln!(writer, "![{}]({})\n", title, url)?;
+    }
+
+    if options.toc {
+        // The table of contents lists every heading, so the body is built first
+        let mut body = Vec::new();
+        write_body(&mut body)?;
+        writer.write_all(insert_markdown_toc(&String::from_utf8_lossy(&body)).as_bytes())?;
+    } else {
+        write_body(&mut writer)?;
+    }
+    Ok(())
+}
+
+/// Add a `- [Section](#section)` list of the `##` headings in `markdown` after its
+/// `#` title (or at the top when there is none). Documents with fewer than two
+/// sections are returned unchanged.
+fn insert_markdown_toc(markdown: &str) -> String {
+    let mut headings = Vec::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        } else if let (false, Some(heading)) = (in_code_block, line.strip_prefix("## ")) {
+            headings.push(heading.trim());
+        }
+    }
+    if headings.len() < 2 {
+        return markdown.to_string();
+    }
+
+    // Repeated headings get -1, -2, ... like the anchors GitHub generates
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut toc = String::new();
+    for heading in headings {
+        let slug = heading_slug(heading);
+        let count = seen.entry(slug.clone()).or_insert(0);
+        let anchor = if *count == 0 {
+            slug
+        } else {
+            format!("{}-{}", slug, count)
+        };
+        *count += 1;
+        toc.push_str(&format!("- [{}](#{})\n", escape_link_text(heading), anchor));
+    }
+    toc.push('\n');
+
+    let title_end = markdown
+        .lines()
+        .next()
+        .filter(|line| line.starts_with("# "))
+        .map(|line| {
+            let after_title = line.len() + 1;
+            let blank = markdown[after_title.min(markdown.len())..].starts_with('\n');
+            (after_title + usize::from(blank)).min(markdown.len())
+        })
+        .unwrap_or(0);
+    format!(
+        "{}{}{}",
+        &markdown[..title_end],
+        toc,
+        &markdown[title_end..]
+    )
+}
+
+/// GitHub-style anchor for a heading: lowercase, punctuation dropped, spaces as hyphens
+fn heading_slug(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+fn write_markdown_cue(
+    writer: &mut dyn Write,
+    item: &TranscriptItem,
+    options: &MarkdownOptions,
+) -> Result<(), TranscriptError> {
+    if let Some(timestamps_format) = options.timestamps {
+        write!(
+            writer,
+            "**[{}]** {}",
+            format_timestamp(item.start, timestamps_format),
+            item.text
+        )?;
+    } else {
+        write!(writer, "{}", item.text)?;
+    }
+    if let Some(url) = options.cue_links {
+        write!(writer, " [▶]({}&t={}s)", url, item.start.floor() as u64)?;
+    }
+    writeln!(writer)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// File extension used for auto-generated output filenames
+fn format_extension(format: &str) -> &'static str {
+    match format.to_lowercase().as_str() {
+        "json" => "json",
+        "jsonl" | "ndjson" => "jsonl",
+        "srt" => "srt",
+        "html" => "html",
+        "markdown" | "md" => "md",
+        "text" | "txt" => "txt",
+        _ => "txt",
+    }
+}
+
+/// Standalone HTML page where every cue links to its moment in the video
+fn output_html(
+    items: &[TranscriptItem],
+    dest: &OutputDestination,
+    video_id: &str,
+    video_title: Option<&str>,
+) -> Result<(), TranscriptError> {
+    let mut writer = dest.writer()?;
+    let title = escape_html(video_title.unwrap_or("Transcript"));
+
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html>")?;
+    writeln!(writer, "<head>")?;
+    writeln!(writer, "<meta charset=\"utf-8\">")?;
+    writeln!(writer, "<title>{}</title>", title)?;
+    writeln!(
+        writer,
+        "<style>body {{ font-family: sans-serif; max-width: 48em; margin: 2em auto; line-height: 1.5; }} \
+         .cue a {{ color: #065fd4; text-decoration: none; font-variant-numeric: tabular-nums; margin-right: 0.5em; }}</style>"
+    )?;
+    writeln!(writer, "</head>")?;
+    writeln!(writer, "<body>")?;
+    writeln!(
+        writer,
+        "<h1><a href=\"https://www.youtube.com/watch?v={}\">{}</a></h1>",
+        video_id, title
+    )?;
+
+    for item in items {
+        let seconds = item.start.max(0.0) as u64;
+        writeln!(
+            writer,
+            "<p class=\"cue\"><a href=\"https://www.youtube.com/watch?v={}&amp;t={}s\">{}</a>{}</p>",
+            video_id,
+            seconds,
+            &format_timecode(item.start, ',', false),
+            escape_html(&item.text)
+        )?;
+    }
+
+    writeln!(writer, "</body>")?;
+    writeln!(writer, "</html>")?;
+
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Write the `--metadata` block: title, URL, total duration, cue count and word count
+fn write_metadata_header(
+    writer: &mut dyn Write,
+    items: &[TranscriptItem],
+    video_url: Option<&str>,
+    video_title: Option<&str>,
+    markdown: bool,
+) -> Result<(), TranscriptError> {
+    let total_duration: f64 = items.iter().map(|item| item.duration).sum();
+    let word_count: usize = items
+        .iter()
+        .map(|item| item.text.split_whitespace().count())
+        .sum();
+
+    let mut fields = Vec::new();
+    if let Some(title) = video_title {
+        fields.push(("Title", title.to_string()));
+    }
+    if let Some(url) = video_url {
+        fields.push(("URL", url.to_string()));
+    }
+    // Drop the milliseconds from the SRT timestamp
+    fields.push(("Duration", format_timecode(total_duration, ',', false)));
+    fields.push(("Cues", items.len().to_string()));
+    fields.push(("Words", word_count.to_string()));
+
+    for (name, value) in fields {
+        if markdown {
+            writeln!(writer, "- **{}:** {}", name, value)?;
+        } else {
+            writeln!(writer, "{}: {}", name, value)?;
+        }
+    }
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+/// Timestamp prefix for text and markdown lines, in the `--timestamps-format` style
+fn format_timestamp(seconds: f64, timestamps_format: &str) -> String {
+    match timestamps_format {
+        "mmss" => {
+            // Round to milliseconds first, like format_timecode, so mmss and hms agree
+            let total = (seconds.max(0.0) * 1000.0).round() as u64 / 1000;
+            format!("{:02}:{:02}", total / 60, total % 60)
+        }
+        "hms" => format_timecode(seconds, ',', false),
+        _ => format!("{:.2}s", seconds),
+    }
+}
+
+fn format_srt_time(seconds: f64) -> String {
+    timecode::format_srt_time(seconds)
+}
+
+fn sanitize_filename(title: &str) -> String {
     // Replace invalid filesystem characters with underscores
     let sanitized: String = title
         .chars()
@@ -483,38 +3827,963 @@ fn sanitize_filename(title: &str) -> String {
 
     // Trim whitespace and replace multiple spaces/underscores with single underscore
     let sanitized = sanitized.trim();
-    let sanitized = sanitized
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join("_");
-    let sanitized = sanitized
-        .chars()
-        .fold(String::new(), |mut acc, c| {
-            if c == '_' {
-                if !acc.ends_with('_') {
-                    acc.push(c);
+    let sanitized = sanitized.split_whitespace().collect::<Vec<_>>().join("_");
+    let sanitized = sanitized.chars().fold(String::new(), |mut acc, c| {
+        if c == '_' {
+            if !acc.ends_with('_') {
+                acc.push(c);
+            }
+        } else {
+            acc.push(c);
+        }
+        acc
+    });
+
+    // Limit length to 200 characters (reasonable for most filesystems).
+    // Count chars rather than bytes so multibyte titles are never cut mid-character.
+    let sanitized: String = sanitized.chars().take(200).collect();
+
+    sanitized.trim_end_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(&TranscriptError::VideoUnavailable("x".into())), 2);
+        assert_eq!(
+            exit_code(&TranscriptError::TranscriptsDisabled("x".into())),
+            3
+        );
+        assert_eq!(exit_code(&TranscriptError::EmptyTranscript("x".into())), 3);
+        assert_eq!(exit_code(&TranscriptError::RequestBlocked("x".into())), 4);
+        assert_eq!(exit_code(&TranscriptError::IpBlocked("x".into())), 4);
+        assert_eq!(exit_code(&TranscriptError::RateLimited("x".into())), 4);
+        assert_eq!(exit_code(&TranscriptError::IoError("x".into())), 5);
+        assert_eq!(exit_code(&TranscriptError::InvalidArgument("x".into())), 6);
+        assert_eq!(exit_code(&TranscriptError::HttpError("x".into())), 1);
+    }
+
+    #[test]
+    fn test_requested_formats() {
+        assert_eq!(requested_formats("text"), vec!["text"]);
+        assert_eq!(
+            requested_formats("JSON, srt,txt"),
+            vec!["json", "srt", "txt"]
+        );
+        assert_eq!(requested_formats("all"), vec!["json", "srt", "txt", "md"]);
+    }
+
+    #[tokio::test]
+    async fn test_write_outputs_writes_each_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "-f", "json,srt"]);
+        let items = vec![TranscriptItem {
+            text: "Hello".to_string(),
+            start: 0.0,
+            duration: 1.5,
+        }];
+        let json_path = temp_dir.path().join("out.json");
+        let srt_path = temp_dir.path().join("out.srt");
+        let outputs = vec![
+            (
+                "json".to_string(),
+                OutputDestination::File(json_path.to_string_lossy().to_string()),
+            ),
+            (
+                "srt".to_string(),
+                OutputDestination::File(srt_path.to_string_lossy().to_string()),
+            ),
+        ];
+
+        write_outputs(&args, outputs, items, "dQw4w9WgXcQ", None, Vec::new())
+            .await
+            .unwrap();
+
+        assert!(fs::read_to_string(&json_path)
+            .unwrap()
+            .contains("\"Hello\""));
+        assert!(fs::read_to_string(&srt_path)
+            .unwrap()
+            .contains("00:00:00,000 --> 00:00:01,500"));
+    }
+
+    #[tokio::test]
+    async fn test_write_outputs_reports_first_failure_in_format_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "-f", "json,srt"]);
+        let missing = temp_dir.path().join("missing");
+        let outputs = vec![
+            (
+                "json".to_string(),
+                OutputDestination::File(missing.join("a.json").to_string_lossy().to_string()),
+            ),
+            (
+                "srt".to_string(),
+                OutputDestination::File(missing.join("b.srt").to_string_lossy().to_string()),
+            ),
+        ];
+
+        let err = write_outputs(&args, outputs, Vec::new(), "dQw4w9WgXcQ", None, Vec::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("a.json"));
+    }
+
+    #[test]
+    fn test_min_duration_defaults_to_zero() {
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ"]);
+        assert_eq!(args.min_duration, 0.0);
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--min-duration", "0.1"]);
+        assert_eq!(args.min_duration, 0.1);
+    }
+
+    #[test]
+    fn test_offset_accepts_negative_values() {
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--offset", "-2.5"]);
+        assert_eq!(args.offset, Some(-2.5));
+    }
+
+    #[test]
+    fn test_parse_language_pair() {
+        assert_eq!(parse_language_pair("en,es").unwrap(), ("en", "es"));
+        assert_eq!(
+            parse_language_pair(" en , pt-BR ").unwrap(),
+            ("en", "pt-BR")
+        );
+        assert!(parse_language_pair("en").is_err());
+        assert!(parse_language_pair("en,").is_err());
+    }
+
+    #[test]
+    fn test_output_bilingual() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("out.txt");
+        let dest = OutputDestination::File(path.to_string_lossy().to_string());
+        let cues = vec![
+            BilingualCue {
+                start: 0.0,
+                duration: 2.0,
+                original: "hello".into(),
+                translated: "hola".into(),
+            },
+            BilingualCue {
+                start: 65.0,
+                duration: 2.0,
+                original: "bye".into(),
+                translated: String::new(),
+            },
+        ];
+
+        output_bilingual(&cues, &dest, false, Some("mmss"), None).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[00:00] hello\n        hola\n\n[01:05] bye\n\n"
+        );
+
+        output_bilingual(&cues, &dest, true, None, Some("Greetings")).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# Greetings\n\nhello  \n*hola*\n\nbye\n\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short text", 100), "short text");
+        assert_eq!(truncate_chars("hello wonderful world", 10), "hello");
+        assert_eq!(
+            truncate_chars("hello wonderful world", 15),
+            "hello wonderful"
+        );
+        assert_eq!(truncate_chars("unbrokenword", 4), "unbr");
+        assert_eq!(truncate_chars("héllo wörld", 9), "héllo");
+
+        assert!(Args::try_parse_from(["ytt", "VIDEO", "--max-input-chars", "1000"]).is_err());
+        assert!(
+            Args::try_parse_from(["ytt", "VIDEO", "--cleanup", "--max-input-chars", "0"]).is_err()
+        );
+        assert!(
+            Args::try_parse_from(["ytt", "VIDEO", "--cleanup", "--max-input-chars", "1000"])
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_format_detected_language() {
+        let track = TranscriptInfo {
+            video_id: "abc".to_string(),
+            language_code: "en".to_string(),
+            language: "English (auto-generated)".to_string(),
+            is_generated: true,
+            is_translatable: true,
+            base_url: String::new(),
+            translation_languages: vec![],
+        };
+        assert_eq!(
+            format_detected_language(&track),
+            "en\tauto\tEnglish (auto-generated)"
+        );
+        assert!(Args::try_parse_from(["ytt", "VIDEO", "--detect-language", "--list"]).is_err());
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let stats = TranscriptStats {
+            cues: 3,
+            duration: 30.0,
+            words: 10,
+            words_per_minute: 20.0,
+            average_cue_duration: 10.0,
+        };
+        assert_eq!(
+            format_stats("abc", "en", &stats),
+            "video_id=abc\nlanguage=en\ncues=3\nduration=30.00\nwords=10\nwords_per_minute=20.0\naverage_cue_duration=10.00\n"
+        );
+    }
+
+    #[test]
+    fn test_expand_output_template() {
+        let fields = TemplateFields {
+            id: "abc",
+            title: Some("Intro: Part 1"),
+            index: 7,
+            lang: "en",
+            ext: "srt",
+            date: "2024-05-01",
+        };
+        assert_eq!(
+            expand_output_template("{index:03}-{title}.{ext}", &fields).unwrap(),
+            "007-Intro_Part_1.srt"
+        );
+        assert_eq!(
+            expand_output_template("{date}_{id}.{lang}.{ext}", &fields).unwrap(),
+            "2024-05-01_abc.en.srt"
+        );
+        assert!(expand_output_template("{nope}.txt", &fields).is_err());
+        assert!(expand_output_template("{index:3}.txt", &fields).is_err());
+        assert!(expand_output_template("{id", &fields).is_err());
+    }
+
+    #[test]
+    fn test_output_template_destination() {
+        let args = Args::parse_from([
+            "ytt",
+            "PLxyz",
+            "-p",
+            "--output-template",
+            "{index:02}_{id}.{ext}",
+            "-o",
+            "out/",
+        ]);
+        let dest = output_destination(&args, "abc", None, "en", Some(4), "json").unwrap();
+        let expected = Path::new("out/").join("04_abc.json");
+        assert!(matches!(dest, OutputDestination::File(path) if Path::new(&path) == expected));
+    }
+
+    #[test]
+    fn test_mdbook_chapter_destination() {
+        let args = Args::parse_from(["ytt", "PLxyz", "-p", "--mdbook", "book"]);
+        let dest =
+            output_destination(&args, "abc", Some("Intro: Part 1"), "en", Some(3), "md").unwrap();
+        let expected = Path::new("book").join("src").join("003_Intro_Part_1.md");
+        assert!(matches!(dest, OutputDestination::File(path) if Path::new(&path) == expected));
+    }
+
+    #[test]
+    fn test_mdbook_chapter_heading_and_summary() {
+        let temp_dir = TempDir::new().unwrap();
+        let book_dir = temp_dir.path();
+        let src_dir = book_dir.join("src");
+        fs::create_dir_all(&src_dir).unwrap();
+
+        let items = vec![TranscriptItem {
+            text: "Welcome".to_string(),
+            start: 0.0,
+            duration: 1.0,
+        }];
+        for (file, title) in [
+            ("002_Second.md", "[LIVE] Second"),
+            ("001_First.md", "First"),
+        ] {
+            let dest = OutputDestination::File(src_dir.join(file).to_string_lossy().to_string());
+            output_markdown(&items, &dest, &MarkdownOptions::new(title)).unwrap();
+        }
+        write_mdbook_summary(book_dir, "PLxyz").unwrap();
+
+        let chapter = fs::read_to_string(src_dir.join("001_First.md")).unwrap();
+        assert!(chapter.starts_with("# First\n"));
+        let summary = fs::read_to_string(src_dir.join("SUMMARY.md")).unwrap();
+        assert_eq!(
+            summary,
+            "# Summary\n\n- [First](./001_First.md)\n- [\\[LIVE\\] Second](./002_Second.md)\n"
+        );
+        assert!(fs::read_to_string(book_dir.join("book.toml"))
+            .unwrap()
+            .contains("title = \"PLxyz\""));
+    }
+
+    #[test]
+    fn test_resume_state_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("state.json")
+            .to_string_lossy()
+            .to_string();
+
+        let mut state = ResumeState::load(&path).unwrap();
+        assert!(!state.is_completed("abc"));
+        state.mark_completed("def").unwrap();
+        state.mark_completed("abc").unwrap();
+        state.mark_completed("abc").unwrap();
+
+        let state = ResumeState::load(&path).unwrap();
+        assert_eq!(state.completed.len(), 2);
+        assert!(state.is_completed("def"));
+        let file: ResumeFile = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(file.completed, vec!["abc", "def"]);
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(ResumeState::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_resume_keeps_playlist_positions() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("state.json")
+            .to_string_lossy()
+            .to_string();
+        let mut state = ResumeState::load(&path).unwrap();
+        state.mark_completed("aaaaaaaaaaa").unwrap();
+        state.mark_completed("ccccccccccc").unwrap();
+
+        let ids = [
+            "aaaaaaaaaaa",
+            "bbbbbbbbbbb",
+            "ccccccccccc",
+            "ddddddddddd",
+            "eeeeeeeeeee",
+        ];
+        let videos = select_playlist_videos(
+            ids.iter().map(|id| id.to_string()).collect(),
+            Some(&state),
+            Some(2),
+        );
+        assert_eq!(
+            videos,
+            vec![
+                (2, "bbbbbbbbbbb".to_string()),
+                (4, "ddddddddddd".to_string())
+            ]
+        );
+
+        let book_dir = temp_dir.path().join("book").to_string_lossy().to_string();
+        let args = Args::parse_from([
+            "ytt",
+            "PLxyz",
+            "--playlist",
+            "--resume",
+            &path,
+            "--mdbook",
+            &book_dir,
+        ]);
+        let (position, video_id) = &videos[1];
+        let dest = output_destination(&args, video_id, Some("Outro"), "en", Some(*position), "md")
+            .unwrap();
+        assert!(matches!(dest, OutputDestination::File(path) if path.ends_with("004_Outro.md")));
+
+        let args = Args::parse_from([
+            "ytt",
+            "PLxyz",
+            "--playlist",
+            "--resume",
+            &path,
+            "--output-template",
+            "{index}-{id}.{ext}",
+        ]);
+        let dest = output_destination(&args, video_id, None, "en", Some(*position), "txt").unwrap();
+        assert!(matches!(dest, OutputDestination::File(path) if path == "4-ddddddddddd.txt"));
+    }
+
+    #[test]
+    fn test_format_utc_timestamp() {
+        assert_eq!(format_utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_utc_timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            format_utc_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_sidecar_targets_share_basename() {
+        let outputs = vec![
+            (
+                "json".to_string(),
+                OutputDestination::File("out/talk.json".to_string()),
+            ),
+            (
+                "srt".to_string(),
+                OutputDestination::GzipFile("out/talk.srt.gz".to_string()),
+            ),
+            ("txt".to_string(), OutputDestination::Stdout),
+        ];
+        assert_eq!(
+            sidecar_targets(&outputs),
+            vec![(
+                "out/talk.meta.json".to_string(),
+                vec!["json".to_string(), "srt".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_write_sidecars() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("abc.meta.json")
+            .to_string_lossy()
+            .to_string();
+        let sidecar = Sidecar::new("abc", Some("A talk"), "English", "en", true);
+        write_sidecars(&[(path.clone(), vec!["srt".to_string()])], sidecar).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["video_id"], "abc");
+        assert_eq!(json["title"], "A talk");
+        assert_eq!(json["language_code"], "en");
+        assert_eq!(json["formats"], serde_json::json!(["srt"]));
+        assert!(json["fetched_at"].as_str().unwrap().ends_with('Z'));
+    }
+
+    #[test]
+    fn test_prompt_for_choice() {
+        let mut input = io::Cursor::new("abc\n7\n2\n");
+        let mut prompt = Vec::new();
+        assert_eq!(prompt_for_choice(&mut input, &mut prompt, 3).unwrap(), 1);
+        let prompt = String::from_utf8(prompt).unwrap();
+        assert_eq!(prompt.matches("Select a transcript [1-3]: ").count(), 3);
+        assert_eq!(
+            prompt.matches("Please enter a number from 1 to 3").count(),
+            2
+        );
+
+        let mut input = io::Cursor::new("");
+        assert!(prompt_for_choice(&mut input, &mut Vec::new(), 3).is_err());
+    }
+
+    #[test]
+    fn test_dump_raw_is_hidden() {
+        use clap::CommandFactory;
+
+        let args = Args::parse_from(["ytt", "VIDEO", "--dump-raw", "-o", "raw.xml"]);
+        assert!(args.dump_raw);
+        let command = Args::command();
+        let dump_raw = command
+            .get_arguments()
+            .find(|a| a.get_id() == "dump_raw")
+            .unwrap();
+        assert!(dump_raw.is_hide_set());
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_requires_playlist() {
+        assert!(Args::parse_from(["ytt", "PLxyz", "-p", "--fail-fast"]).fail_fast);
+        let args = Args::parse_from(["ytt", "VIDEO", "--fail-fast"]);
+        assert!(matches!(
+            run(args).await,
+            Err(TranscriptError::InvalidArgument(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sort_requires_playlist() {
+        let args = Args::parse_from(["ytt", "PLxyz", "-p", "--sort", "reverse"]);
+        assert_eq!(args.sort, "reverse");
+        assert_eq!(Args::parse_from(["ytt", "PLxyz", "-p"]).sort, "none");
+        let args = Args::parse_from(["ytt", "VIDEO", "--sort", "reverse"]);
+        assert!(matches!(
+            run(args).await,
+            Err(TranscriptError::InvalidArgument(_))
+        ));
+        assert!(Args::try_parse_from(["ytt", "PLxyz", "-p", "--sort", "newest"]).is_err());
+    }
+
+    #[test]
+    fn test_channel_url_takes_playlist_flags() {
+        let args = Args::parse_from([
+            "ytt",
+            "https://www.youtube.com/@rustlang",
+            "--resume",
+            "state.json",
+            "--fail-fast",
+        ]);
+        assert_eq!(playlist_only_flag(&args), Some("--fail-fast"));
+        assert!(YouTubeTranscript::is_channel_url(
+            args.video.as_deref().unwrap()
+        ));
+        let args = Args::parse_from([
+            "ytt",
+            "@rustlang",
+            "--append",
+            "-o",
+            "all.txt",
+            "--since",
+            "2024-01-01",
+        ]);
+        assert_eq!(playlist_only_flag(&args), Some("--since"));
+        assert_eq!(
+            playlist_only_flag(&Args::parse_from(["ytt", "VIDEO"])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_input_file_replaces_video_argument() {
+        let args = Args::parse_from(["ytt", "--input-file", "captions.xml", "-f", "srt"]);
+        assert_eq!(args.input_file.as_deref(), Some("captions.xml"));
+        assert!(args.video.is_none());
+        assert!(Args::try_parse_from(["ytt"]).is_err());
+        assert!(Args::try_parse_from(["ytt", "VIDEO", "--input-file", "captions.xml"]).is_err());
+    }
+
+    #[test]
+    fn test_read_input_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("lecture.xml");
+        fs::write(
+            &path,
+            r#"<transcript><text start="0" dur="1.5">Hello</text><text start="1.5" dur="2">world</text></transcript>"#,
+        )
+        .unwrap();
+
+        let transcript = read_input_file(path.to_str().unwrap(), false).unwrap();
+        assert_eq!(transcript.video_id, "lecture");
+        assert_eq!(transcript.title.as_deref(), Some("lecture"));
+        assert_eq!(transcript.transcript.len(), 2);
+        assert_eq!(transcript.transcript[1].start, 1.5);
+
+        let empty = temp_dir.path().join("empty.xml");
+        fs::write(&empty, "<transcript></transcript>").unwrap();
+        assert!(matches!(
+            read_input_file(empty.to_str().unwrap(), false),
+            Err(TranscriptError::EmptyTranscript(_))
+        ));
+        assert!(read_input_file("/nonexistent/captions.xml", false).is_err());
+    }
+
+    #[test]
+    fn test_read_input_file_json_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("talk.json");
+        let items = vec![
+            TranscriptItem {
+                text: "Hello".into(),
+                start: 0.08,
+                duration: 1.5,
+            },
+            TranscriptItem {
+                text: "\"quoted\" & more".into(),
+                start: 1.58,
+                duration: 2.25,
+            },
+        ];
+        output_json(
+            &items,
+            &OutputDestination::File(path.to_string_lossy().to_string()),
+        )
+        .unwrap();
+
+        let transcript = read_input_file(path.to_str().unwrap(), false).unwrap();
+        assert_eq!(transcript.video_id, "talk");
+        assert_eq!(transcript.transcript.len(), 2);
+        assert_eq!(transcript.transcript[1].text, "\"quoted\" & more");
+        assert_eq!(transcript.transcript[1].start, 1.58);
+        assert_eq!(transcript.transcript[1].duration, 2.25);
+
+        let broken = temp_dir.path().join("broken.json");
+        fs::write(&broken, r#"[{"text": "no times"}]"#).unwrap();
+        assert!(matches!(
+            read_input_file(broken.to_str().unwrap(), false),
+            Err(TranscriptError::JsonParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_input_file_vtt() {
+        let temp_dir = TempDir::new().unwrap();
+        // Detected by the WEBVTT header even without a .vtt extension
+        let path = temp_dir.path().join("captions.txt");
+        fs::write(
+            &path,
+            "WEBVTT\n\n1\n00:00:01.000 --> 00:00:02.500 line:90%\nHello\n\n00:02.500 --> 00:04.000\nworld\n",
+        )
+        .unwrap();
+
+        let transcript = read_input_file(path.to_str().unwrap(), false).unwrap();
+        assert_eq!(transcript.transcript.len(), 2);
+        assert_eq!(transcript.transcript[0].duration, 1.5);
+        assert_eq!(transcript.transcript[1].text, "world");
+
+        let broken = temp_dir.path().join("broken.vtt");
+        fs::write(&broken, "WEBVTT\n\n00:01.000 --> later\nHi\n").unwrap();
+        assert!(matches!(
+            read_input_file(broken.to_str().unwrap(), false),
+            Err(TranscriptError::SubtitleParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_append_writes_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("course.md")
+            .to_string_lossy()
+            .to_string();
+        let args = Args::parse_from(["ytt", "PLxyz", "-p", "-f", "md", "--append", "-o", &path]);
+        validate_append(&args).unwrap();
+
+        let dest =
+            output_destination(&args, "aaaaaaaaaaa", Some("Intro"), "en", Some(1), "md").unwrap();
+        assert!(dest.is_append());
+        let items = vec![TranscriptItem {
+            text: "First".to_string(),
+            start: 0.0,
+            duration: 1.0,
+        }];
+        write_output(
+            &args,
+            "md",
+            &items,
+            &dest,
+            "aaaaaaaaaaa",
+            Some("Intro"),
+            &[],
+        )
+        .unwrap();
+        let items = vec![TranscriptItem {
+            text: "Second".to_string(),
+            start: 0.0,
+            duration: 1.0,
+        }];
+        write_output(
+            &args,
+            "md",
+            &items,
+            &dest,
+            "bbbbbbbbbbb",
+            Some("Part Two"),
+            &[],
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Intro\n\nFirst\n\n\n## Part Two\n\nSecond\n\n");
+    }
+
+    #[test]
+    fn test_append_text_separator() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("course.txt")
+            .to_string_lossy()
+            .to_string();
+        let args = Args::parse_from(["ytt", "PLxyz", "-p", "-f", "text", "--append", "-o", &path]);
+        let dest = OutputDestination::Append(path.clone());
+        let items = vec![TranscriptItem {
+            text: "Hello".to_string(),
+            start: 0.0,
+            duration: 1.0,
+        }];
+        write_output(
+            &args,
+            "text",
+            &items,
+            &dest,
+            "aaaaaaaaaaa",
+            Some("Intro"),
+            &[],
+        )
+        .unwrap();
+        write_output(&args, "text", &items, &dest, "bbbbbbbbbbb", None, &[]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "=== Intro ===\n\nHello\n\n=== bbbbbbbbbbb ===\n\nHello\n"
+        );
+    }
+
+    #[test]
+    fn test_validate_append() {
+        let args = Args::parse_from([
+            "ytt", "PLxyz", "-p", "-f", "srt", "--append", "-o", "out.srt",
+        ]);
+        assert!(validate_append(&args).is_err());
+        let args = Args::parse_from(["ytt", "PLxyz", "-p", "--append"]);
+        assert!(validate_append(&args).is_err());
+        let args = Args::parse_from(["ytt", "VIDEO", "--append", "-o", "out.txt"]);
+        assert_eq!(playlist_only_flag(&args), Some("--append"));
+    }
+
+    #[test]
+    fn test_playlist_progress_eta_uses_recent_videos() {
+        let mut progress = PlaylistProgress::new(10);
+        assert_eq!(progress.eta(), None);
+
+        progress.record(Duration::from_secs(100), true);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(900)));
+
+        for _ in 0..5 {
+            progress.record(Duration::from_secs(10), true);
+        }
+        progress.record(Duration::from_secs(10), false);
+        // The slow first video has dropped out of the window
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+        assert!(progress.summary().contains("6 succeeded, 1 failed"));
+    }
+
+    #[test]
+    fn test_playlist_progress_skipped_report() {
+        let mut progress = PlaylistProgress::new(5);
+        assert_eq!(progress.skipped_report(), None);
+
+        progress.record_skipped("aaa", TranscriptError::VideoUnavailable("aaa".into()));
+        progress.record_skipped("bbb", TranscriptError::TranscriptsDisabled("bbb".into()));
+        progress.record_skipped("ccc", TranscriptError::VideoUnavailable("ccc".into()));
+
+        assert_eq!(
+            progress.skipped_report().unwrap(),
+            "Skipped 3 of 5 videos:\n  \
+             Unavailable, unplayable or age-restricted (2):\n    \
+             aaa: Video unavailable: aaa\n    \
+             ccc: Video unavailable: ccc\n  \
+             No usable transcript (1):\n    \
+             bbb: Transcripts disabled for video: bbb"
+        );
+    }
+
+    #[test]
+    fn test_progress_events_json() {
+        let start = ProgressEvent::VideoStart {
+            index: 2,
+            total: 5,
+            id: "abc",
+        };
+        assert_eq!(
+            serde_json::to_string(&start).unwrap(),
+            r#"{"event":"video_start","index":2,"total":5,"id":"abc"}"#
+        );
+
+        let failed: Result<(), TranscriptError> =
+            Err(TranscriptError::VideoUnavailable("abc".into()));
+        let event = ProgressEvent::finished(2, 5, "abc", Instant::now(), &failed);
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"video_error","index":2,"total":5,"id":"abc","error":"Video unavailable: abc","exit_code":2}"#
+        );
+
+        let done = serde_json::to_value(ProgressEvent::finished(
+            3,
+            5,
+            "def",
+            Instant::now(),
+            &Ok(()),
+        ))
+        .unwrap();
+        assert_eq!(done["event"], "video_done");
+        assert!(done["seconds"].is_f64());
+
+        let stopped = ProgressEvent::RunStopped {
+            index: 2,
+            total: 5,
+            succeeded: 1,
+        };
+        assert_eq!(
+            serde_json::to_string(&stopped).unwrap(),
+            r#"{"event":"run_stopped","index":2,"total":5,"succeeded":1}"#
+        );
+        let log = ProgressEvent::Log {
+            level: "warn".to_string(),
+            message: "No publish date".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&log).unwrap(),
+            r#"{"event":"log","level":"warn","message":"No publish date"}"#
+        );
+    }
+
+    #[test]
+    fn test_progress_json_conflicts_with_stderr_text() {
+        for flag in ["--stream", "--show-usage", "-i"] {
+            let result = Args::try_parse_from(["ytt", "dQw4w9WgXcQ", "--progress-json", flag]);
+            assert!(result.is_err(), "{} should conflict", flag);
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h02m00s");
+    }
+
+    #[test]
+    fn test_validate_date() {
+        assert!(validate_date("2024-01-31").is_ok());
+        assert!(validate_date("2024-1-31").is_err());
+        assert!(validate_date("2024-13-01").is_err());
+        assert!(validate_date("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_in_date_range() {
+        assert!(in_date_range("2024-05-01", Some("2024-05-01"), None));
+        assert!(!in_date_range("2024-04-30", Some("2024-05-01"), None));
+        assert!(in_date_range("2024-05-31", None, Some("2024-05-31")));
+        assert!(!in_date_range(
+            "2024-06-01",
+            Some("2024-05-01"),
+            Some("2024-05-31")
+        ));
+        assert!(in_date_range("2020-01-01", None, None));
+    }
+
+    #[test]
+    fn test_output_exists() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("dQw4w9WgXcQ.txt");
+        let dest = OutputDestination::File(path.to_string_lossy().to_string());
+        assert!(!output_exists(&dest));
+
+        fs::write(&path, "done").unwrap();
+        assert!(output_exists(&dest));
+        assert!(!output_exists(&OutputDestination::Stdout));
+        assert!(!output_exists(&OutputDestination::Append(
+            path.to_string_lossy().to_string()
+        )));
+    }
+
+    #[test]
+    fn test_gzip_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = format!("{}/", temp_dir.path().to_string_lossy());
+        let args = Args::parse_from(["ytt", "PLxyz", "-p", "-f", "json", "--gzip", "-o", &dir]);
+
+        let dest = output_destination(&args, "dQw4w9WgXcQ", None, "en", Some(1), "json").unwrap();
+        let OutputDestination::GzipFile(path) = &dest else {
+            panic!("expected a gzip destination");
+        };
+        assert!(path.ends_with("dQw4w9WgXcQ.json.gz"));
+
+        let items = vec![TranscriptItem {
+            text: "Hello".to_string(),
+            start: 0.0,
+            duration: 1.0,
+        }];
+        output_json(&items, &dest).unwrap();
+
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(File::open(path).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert!(content.contains("\"Hello\""));
+    }
+
+    #[test]
+    fn test_gzip_leaves_stdout_uncompressed() {
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--gzip"]);
+        let dest = output_destination(&args, "dQw4w9WgXcQ", None, "en", None, "text").unwrap();
+        assert!(matches!(dest, OutputDestination::Stdout));
+    }
+
+    #[test]
+    fn test_output_destination_directory_per_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = format!("{}/", temp_dir.path().to_string_lossy());
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "-f", "txt,srt", "-o", &dir]);
+        let paths: Vec<String> = ["txt", "srt"]
+            .iter()
+            .map(|format| {
+                match output_destination(&args, "dQw4w9WgXcQ", None, "en", None, format).unwrap() {
+                    OutputDestination::File(path) => path,
+                    _ => panic!("expected a file destination"),
                 }
-            } else {
-                acc.push(c);
-            }
-            acc
-        });
+            })
+            .collect();
+        assert!(paths[0].ends_with("dQw4w9WgXcQ.txt"));
+        assert!(paths[1].ends_with("dQw4w9WgXcQ.srt"));
+    }
 
-    // Limit length to 200 characters (reasonable for most filesystems)
-    let sanitized = if sanitized.len() > 200 {
-        &sanitized[..200]
-    } else {
-        &sanitized
-    };
+    #[test]
+    fn test_chatgpt_slot_builds_on_demand() {
+        let args = Args::parse_from([
+            "ytt",
+            "dQw4w9WgXcQ",
+            "--translate",
+            "de",
+            "--translate-via",
+            "chatgpt",
+            "--openai-key",
+            "test-key",
+        ]);
+        let slot = ChatGptSlot::default();
+        assert!(slot.get().is_none());
+        assert_eq!(slot.usage(), Usage::default());
+        slot.get_or_build(&args).unwrap();
+        assert!(slot.get().is_some());
+    }
 
-    sanitized.trim_end_matches('_').to_string()
-}
+    #[tokio::test]
+    async fn test_stream_needs_chatgpt() {
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--stream"]);
+        assert!(
+            matches!(run(args).await, Err(TranscriptError::InvalidArgument(msg)) if msg.contains("--stream"))
+        );
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--stream", "--translate", "de"]);
+        assert!(matches!(
+            run(args).await,
+            Err(TranscriptError::InvalidArgument(_))
+        ));
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
+    #[tokio::test]
+    async fn test_multiple_formats_need_ext_in_template() {
+        let args = Args::parse_from([
+            "ytt",
+            "dQw4w9WgXcQ",
+            "-f",
+            "txt,srt",
+            "--output-template",
+            "{id}",
+        ]);
+        assert!(
+            matches!(run(args).await, Err(TranscriptError::InvalidArgument(msg)) if msg.contains("{ext}"))
+        );
+    }
+
+    #[test]
+    fn test_output_is_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(output_is_directory(&temp_dir.path().to_string_lossy()));
+        assert!(output_is_directory("does/not/exist/"));
+        assert!(!output_is_directory("does/not/exist.txt"));
+    }
 
     #[test]
     fn test_format_srt_time() {
@@ -523,6 +4792,39 @@ mod tests {
         assert_eq!(format_srt_time(3661.123), "01:01:01,123");
     }
 
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(65.0, "seconds"), "65.00s");
+        assert_eq!(format_timestamp(65.4, "mmss"), "01:05");
+        assert_eq!(format_timestamp(3725.0, "mmss"), "62:05");
+        assert_eq!(format_timestamp(65.0, "hms"), "00:01:05");
+        assert_eq!(format_timestamp(3725.9, "hms"), "01:02:05");
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("Hello: World?"), "Hello_World");
+        assert_eq!(sanitize_filename("  a  b  "), "a_b");
+    }
+
+    #[test]
+    fn test_sanitize_filename_truncates_multibyte_titles() {
+        let cjk_title = "日本語のタイトル".repeat(40);
+        let sanitized = sanitize_filename(&cjk_title);
+        assert_eq!(sanitized.chars().count(), 200);
+        assert!(sanitized.chars().all(|c| "日本語のタイトル".contains(c)));
+
+        // Emoji are replaced, CJK kept; byte 200 would land mid-character here
+        let mixed_title = format!("{}{}", "é".repeat(150), "漢字".repeat(100));
+        let sanitized = sanitize_filename(&mixed_title);
+        assert_eq!(sanitized.chars().count(), 200);
+
+        let emoji_title = "🎉 Party ".repeat(50);
+        let sanitized = sanitize_filename(&emoji_title);
+        assert!(sanitized.chars().count() <= 200);
+        assert!(sanitized.contains("Party"));
+    }
+
     #[test]
     fn test_output_destination_stdout() {
         let dest = OutputDestination::Stdout;
@@ -530,6 +4832,34 @@ mod tests {
         assert!(writer.is_ok());
     }
 
+    #[test]
+    fn test_output_destination_dash_is_stdout() {
+        for argv in [
+            vec!["ytt", "dQw4w9WgXcQ", "-o", "-", "-n"],
+            vec!["ytt", "PLxyz", "--playlist", "-o", "-", "--gzip"],
+        ] {
+            let args = Args::parse_from(argv);
+            let dest =
+                output_destination(&args, "dQw4w9WgXcQ", Some("Title"), "en", Some(2), "txt")
+                    .unwrap();
+            assert!(matches!(dest, OutputDestination::Stdout));
+        }
+
+        let args = Args::parse_from(["ytt", "PLxyz", "--playlist", "--append", "-o", "-"]);
+        assert!(validate_append(&args).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_playlist_to_stdout_needs_sectioned_format() {
+        for format in ["srt", "vtt", "json", "html"] {
+            let args = Args::parse_from(["ytt", "PLxyz", "--playlist", "-o", "-", "-f", format]);
+            assert!(
+                matches!(run(args).await, Err(TranscriptError::InvalidArgument(_))),
+                "{format}"
+            );
+        }
+    }
+
     #[test]
     fn test_output_destination_file() {
         let temp_dir = TempDir::new().unwrap();
@@ -539,6 +4869,60 @@ mod tests {
         assert!(writer.is_ok());
     }
 
+    #[test]
+    fn test_output_encodings() {
+        let temp_dir = TempDir::new().unwrap();
+        let write = |name: &str, encoding: OutputEncoding| {
+            let path = temp_dir.path().join(name).to_string_lossy().to_string();
+            let dest = OutputDestination::Encoded(
+                Box::new(OutputDestination::File(path.clone())),
+                encoding,
+            );
+            let mut writer = dest.writer().unwrap();
+            // Split a multi-byte character across two writes
+            let text = "Café ☕\n".as_bytes();
+            writer.write_all(&text[..4]).unwrap();
+            writer.write_all(&text[4..]).unwrap();
+            drop(writer);
+            fs::read(path).unwrap()
+        };
+
+        assert_eq!(
+            write("bom.txt", OutputEncoding::Utf8Bom),
+            "\u{feff}Café ☕\n".as_bytes()
+        );
+
+        let utf16 = write("utf16.txt", OutputEncoding::Utf16Le);
+        let expected: Vec<u8> = "\u{feff}Café ☕\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(utf16, expected);
+    }
+
+    #[test]
+    fn test_encoded_append_writes_one_byte_order_mark() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("all.txt")
+            .to_string_lossy()
+            .to_string();
+        fs::write(&path, "").unwrap();
+        for section in ["one", "two"] {
+            write_section_header(
+                &OutputDestination::Append(path.clone()),
+                "text",
+                section,
+                OutputEncoding::Utf8Bom,
+            )
+            .unwrap();
+        }
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.matches('\u{feff}').count(), 1);
+        assert!(content.starts_with("\u{feff}=== one ==="));
+    }
+
     #[test]
     fn test_output_json() {
         let items = vec![
@@ -564,6 +4948,134 @@ mod tests {
         assert!(content.contains("\"start\": 0.0"));
     }
 
+    #[test]
+    fn test_indexed_json_output() {
+        let items = vec![
+            TranscriptItem {
+                text: "Hello".into(),
+                start: 0.0,
+                duration: 1.0,
+            },
+            TranscriptItem {
+                text: "World".into(),
+                start: 1.0,
+                duration: 1.5,
+            },
+        ];
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("cues.jsonl");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        output_jsonl(&indexed_cues(&items, "dQw4w9WgXcQ"), &dest).unwrap();
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert_eq!(
+            content.lines().nth(1).unwrap(),
+            r#"{"index":2,"id":"dQw4w9WgXcQ-000002","text":"World","start":1.0,"duration":1.5}"#
+        );
+
+        // The extra fields don't stop the JSON from being read back
+        let json_path = temp_dir.path().join("cues.json");
+        output_json(
+            &indexed_cues(&items, "abc"),
+            &OutputDestination::File(json_path.to_string_lossy().to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            read_input_file(json_path.to_str().unwrap(), false)
+                .unwrap()
+                .transcript
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_output_words_json() {
+        let items = vec![WordTimedItem {
+            text: "Hello world".to_string(),
+            start: 1.0,
+            duration: 2.0,
+            words: vec![
+                ytt::TimedWord {
+                    text: "Hello".to_string(),
+                    start: 1.0,
+                },
+                ytt::TimedWord {
+                    text: "world".to_string(),
+                    start: 1.5,
+                },
+            ],
+        }];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("words.json");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        output_words_json(&items, &dest).unwrap();
+        let parsed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&file_path).unwrap()).unwrap();
+        assert_eq!(parsed[0]["words"][1]["text"], "world");
+        assert_eq!(parsed[0]["words"][1]["start"], 1.5);
+    }
+
+    #[test]
+    fn test_output_verbose_json() {
+        let items = vec![
+            VerboseItem {
+                text: "Hello".to_string(),
+                start: 1.0,
+                duration: 2.0,
+                confidence: Some(0.75),
+                kind: Some("asr".to_string()),
+            },
+            VerboseItem {
+                text: "world".to_string(),
+                start: 3.0,
+                duration: 1.0,
+                confidence: None,
+                kind: Some("asr".to_string()),
+            },
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("verbose.json");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        output_verbose_json(&items, &dest).unwrap();
+        let parsed: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&file_path).unwrap()).unwrap();
+        assert_eq!(parsed[0]["confidence"], 0.75);
+        assert_eq!(parsed[0]["kind"], "asr");
+        assert!(parsed[1].get("confidence").is_none());
+    }
+
+    #[test]
+    fn test_output_jsonl() {
+        let items = vec![
+            TranscriptItem {
+                text: "Hello".to_string(),
+                start: 0.0,
+                duration: 1.0,
+            },
+            TranscriptItem {
+                text: "World".to_string(),
+                start: 1.0,
+                duration: 1.5,
+            },
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.jsonl");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        assert!(output_jsonl(&items, &dest).is_ok());
+        let content = fs::read_to_string(&file_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], r#"{"text":"Hello","start":0.0,"duration":1.0}"#);
+        assert_eq!(lines[1], r#"{"text":"World","start":1.0,"duration":1.5}"#);
+    }
+
     #[test]
     fn test_output_srt() {
         let items = vec![
@@ -590,6 +5102,34 @@ mod tests {
         assert!(content.contains("Hello"));
     }
 
+    #[test]
+    fn test_output_html() {
+        let items = vec![
+            TranscriptItem {
+                text: "Hello <world> & friends".to_string(),
+                start: 0.0,
+                duration: 1.0,
+            },
+            TranscriptItem {
+                text: "Later".to_string(),
+                start: 65.7,
+                duration: 1.0,
+            },
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.html");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        assert!(output_html(&items, &dest, "dQw4w9WgXcQ", Some("My \"Video\"")).is_ok());
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert!(content.contains("<title>My &quot;Video&quot;</title>"));
+        assert!(content.contains("Hello &lt;world&gt; &amp; friends"));
+        assert!(content.contains(
+            r#"href="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;t=65s">00:01:05</a>Later"#
+        ));
+    }
+
     #[test]
     fn test_output_text_only() {
         let items = vec![TranscriptItem {
@@ -602,7 +5142,7 @@ mod tests {
         let file_path = temp_dir.path().join("test.txt");
         let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
 
-        assert!(output_text_only(&items, &dest, None, None).is_ok());
+        assert!(output_text_only(&items, &dest, None, None, false).is_ok());
         let content = fs::read_to_string(&file_path).unwrap();
         assert_eq!(content.trim(), "Hello world");
     }
@@ -619,13 +5159,304 @@ mod tests {
         let file_path = temp_dir.path().join("test.txt");
         let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
 
-        assert!(output_text(&items, &dest, None, None).is_ok());
+        assert!(output_text(&items, &dest, "seconds", None, None, false).is_ok());
         let content = fs::read_to_string(&file_path).unwrap();
         // Check for timestamp format [X.XX] where X can be any digit
         assert!(content.contains("[1"));
         assert!(content.contains("Hello world"));
     }
 
+    #[tokio::test]
+    async fn test_output_markdown_chapters() {
+        let items = vec![
+            TranscriptItem {
+                text: "Welcome".into(),
+                start: 0.0,
+                duration: 2.0,
+            },
+            TranscriptItem {
+                text: "Let's install it".into(),
+                start: 65.0,
+                duration: 3.0,
+            },
+        ];
+        let chapters = vec![("Intro".to_string(), 0.0), ("Setup".to_string(), 60.0)];
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("talk.md");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        let sections = split_by_chapters(&items, &chapters);
+        let options = MarkdownOptions {
+            timestamps: Some("mmss"),
+            ..MarkdownOptions::new("Transcript")
+        };
+        output_markdown_chapters(&sections, &dest, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "# Transcript\n\n## Intro\n\n**[00:00]** Welcome\n\n## Setup\n\n**[01:05]** Let's install it\n\n"
+        );
+
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--chapters", "-f", "srt"]);
+        assert!(
+            matches!(run(args).await, Err(TranscriptError::InvalidArgument(msg)) if msg.contains("markdown"))
+        );
+        assert!(Args::try_parse_from(["ytt", "--input-file", "a.srt", "--chapters"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_self_test() {
+        let args = Args::parse_from(["ytt", "--self-test"]);
+        assert!(args.self_test && args.video.is_none());
+        assert!(Args::try_parse_from(["ytt", "--self-test", "--input-file", "a.srt"]).is_err());
+
+        // A bad override fails at the first step, before anything is fetched
+        let api = YouTubeTranscript::with_delay(0);
+        assert!(matches!(
+            self_test(&api, "not a video").await,
+            Err(TranscriptError::InvalidVideoId(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_diff() {
+        let manual = vec![
+            TranscriptItem {
+                text: "Hello, world!".into(),
+                start: 0.0,
+                duration: 2.0,
+            },
+            TranscriptItem {
+                text: "This is a test.".into(),
+                start: 2.0,
+                duration: 2.0,
+            },
+        ];
+        let auto = vec![
+            TranscriptItem {
+                text: "hello world".into(),
+                start: 1.5,
+                duration: 1.5,
+            },
+            TranscriptItem {
+                text: "this is the test".into(),
+                start: 3.0,
+                duration: 2.0,
+            },
+            TranscriptItem {
+                text: "bye".into(),
+                start: 5.0,
+                duration: 1.0,
+            },
+        ];
+        assert_eq!(
+            format_diff("manual.srt", "auto.srt", &diff_transcripts(&manual, &auto)),
+            "--- manual.srt\n+++ auto.srt\n  [00:00:00] Hello, world! (+1.5s)\n- [00:00:02] This is a test.\n+ [00:00:03] this is the test\n+ [00:00:05] bye\n"
+        );
+    }
+
+    #[test]
+    fn test_split_every() {
+        let items: Vec<TranscriptItem> = (0..5)
+            .map(|i| TranscriptItem {
+                text: format!("cue {}", i),
+                start: i as f64 * 100.0,
+                duration: 2.0,
+            })
+            .collect();
+        assert_eq!(SplitEvery::parse("2").unwrap(), SplitEvery::Cues(2));
+        assert_eq!(SplitEvery::parse("3m").unwrap(), SplitEvery::Minutes(3));
+        assert!(SplitEvery::parse("0").is_err());
+        assert!(SplitEvery::parse("3h").is_err());
+
+        let lengths =
+            |parts: Vec<&[TranscriptItem]>| parts.iter().map(|part| part.len()).collect::<Vec<_>>();
+        assert_eq!(lengths(SplitEvery::Cues(2).split(&items)), vec![2, 2, 1]);
+        // 0s, 100s, 200s | 300s, 400s
+        assert_eq!(lengths(SplitEvery::Minutes(5).split(&items)), vec![3, 2]);
+        assert!(SplitEvery::Minutes(5).split(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_split_every_output() {
+        let items: Vec<TranscriptItem> = (0..3)
+            .map(|i| TranscriptItem {
+                text: format!("cue {}", i),
+                start: i as f64 * 60.0,
+                duration: 2.0,
+            })
+            .collect();
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("talk.md")
+            .to_string_lossy()
+            .to_string();
+        let args = Args::parse_from([
+            "ytt",
+            "dQw4w9WgXcQ",
+            "-f",
+            "md",
+            "--split-every",
+            "2",
+            "-o",
+            &path,
+        ]);
+        write_output(
+            &args,
+            "md",
+            &items,
+            &OutputDestination::File(path),
+            "dQw4w9WgXcQ",
+            None,
+            &[],
+        )
+        .unwrap();
+
+        assert!(!temp_dir.path().join("talk.md").exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("talk.part01.md")).unwrap(),
+            "# Transcript\n\n*Part 1 of 2*\n\ncue 0\n\ncue 1\n\n"
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("talk.part02.md")).unwrap(),
+            "# Transcript\n\n*Part 2 of 2, continued from part 1 at 00:02:00*\n\ncue 2\n\n"
+        );
+
+        let gzip = OutputDestination::GzipFile("out/talk.srt.gz".to_string());
+        assert!(
+            matches!(gzip.part(3, 4).unwrap(), OutputDestination::Part(inner, 3, 4)
+            if matches!(inner.as_ref(), OutputDestination::GzipFile(path) if path == "out/talk.part03.srt.gz"))
+        );
+        assert!(OutputDestination::Stdout.part(1, 2).is_err());
+    }
+
+    #[test]
+    fn test_output_markdown_front_matter() {
+        let items = vec![TranscriptItem {
+            text: "Hello world".into(),
+            start: 0.0,
+            duration: 2.0,
+        }];
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("post.md");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        let options = MarkdownOptions {
+            front_matter: Some(FrontMatter {
+                title: "Rust: \"Ownership\" explained",
+                url: "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+                date: "2024-05-01".to_string(),
+            }),
+            ..MarkdownOptions::new("Transcript")
+        };
+        output_markdown(&items, &dest, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "---\ntitle: \"Rust: \\\"Ownership\\\" explained\"\nurl: https://www.youtube.com/watch?v=dQw4w9WgXcQ\ndate: 2024-05-01\n---\n\n# Transcript\n\nHello world\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_markdown_cue_links() {
+        let items = vec![
+            TranscriptItem {
+                text: "Hello".to_string(),
+                start: 0.4,
+                duration: 1.0,
+            },
+            TranscriptItem {
+                text: "world".to_string(),
+                start: 65.9,
+                duration: 1.0,
+            },
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("links.md");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        let options = MarkdownOptions {
+            timestamps: Some("mmss"),
+            cue_links: Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            ..MarkdownOptions::new("Transcript")
+        };
+        output_markdown(&items, &dest, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "# Transcript\n\n**[00:00]** Hello [▶](https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=0s)\n\n**[01:05]** world [▶](https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=65s)\n\n"
+        );
+
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--append-url-per-cue"]);
+        assert!(
+            matches!(run(args).await, Err(TranscriptError::InvalidArgument(msg)) if msg.contains("markdown"))
+        );
+        assert!(Args::try_parse_from([
+            "ytt",
+            "--input-file",
+            "a.srt",
+            "--append-url-per-cue",
+            "-f",
+            "md"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_insert_markdown_toc() {
+        let markdown = "# Talk\n\n## Intro\n\nHi\n\n```\n## not a heading\n```\n\n## Q&A: Part 2\n\n## Intro\n\nBye\n";
+        assert_eq!(
+            insert_markdown_toc(markdown),
+            "# Talk\n\n- [Intro](#intro)\n- [Q&A: Part 2](#qa-part-2)\n- [Intro](#intro-1)\n\n## Intro\n\nHi\n\n```\n## not a heading\n```\n\n## Q&A: Part 2\n\n## Intro\n\nBye\n"
+        );
+
+        // Without a title the list goes first; a single section gets no list
+        assert!(insert_markdown_toc("## A\n\n## B\n").starts_with("- [A](#a)\n- [B](#b)\n\n## A"));
+        assert_eq!(
+            insert_markdown_toc("# Talk\n\n## Only\n"),
+            "# Talk\n\n## Only\n"
+        );
+
+        // Brackets in a chapter title don't end the link text early
+        assert!(insert_markdown_toc("## [Live] Demo\n\n## Q&A\n")
+            .starts_with("- [\\[Live\\] Demo](#live-demo)\n"));
+    }
+
+    #[tokio::test]
+    async fn test_output_markdown_chapters_toc() {
+        let items = vec![
+            TranscriptItem {
+                text: "Welcome".into(),
+                start: 0.0,
+                duration: 2.0,
+            },
+            TranscriptItem {
+                text: "Let's install it".into(),
+                start: 65.0,
+                duration: 3.0,
+            },
+        ];
+        let chapters = vec![("Intro".to_string(), 0.0), ("Setup".to_string(), 60.0)];
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("talk.md");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        let sections = split_by_chapters(&items, &chapters);
+        let options = MarkdownOptions {
+            toc: true,
+            ..MarkdownOptions::new("Transcript")
+        };
+        output_markdown_chapters(&sections, &dest, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&file_path).unwrap(),
+            "# Transcript\n\n- [Intro](#intro)\n- [Setup](#setup)\n\n## Intro\n\nWelcome\n\n## Setup\n\nLet's install it\n\n"
+        );
+
+        let args = Args::parse_from(["ytt", "dQw4w9WgXcQ", "--markdown-toc"]);
+        assert!(
+            matches!(run(args).await, Err(TranscriptError::InvalidArgument(msg)) if msg.contains("markdown"))
+        );
+    }
+
     #[test]
     fn test_output_markdown() {
         let items = vec![TranscriptItem {
@@ -638,12 +5469,62 @@ mod tests {
         let file_path = temp_dir.path().join("test.md");
         let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
 
-        assert!(output_markdown(&items, &dest, false, None, None).is_ok());
+        assert!(output_markdown(&items, &dest, &MarkdownOptions::new("Transcript")).is_ok());
         let content = fs::read_to_string(&file_path).unwrap();
         assert!(content.contains("# Transcript"));
         assert!(content.contains("Hello world"));
     }
 
+    #[test]
+    fn test_output_text_only_with_metadata() {
+        let items = vec![
+            TranscriptItem {
+                text: "Hello world".to_string(),
+                start: 0.0,
+                duration: 40.0,
+            },
+            TranscriptItem {
+                text: "This is a test".to_string(),
+                start: 40.0,
+                duration: 25.5,
+            },
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.txt");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        let url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+        assert!(output_text_only(&items, &dest, Some(url), Some("My Video"), true).is_ok());
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert!(content.starts_with(
+            "Title: My Video\nURL: https://www.youtube.com/watch?v=dQw4w9WgXcQ\nDuration: 00:01:05\nCues: 2\nWords: 6\n\nHello world\n"
+        ));
+    }
+
+    #[test]
+    fn test_output_markdown_with_metadata() {
+        let items = vec![TranscriptItem {
+            text: "Hello world".to_string(),
+            start: 0.0,
+            duration: 3.0,
+        }];
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("test.md");
+        let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
+
+        let options = MarkdownOptions {
+            metadata: true,
+            ..MarkdownOptions::new("Transcript")
+        };
+        assert!(output_markdown(&items, &dest, &options).is_ok());
+        let content = fs::read_to_string(&file_path).unwrap();
+        assert!(content.starts_with(
+            "- **Duration:** 00:00:03\n- **Cues:** 1\n- **Words:** 2\n\n# Transcript"
+        ));
+    }
+
     #[test]
     fn test_output_markdown_with_chatgpt_formatting() {
         let items = vec![TranscriptItem {
@@ -656,7 +5537,7 @@ mod tests {
         let file_path = temp_dir.path().join("test.md");
         let dest = OutputDestination::File(file_path.to_string_lossy().to_string());
 
-        assert!(output_markdown(&items, &dest, false, None, None).is_ok());
+        assert!(output_markdown(&items, &dest, &MarkdownOptions::new("Transcript")).is_ok());
         let content = fs::read_to_string(&file_path).unwrap();
         // Should detect ChatGPT formatting and not add extra heading
         assert!(content.contains("## Section"));
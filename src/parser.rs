@@ -1,48 +1,181 @@
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use serde::Deserialize;
 use std::str;
 
+/// Decode a caption body that may not be valid UTF-8. Malformed byte sequences are
+/// replaced with U+FFFD (with a warning naming `source`) so one bad cue doesn't lose the rest.
+pub(crate) fn decode_lossy(body: &[u8], source: &str) -> String {
+    let text = String::from_utf8_lossy(body);
+    if let std::borrow::Cow::Owned(_) = text {
+        log::warn!(
+            "{} is not valid UTF-8; replacing the malformed bytes",
+            source
+        );
+    }
+    text.into_owned()
+}
+
+/// Parser for YouTube timedtext captions
+///
+/// Handles the classic `<text start=".." dur="..">` XML format, the srv3
+/// `<p t=".." d="..">` format and json3 (`{"events": [...]}`), so caption files downloaded ahead of time can be
+/// converted without fetching anything:
+///
+/// ```
+/// let xml = r#"<transcript><text start="0.5" dur="2">Hello &amp;amp; welcome</text></transcript>"#;
+/// let items = ytt::TranscriptParser::new(false).parse(xml).unwrap();
+/// assert_eq!(items[0].text, "Hello & welcome");
+/// assert_eq!(items[0].start, 0.5);
+/// ```
 pub struct TranscriptParser {
     _preserve_formatting: bool,
+    keep_empty_cues: bool,
 }
 
 impl TranscriptParser {
+    /// Create a parser. `preserve_formatting` is reserved for keeping inline
+    /// formatting tags and currently has no effect.
     pub fn new(preserve_formatting: bool) -> Self {
         Self {
             _preserve_formatting: preserve_formatting,
+            keep_empty_cues: false,
         }
     }
 
+    /// Emit cues without text (with their timing) instead of skipping them, for
+    /// forced-alignment and gap-analysis tools that need the silent intervals
+    pub fn keep_empty_cues(mut self, keep: bool) -> Self {
+        self.keep_empty_cues = keep;
+        self
+    }
+
+    /// Parse every cue in `xml`. The error is a description of the malformed XML.
     pub fn parse(&self, xml: &str) -> Result<Vec<crate::TranscriptItem>, String> {
+        let mut items = Vec::new();
+        self.parse_with(xml, |item| {
+            items.push(item);
+            true
+        })?;
+        Ok(items)
+    }
+
+    /// Parse cues one at a time, handing each to `on_item` as soon as it is read.
+    /// Parsing stops early if `on_item` returns `false`.
+    pub fn parse_with<F>(&self, xml: &str, mut on_item: F) -> Result<(), String>
+    where
+        F: FnMut(crate::TranscriptItem) -> bool,
+    {
+        self.parse_cues(xml, |item, _| on_item(item))
+    }
+
+    /// Parse cues along with the recognizer confidence carried by srv3 `<s ac="...">`
+    /// elements and json3 `acAsrConf` segments. Cues without it get `confidence: None`;
+    /// `kind` is left unset, since the body doesn't say what track it came from.
+    pub fn parse_verbose(&self, xml: &str) -> Result<Vec<crate::VerboseItem>, String> {
+        let mut items = Vec::new();
+        self.parse_cues(xml, |item, details| {
+            items.push(crate::VerboseItem {
+                text: item.text,
+                start: item.start,
+                duration: item.duration,
+                confidence: details.confidence,
+                kind: None,
+            });
+            true
+        })?;
+        Ok(items)
+    }
+
+    /// Parse cues along with the per-word timing carried by srv3 `<s t="...">`
+    /// elements. Cues without word timing get an empty `words` list.
+    pub fn parse_words(&self, xml: &str) -> Result<Vec<crate::WordTimedItem>, String> {
+        let mut items = Vec::new();
+        self.parse_cues(xml, |item, details| {
+            items.push(crate::WordTimedItem {
+                text: item.text,
+                start: item.start,
+                duration: item.duration,
+                words: details.words,
+            });
+            true
+        })?;
+        Ok(items)
+    }
+
+    /// Parse either caption format YouTube serves: json3 when the body is a JSON
+    /// object, timedtext XML otherwise
+    fn parse_cues<F>(&self, body: &str, mut on_cue: F) -> Result<(), String>
+    where
+        F: FnMut(crate::TranscriptItem, CueDetails) -> bool,
+    {
+        let mut previous_start = 0.0;
+        let mut emit = |item: crate::TranscriptItem, details| {
+            if item.start < previous_start {
+                log::warn!(
+                    "Cue at {:.3}s starts before the previous cue at {:.3}s",
+                    item.start,
+                    previous_start
+                );
+            }
+            previous_start = item.start;
+            on_cue(item, details)
+        };
+
+        if is_json(body) {
+            parse_json3(body, self.keep_empty_cues, &mut emit)
+        } else {
+            self.parse_xml(body, &mut emit)
+        }
+    }
+
+    fn parse_xml<F>(&self, xml: &str, mut on_cue: F) -> Result<(), String>
+    where
+        F: FnMut(crate::TranscriptItem, CueDetails) -> bool,
+    {
         let mut reader = Reader::from_str(xml);
         reader.trim_text(true);
 
-        let mut items = Vec::new();
         let mut buf = Vec::new();
 
         loop {
-            match reader.read_event_into(&mut buf) {
+            let cue = match reader.read_event_into(&mut buf) {
                 Ok(Event::Start(e)) => match e.name().as_ref() {
-                    b"text" => {
-                        if let Some(item) = self.parse_text_element(&mut reader, &e)? {
-                            items.push(item);
-                        }
-                    }
-                    b"p" => {
-                        if let Some(item) = self.parse_p_element(&mut reader, &e)? {
-                            items.push(item);
-                        }
-                    }
-                    _ => {}
+                    b"text" => self
+                        .parse_text_element(&mut reader, &e)?
+                        .map(|item| (item, CueDetails::default())),
+                    b"p" => self.parse_p_element(&mut reader, &e)?,
+                    _ => None,
                 },
+                // Self-closing <text .../> and <p .../> carry timing but no text
+                Ok(Event::Empty(e)) if self.keep_empty_cues => {
+                    let timing = match e.name().as_ref() {
+                        b"text" => Some(text_timing(&e)),
+                        b"p" => Some(p_timing(&e)),
+                        _ => None,
+                    };
+                    timing.map(|(start, duration)| {
+                        let item = crate::TranscriptItem {
+                            text: String::new(),
+                            start,
+                            duration,
+                        };
+                        (item, CueDetails::default())
+                    })
+                }
                 Ok(Event::Eof) => break,
                 Err(e) => return Err(format!("XML parse error: {}", e)),
-                _ => {}
+                _ => None,
+            };
+            if let Some((item, details)) = cue {
+                if !on_cue(item, details) {
+                    break;
+                }
             }
             buf.clear();
         }
 
-        Ok(items)
+        Ok(())
     }
 
     fn parse_text_element(
@@ -50,33 +183,7 @@ impl TranscriptParser {
         reader: &mut Reader<&[u8]>,
         e: &quick_xml::events::BytesStart,
     ) -> Result<Option<crate::TranscriptItem>, String> {
-        let start = e
-            .attributes()
-            .find(|a| {
-                a.as_ref()
-                    .map(|attr| attr.key.as_ref() == b"start")
-                    .unwrap_or(false)
-            })
-            .and_then(|a| {
-                a.ok()
-                    .and_then(|attr| str::from_utf8(&attr.value).ok().map(|s| s.to_string()))
-                    .and_then(|s| s.parse::<f64>().ok())
-            })
-            .unwrap_or(0.0);
-
-        let duration = e
-            .attributes()
-            .find(|a| {
-                a.as_ref()
-                    .map(|attr| attr.key.as_ref() == b"dur")
-                    .unwrap_or(false)
-            })
-            .and_then(|a| {
-                a.ok()
-                    .and_then(|attr| str::from_utf8(&attr.value).ok().map(|s| s.to_string()))
-                    .and_then(|s| s.parse::<f64>().ok())
-            })
-            .unwrap_or(0.0);
+        let (start, duration) = text_timing(e);
 
         let mut text = String::new();
         let mut buf = Vec::new();
@@ -84,11 +191,7 @@ impl TranscriptParser {
         loop {
             match reader.read_event_into(&mut buf) {
                 Ok(Event::Text(e)) => {
-                    let decoded = html_escape::decode_html_entities(
-                        e.unescape()
-                            .map_err(|e| format!("Failed to unescape: {}", e))?
-                            .as_ref(),
-                    );
+                    let decoded = decode_text(&e);
                     text.push_str(&decoded);
                 }
                 Ok(Event::End(e)) if e.name().as_ref() == b"text" => break,
@@ -99,7 +202,7 @@ impl TranscriptParser {
             buf.clear();
         }
 
-        if text.trim().is_empty() {
+        if text.trim().is_empty() && !self.keep_empty_cues {
             return Ok(None);
         }
 
@@ -114,66 +217,50 @@ impl TranscriptParser {
         &self,
         reader: &mut Reader<&[u8]>,
         e: &quick_xml::events::BytesStart,
-    ) -> Result<Option<crate::TranscriptItem>, String> {
-        let start = e
-            .attributes()
-            .find(|a| {
-                a.as_ref()
-                    .map(|attr| attr.key.as_ref() == b"t")
-                    .unwrap_or(false)
-            })
-            .and_then(|a| {
-                a.ok().and_then(|attr| {
-                    str::from_utf8(&attr.value)
-                        .ok()
-                        .map(|s| s.to_string())
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .map(|s| s / 1000.0) // Convert from milliseconds
-                })
-            })
-            .unwrap_or(0.0);
-
-        let duration = e
-            .attributes()
-            .find(|a| {
-                a.as_ref()
-                    .map(|attr| attr.key.as_ref() == b"d")
-                    .unwrap_or(false)
-            })
-            .and_then(|a| {
-                a.ok().and_then(|attr| {
-                    str::from_utf8(&attr.value)
-                        .ok()
-                        .map(|s| s.to_string())
-                        .and_then(|s| s.parse::<f64>().ok())
-                        .map(|s| s / 1000.0) // Convert from milliseconds
-                })
-            })
-            .unwrap_or(0.0);
+    ) -> Result<Option<(crate::TranscriptItem, CueDetails)>, String> {
+        let (start, duration) = p_timing(e);
 
         let mut text = String::new();
+        let mut words = Vec::new();
+        let mut confidences = Vec::new();
+        // Word being read inside an <s> element, with its absolute start time
+        let mut current_word: Option<crate::TimedWord> = None;
         let mut buf = Vec::new();
 
         loop {
             match reader.read_event_into(&mut buf) {
                 Ok(Event::Text(e)) => {
-                    let decoded = html_escape::decode_html_entities(
-                        e.unescape()
-                            .map_err(|e| format!("Failed to unescape: {}", e))?
-                            .as_ref(),
-                    );
+                    let decoded = decode_text(&e);
                     text.push_str(&decoded);
+                    if let Some(word) = current_word.as_mut() {
+                        word.text.push_str(&decoded);
+                    }
                 }
                 Ok(Event::Start(e)) => {
                     // Handle nested tags like <s>, <br/>, etc.
                     match e.name().as_ref() {
-                        b"s" | b"br" => {
-                            if !text.ends_with(' ') {
-                                text.push(' ');
-                            }
+                        b"s" | b"br" if !text.ends_with(' ') => {
+                            text.push(' ');
                         }
                         _ => {}
                     }
+                    if e.name().as_ref() == b"s" {
+                        // Word offsets are milliseconds relative to the cue start
+                        let offset = number_attribute(&e, b"t").unwrap_or(0.0);
+                        confidences.extend(number_attribute(&e, b"ac"));
+                        current_word = Some(crate::TimedWord {
+                            text: String::new(),
+                            start: sanitize_time(start + offset / 1000.0),
+                        });
+                    }
+                }
+                Ok(Event::End(e)) if e.name().as_ref() == b"s" => {
+                    if let Some(mut word) = current_word.take() {
+                        word.text = word.text.trim().to_string();
+                        if !word.text.is_empty() {
+                            words.push(word);
+                        }
+                    }
                 }
                 Ok(Event::End(e)) if e.name().as_ref() == b"p" => break,
                 Ok(Event::Eof) => return Err("Unexpected EOF in p element".to_string()),
@@ -183,15 +270,351 @@ impl TranscriptParser {
             buf.clear();
         }
 
-        if text.trim().is_empty() {
+        if text.trim().is_empty() && !self.keep_empty_cues {
             return Ok(None);
         }
 
-        Ok(Some(crate::TranscriptItem {
-            text: text.trim().to_string(),
+        Ok(Some((
+            crate::TranscriptItem {
+                text: text.trim().to_string(),
+                start,
+                duration,
+            },
+            CueDetails {
+                words,
+                confidence: mean_confidence(&confidences),
+            },
+        )))
+    }
+}
+
+/// Parse SubRip (`.srt`) subtitles, the reverse of the SRT output format
+///
+/// Each block is an optional index line, a `start --> end` timing line and one or more
+/// text lines, which are joined with spaces. Formatting tags such as `<i>` are removed,
+/// and blocks left without text are skipped. The error describes the first malformed block.
+///
+/// ```
+/// let srt = "1\n00:00:01,500 --> 00:00:04,000\nHello\nworld\n";
+/// let items = ytt::parse_srt(srt).unwrap();
+/// assert_eq!(items[0].text, "Hello world");
+/// assert_eq!((items[0].start, items[0].duration), (1.5, 2.5));
+/// ```
+pub fn parse_srt(text: &str) -> Result<Vec<crate::TranscriptItem>, String> {
+    parse_cue_blocks(text, SubtitleFormat::Srt)
+}
+
+/// Parse WebVTT (`.vtt`) subtitles
+///
+/// The `WEBVTT` header and `NOTE`, `STYLE` and `REGION` blocks are skipped. Cue
+/// identifiers and cue settings after the end time (`align:start position:0%`) are
+/// ignored, inline tags such as `<c>`, `<v Speaker>` and `<00:00:01.500>` are removed,
+/// and `&amp;`-style entities are decoded. Multi-line cue text is joined with spaces.
+///
+/// ```
+/// let vtt = "WEBVTT\n\n1\n00:01.500 --> 00:04.000 align:start\n<v Ann>Hello &amp; welcome\n";
+/// let items = ytt::parse_vtt(vtt).unwrap();
+/// assert_eq!(items[0].text, "Hello & welcome");
+/// assert_eq!((items[0].start, items[0].duration), (1.5, 2.5));
+/// ```
+pub fn parse_vtt(text: &str) -> Result<Vec<crate::TranscriptItem>, String> {
+    parse_cue_blocks(text, SubtitleFormat::Vtt)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    fn name(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "SRT",
+            SubtitleFormat::Vtt => "WebVTT",
+        }
+    }
+}
+
+/// Parse the blank-line separated cue blocks SRT and WebVTT share: an optional
+/// identifier line, a `start --> end` timing line, then the cue text
+fn parse_cue_blocks(
+    text: &str,
+    format: SubtitleFormat,
+) -> Result<Vec<crate::TranscriptItem>, String> {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut items = Vec::new();
+    let mut block: Vec<&str> = Vec::new();
+    let mut block_number = 0;
+
+    for line in text.lines().chain(std::iter::once("")) {
+        if !line.trim().is_empty() {
+            block.push(line);
+            continue;
+        }
+        if block.is_empty() {
+            continue;
+        }
+        block_number += 1;
+
+        let Some(timing) = block.iter().position(|line| line.contains("-->")) else {
+            let is_vtt_metadata = ["WEBVTT", "NOTE", "STYLE", "REGION"]
+                .iter()
+                .any(|keyword| block[0].starts_with(keyword));
+            if format == SubtitleFormat::Vtt && is_vtt_metadata {
+                block.clear();
+                continue;
+            }
+            return Err(format!(
+                "{} block {}: missing a 'start --> end' line",
+                format.name(),
+                block_number
+            ));
+        };
+        let (start, end) = block[timing].split_once("-->").unwrap_or_default();
+        // Anything after the end time (SRT position hints, WebVTT cue settings) is ignored
+        let end = end.split_whitespace().next().unwrap_or_default();
+        let parse_time = |time: &str| {
+            crate::timecode::parse_timecode(time)
+                .map(sanitize_time)
+                .map_err(|_| {
+                    format!(
+                        "{} block {}: invalid time '{}'",
+                        format.name(),
+                        block_number,
+                        time.trim()
+                    )
+                })
+        };
+        let start = parse_time(start)?;
+        let end = parse_time(end)?;
+
+        let text = block[timing + 1..]
+            .iter()
+            .map(|line| strip_tags(line.trim()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = match format {
+            SubtitleFormat::Vtt => html_escape::decode_html_entities(&text),
+            SubtitleFormat::Srt => text,
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            items.push(crate::TranscriptItem {
+                text,
+                start,
+                duration: (end - start).max(0.0),
+            });
+        }
+        block.clear();
+    }
+
+    Ok(items)
+}
+
+/// Whether `text` is WebVTT, i.e. starts with the `WEBVTT` header line
+pub fn is_vtt(text: &str) -> bool {
+    text.trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with("WEBVTT")
+}
+
+/// Whether `text` looks like SRT: its first line is a timing line, or a cue
+/// number followed by one
+pub fn is_srt(text: &str) -> bool {
+    let mut lines = text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| line.is_empty());
+    match lines.next() {
+        Some(first) if first.contains("-->") => true,
+        Some(first) if !first.is_empty() && first.chars().all(|c| c.is_ascii_digit()) => {
+            lines.next().is_some_and(|second| second.contains("-->"))
+        }
+        _ => false,
+    }
+}
+
+/// Remove `<...>` markup such as `<i>` and `<font color="...">` from a subtitle line
+fn strip_tags(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Cue text with XML escapes resolved, then the HTML entities YouTube double-escapes.
+/// An escape quick-xml rejects (e.g. `&bogus;`) leaves the text as written, with a
+/// warning, instead of failing the whole transcript.
+fn decode_text(e: &quick_xml::events::BytesText) -> String {
+    match e.unescape() {
+        Ok(text) => html_escape::decode_html_entities(&text),
+        Err(err) => {
+            log::warn!(
+                "Keeping caption text with a malformed escape as written: {}",
+                err
+            );
+            html_escape::decode_html_entities(&String::from_utf8_lossy(e))
+        }
+    }
+}
+
+/// Whether a caption body is json3 rather than XML
+fn is_json(body: &str) -> bool {
+    body.trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('{')
+}
+
+#[derive(Deserialize)]
+struct Json3Captions {
+    #[serde(default)]
+    events: Vec<Json3Event>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Event {
+    t_start_ms: Option<f64>,
+    d_duration_ms: Option<f64>,
+    #[serde(default)]
+    segs: Vec<Json3Segment>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Json3Segment {
+    #[serde(default)]
+    utf8: String,
+    t_offset_ms: Option<f64>,
+    ac_asr_conf: Option<f64>,
+}
+
+/// Parse json3 timedtext: each `events[]` entry is a cue whose text is the
+/// concatenation of its `segs[].utf8`. Events without text (window setup and
+/// line breaks) are skipped, as are timed events whose text is blank unless
+/// `keep_empty` is set. Segments carrying a `tOffsetMs` give word timing.
+fn parse_json3<F>(json: &str, keep_empty: bool, mut on_cue: F) -> Result<(), String>
+where
+    F: FnMut(crate::TranscriptItem, CueDetails) -> bool,
+{
+    let captions: Json3Captions =
+        serde_json::from_str(json).map_err(|e| format!("json3 parse error: {}", e))?;
+
+    for event in captions.events {
+        let start = sanitize_time(event.t_start_ms.unwrap_or(0.0) / 1000.0);
+        let duration = sanitize_time(event.d_duration_ms.unwrap_or(0.0) / 1000.0);
+
+        let text: String = event.segs.iter().map(|seg| seg.utf8.as_str()).collect();
+        let text = text.replace('\n', " ").trim().to_string();
+        let is_timed_cue = !event.segs.is_empty() && event.d_duration_ms.is_some();
+        if text.is_empty() && !(keep_empty && is_timed_cue) {
+            continue;
+        }
+
+        let words = if event.segs.iter().any(|seg| seg.t_offset_ms.is_some()) {
+            event
+                .segs
+                .iter()
+                .filter(|seg| !seg.utf8.trim().is_empty())
+                .map(|seg| crate::TimedWord {
+                    text: seg.utf8.trim().to_string(),
+                    start: sanitize_time(start + seg.t_offset_ms.unwrap_or(0.0) / 1000.0),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let confidences: Vec<f64> = event
+            .segs
+            .iter()
+            .filter_map(|seg| seg.ac_asr_conf)
+            .collect();
+        let details = CueDetails {
+            words,
+            confidence: mean_confidence(&confidences),
+        };
+
+        let item = crate::TranscriptItem {
+            text,
             start,
             duration,
-        }))
+        };
+        if !on_cue(item, details) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// What a cue carries besides its text and timing: per-word timing and the
+/// recognizer's confidence, when the format has them
+#[derive(Default)]
+struct CueDetails {
+    words: Vec<crate::TimedWord>,
+    confidence: Option<f64>,
+}
+
+/// Mean of the 0-255 `ac` confidence values of a cue's words, scaled to 0.0-1.0;
+/// `None` when no word carries one
+fn mean_confidence(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    Some((mean / 255.0).clamp(0.0, 1.0))
+}
+
+/// Numeric attribute `key`, or `None` when missing or malformed
+fn number_attribute(e: &quick_xml::events::BytesStart, key: &[u8]) -> Option<f64> {
+    e.attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == key)
+        .and_then(|attr| str::from_utf8(&attr.value).ok()?.parse::<f64>().ok())
+        .filter(|value| value.is_finite())
+}
+
+/// `start` and `dur` of a classic `<text>` cue, in seconds
+fn text_timing(e: &quick_xml::events::BytesStart) -> (f64, f64) {
+    (
+        time_attribute(e, b"start", 1.0),
+        time_attribute(e, b"dur", 1.0),
+    )
+}
+
+/// `t` and `d` of an srv3 `<p>` cue, converted from milliseconds
+fn p_timing(e: &quick_xml::events::BytesStart) -> (f64, f64) {
+    (
+        time_attribute(e, b"t", 1000.0),
+        time_attribute(e, b"d", 1000.0),
+    )
+}
+
+/// Numeric attribute `key` divided by `units_per_second`; missing or malformed
+/// values read as 0.0
+fn time_attribute(e: &quick_xml::events::BytesStart, key: &[u8], units_per_second: f64) -> f64 {
+    number_attribute(e, key)
+        .map(|value| sanitize_time(value / units_per_second))
+        .unwrap_or(0.0)
+}
+
+/// Negative, NaN and infinite times become 0.0, so a malformed attribute can't
+/// produce nonsense such as huge hour counts downstream
+fn sanitize_time(seconds: f64) -> f64 {
+    if seconds.is_finite() && seconds > 0.0 {
+        seconds
+    } else {
+        0.0
     }
 }
 
@@ -333,6 +756,108 @@ mod tests {
         assert_eq!(items[1].start, 2.5);
     }
 
+    #[test]
+    fn test_keep_empty_cues() {
+        let xml = r#"<transcript>
+            <text start="0" dur="1.5">Hello</text>
+            <text start="1.5" dur="2"></text>
+            <text start="3.5" dur="0.5"/>
+            <text start="4" dur="1">world</text>
+        </transcript>"#;
+
+        let dropped = TranscriptParser::new(false).parse(xml).unwrap();
+        assert_eq!(dropped.len(), 2);
+
+        let kept = TranscriptParser::new(false)
+            .keep_empty_cues(true)
+            .parse(xml)
+            .unwrap();
+        let cues: Vec<(&str, f64, f64)> = kept
+            .iter()
+            .map(|i| (i.text.as_str(), i.start, i.duration))
+            .collect();
+        assert_eq!(
+            cues,
+            vec![
+                ("Hello", 0.0, 1.5),
+                ("", 1.5, 2.0),
+                ("", 3.5, 0.5),
+                ("world", 4.0, 1.0)
+            ]
+        );
+
+        let srv3 = r#"<timedtext format="3"><body><p t="0" d="1000">Hi</p><p t="1000" d="500"/></body></timedtext>"#;
+        let kept = TranscriptParser::new(false)
+            .keep_empty_cues(true)
+            .parse(srv3)
+            .unwrap();
+        assert_eq!(
+            (kept[1].text.as_str(), kept[1].start, kept[1].duration),
+            ("", 1.0, 0.5)
+        );
+
+        // json3 window setup events have no segments and stay skipped
+        let json3 = r#"{"events": [{"tStartMs": 0, "dDurationMs": 9000, "id": 1},
+            {"tStartMs": 0, "dDurationMs": 1000, "segs": [{"utf8": "Hi"}]},
+            {"tStartMs": 1000, "dDurationMs": 500, "segs": [{"utf8": " "}]}]}"#;
+        let kept = TranscriptParser::new(false)
+            .keep_empty_cues(true)
+            .parse(json3)
+            .unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!((kept[1].text.as_str(), kept[1].start), ("", 1.0));
+    }
+
+    #[test]
+    fn test_parse_words_srv3() {
+        let xml = r#"<timedtext format="3"><body>
+            <p t="1000" d="2000"><s ac="0">Hello</s><s t="400" ac="0"> big</s><s t="900" ac="0"> world</s></p>
+            <p t="3000" d="1000">No word timing</p>
+        </body></timedtext>"#;
+
+        let parser = TranscriptParser::new(false);
+        let items = parser.parse_words(xml).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].text, "Hello big world");
+        let words: Vec<(&str, f64)> = items[0]
+            .words
+            .iter()
+            .map(|w| (w.text.as_str(), w.start))
+            .collect();
+        assert_eq!(words, vec![("Hello", 1.0), ("big", 1.4), ("world", 1.9)]);
+        assert!(items[1].words.is_empty());
+    }
+
+    #[test]
+    fn test_parse_verbose_confidence() {
+        let xml = r#"<timedtext format="3"><body>
+            <p t="0" d="1000"><s ac="255">Hello</s><s t="400" ac="0"> world</s></p>
+            <p t="1000" d="1000">Manual cue</p>
+        </body></timedtext>"#;
+
+        let items = TranscriptParser::new(false).parse_verbose(xml).unwrap();
+        assert_eq!(items[0].confidence, Some(0.5));
+        assert_eq!(items[1].confidence, None);
+
+        let json = r#"{"events": [{"tStartMs": 0, "dDurationMs": 1000,
+            "segs": [{"utf8": "Hi", "acAsrConf": 255}, {"utf8": " there", "tOffsetMs": 300, "acAsrConf": 255}]}]}"#;
+        let items = TranscriptParser::new(false).parse_verbose(json).unwrap();
+        assert_eq!(items[0].text, "Hi there");
+        assert_eq!(items[0].confidence, Some(1.0));
+    }
+
+    #[test]
+    fn test_parse_words_text_format_has_no_words() {
+        let xml = r#"<transcript><text start="0.0" dur="1.0">Hello</text></transcript>"#;
+
+        let parser = TranscriptParser::new(false);
+        let items = parser.parse_words(xml).unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert!(items[0].words.is_empty());
+    }
+
     #[test]
     fn test_parse_with_html_entities() {
         let xml = r#"<transcript>
@@ -361,6 +886,105 @@ mod tests {
         assert_eq!(items.len(), 0);
     }
 
+    #[test]
+    fn test_parse_with_stops_early() {
+        let xml = r#"<transcript>
+            <text start="0.0" dur="1.0">One</text>
+            <text start="1.0" dur="1.0">Two</text>
+            <text start="2.0" dur="1.0">Three</text>
+        </transcript>"#;
+
+        let parser = TranscriptParser::new(false);
+        let mut seen = Vec::new();
+        parser
+            .parse_with(xml, |item| {
+                seen.push(item.text);
+                seen.len() < 2
+            })
+            .unwrap();
+
+        assert_eq!(seen, vec!["One", "Two"]);
+    }
+
+    #[test]
+    fn test_parse_clamps_malformed_times() {
+        let xml = r#"<transcript>
+            <text start="-1" dur="NaN">Negative start</text>
+            <text start="inf" dur="-2.5">Infinite start</text>
+        </transcript>"#;
+
+        let parser = TranscriptParser::new(false);
+        let items = parser.parse(xml).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].start, 0.0);
+        assert_eq!(items[0].duration, 0.0);
+        assert_eq!(items[1].start, 0.0);
+        assert_eq!(items[1].duration, 0.0);
+    }
+
+    #[test]
+    fn test_parse_p_clamps_malformed_times() {
+        let xml = r#"<timedtext><body>
+            <p t="-500" d="NaN">Hello</p>
+        </body></timedtext>"#;
+
+        let parser = TranscriptParser::new(false);
+        let items = parser.parse(xml).unwrap();
+
+        assert_eq!(items[0].start, 0.0);
+        assert_eq!(items[0].duration, 0.0);
+    }
+
+    #[test]
+    fn test_parse_keeps_non_monotonic_cues() {
+        let xml = r#"<transcript>
+            <text start="5.0" dur="1.0">Later</text>
+            <text start="2.0" dur="1.0">Earlier</text>
+        </transcript>"#;
+
+        let parser = TranscriptParser::new(false);
+        let items = parser.parse(xml).unwrap();
+
+        // Out-of-order cues are only warned about, not dropped or reordered
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].start, 2.0);
+    }
+
+    #[test]
+    fn test_parse_json3() {
+        let json = r#"{"wireMagic":"pb3","events":[
+            {"tStartMs":0,"dDurationMs":5000,"id":1,"wpWinPosId":1},
+            {"tStartMs":1000,"dDurationMs":2500,"wWinId":1,"segs":[{"utf8":"Hello"},{"utf8":" big","tOffsetMs":400},{"utf8":" world","tOffsetMs":900}]},
+            {"tStartMs":3500,"wWinId":1,"aAppend":1,"segs":[{"utf8":"\n"}]},
+            {"tStartMs":4000,"dDurationMs":1000,"segs":[{"utf8":"line one\nline two"}]}
+        ]}"#;
+
+        let parser = TranscriptParser::new(false);
+        let items = parser.parse(json).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].text, "Hello big world");
+        assert_eq!(items[0].start, 1.0);
+        assert_eq!(items[0].duration, 2.5);
+        assert_eq!(items[1].text, "line one line two");
+        assert_eq!(items[1].start, 4.0);
+
+        let items = parser.parse_words(json).unwrap();
+        let words: Vec<(&str, f64)> = items[0]
+            .words
+            .iter()
+            .map(|w| (w.text.as_str(), w.start))
+            .collect();
+        assert_eq!(words, vec![("Hello", 1.0), ("big", 1.4), ("world", 1.9)]);
+        assert!(items[1].words.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid_json3() {
+        let parser = TranscriptParser::new(false);
+        assert!(parser.parse(r#"{"events": [{"tStartMs": "#).is_err());
+    }
+
     #[test]
     fn test_parse_invalid_xml() {
         let xml = "<transcript><text>Unclosed tag";
@@ -368,4 +992,114 @@ mod tests {
         let parser = TranscriptParser::new(false);
         assert!(parser.parse(xml).is_err());
     }
+
+    #[test]
+    fn test_parse_srt_round_trips_srt_times() {
+        use crate::timecode::format_srt_time;
+
+        let cues = [
+            ("first cue", 0.08, 3.839),
+            ("second cue", 61.5, 2.0),
+            ("third", 3725.9, 0.25),
+        ];
+        let srt: String = cues
+            .iter()
+            .enumerate()
+            .map(|(index, (text, start, duration))| {
+                format!(
+                    "{}\r\n{} --> {}\r\n{}\r\n\r\n",
+                    index + 1,
+                    format_srt_time(*start),
+                    format_srt_time(start + duration),
+                    text
+                )
+            })
+            .collect();
+
+        let items = parse_srt(&srt).unwrap();
+        assert_eq!(items.len(), 3);
+        for (item, (text, start, duration)) in items.iter().zip(cues) {
+            assert_eq!(item.text, text);
+            assert!((item.start - start).abs() < 0.0005);
+            assert!((item.duration - duration).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_parse_srt_multiline_and_tags() {
+        let srt = "\u{feff}1\n00:00:01,000 --> 00:00:02,000 X1:10 X2:20\n<i>Hello</i>\n<font color=\"red\">world</font>\n\n\n2\n00:00:03,000 --> 00:00:04,000\n<b></b>\n\n3\n00:00:05.000 --> 00:00:06.500\nbye\n";
+
+        let items = parse_srt(srt).unwrap();
+        let texts: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello world", "bye"]);
+        assert_eq!(items[1].start, 5.0);
+        assert_eq!(items[1].duration, 1.5);
+    }
+
+    #[test]
+    fn test_parse_srt_invalid() {
+        assert!(parse_srt("1\nnot a timing line\ntext\n").is_err());
+        assert!(parse_srt("1\n00:00:xx,000 --> 00:00:02,000\ntext\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_vtt() {
+        let vtt = "WEBVTT - Lecture\nKind: captions\nLanguage: en\n\n\
+            STYLE\n::cue { color: yellow }\n\n\
+            NOTE this is a comment\n\n\
+            intro\n00:00:01.000 --> 00:00:03.500 align:start position:0%\n\
+            <v Ann>Welcome<00:00:02.000><c> back</c></v>\n\n\
+            00:04.250 --> 00:06.000\nfish &amp; chips\non two lines\n";
+
+        let items = parse_vtt(vtt).unwrap();
+        let texts: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["Welcome back", "fish & chips on two lines"]);
+        assert_eq!((items[0].start, items[0].duration), (1.0, 2.5));
+        assert_eq!((items[1].start, items[1].duration), (4.25, 1.75));
+    }
+
+    #[test]
+    fn test_parse_vtt_invalid() {
+        assert!(parse_vtt("WEBVTT\n\nnot a cue\n").is_err());
+        assert!(parse_vtt("WEBVTT\n\n00:01.000 --> soon\nHi\n").is_err());
+    }
+
+    #[test]
+    fn test_is_vtt() {
+        assert!(is_vtt("\u{feff}WEBVTT\n\n00:01.000 --> 00:02.000\nHi\n"));
+        assert!(!is_vtt("1\n00:00:01,000 --> 00:00:02,000\nHi\n"));
+    }
+
+    #[test]
+    fn test_is_srt() {
+        assert!(is_srt("\n1\n00:00:01,000 --> 00:00:02,000\nHi\n"));
+        assert!(is_srt("00:00:01,000 --> 00:00:02,000\nHi\n"));
+        assert!(!is_srt("<transcript></transcript>"));
+        assert!(!is_srt("{\"events\": []}"));
+        assert!(!is_srt("WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nHi\n"));
+    }
+
+    #[test]
+    fn test_decode_lossy_keeps_cues_around_invalid_utf8() {
+        let mut body = b"<transcript><text start=\"0\" dur=\"1\">before</text><text start=\"1\" dur=\"1\">bad ".to_vec();
+        body.extend_from_slice(&[0xff, 0xfe]);
+        body.extend_from_slice(
+            b" bytes</text><text start=\"2\" dur=\"1\">after</text></transcript>",
+        );
+
+        let items = TranscriptParser::new(false)
+            .parse(&decode_lossy(&body, "test body"))
+            .unwrap();
+        let texts: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["before", "bad \u{fffd}\u{fffd} bytes", "after"]);
+    }
+
+    #[test]
+    fn test_parse_keeps_cue_with_malformed_escape() {
+        let xml = r#"<transcript><text start="0" dur="1">one</text><text start="1" dur="1">fish &bogus; chips</text><text start="2" dur="1">two &amp;amp; three</text></transcript>"#;
+
+        let items = TranscriptParser::new(false).parse(xml).unwrap();
+        let texts: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["one", "fish &bogus; chips", "two & three"]);
+    }
 }
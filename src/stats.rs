@@ -0,0 +1,93 @@
+use crate::TranscriptItem;
+
+/// Size and pace of a transcript, for estimating reading or listening time
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct TranscriptStats {
+    pub cues: usize,
+    /// Seconds from the start of the first cue to the end of the last one
+    pub duration: f64,
+    pub words: usize,
+    /// Words spoken per minute of `duration`; zero when the duration is zero
+    pub words_per_minute: f64,
+    /// Mean cue duration in seconds
+    pub average_cue_duration: f64,
+}
+
+impl TranscriptStats {
+    pub fn from_items(items: &[TranscriptItem]) -> Self {
+        let first_start = items
+            .iter()
+            .map(|item| item.start)
+            .fold(f64::INFINITY, f64::min);
+        let last_end = items
+            .iter()
+            .map(|item| item.start + item.duration)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let duration = if items.is_empty() {
+            0.0
+        } else {
+            (last_end - first_start).max(0.0)
+        };
+
+        let words = items
+            .iter()
+            .map(|item| item.text.split_whitespace().count())
+            .sum();
+        let words_per_minute = if duration > 0.0 {
+            words as f64 / (duration / 60.0)
+        } else {
+            0.0
+        };
+        let average_cue_duration = if items.is_empty() {
+            0.0
+        } else {
+            items.iter().map(|item| item.duration).sum::<f64>() / items.len() as f64
+        };
+
+        TranscriptStats {
+            cues: items.len(),
+            duration,
+            words,
+            words_per_minute,
+            average_cue_duration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, start: f64, duration: f64) -> TranscriptItem {
+        TranscriptItem {
+            text: text.to_string(),
+            start,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_transcript_stats() {
+        let items = vec![
+            item("one two three", 10.0, 4.0),
+            item("four five", 14.0, 2.0),
+            item("six seven eight nine ten", 16.0, 24.0),
+        ];
+
+        let stats = TranscriptStats::from_items(&items);
+        assert_eq!(stats.cues, 3);
+        assert_eq!(stats.duration, 30.0);
+        assert_eq!(stats.words, 10);
+        assert_eq!(stats.words_per_minute, 20.0);
+        assert_eq!(stats.average_cue_duration, 10.0);
+    }
+
+    #[test]
+    fn test_transcript_stats_empty() {
+        let stats = TranscriptStats::from_items(&[]);
+        assert_eq!(stats.cues, 0);
+        assert_eq!(stats.duration, 0.0);
+        assert_eq!(stats.words_per_minute, 0.0);
+        assert_eq!(stats.average_cue_duration, 0.0);
+    }
+}
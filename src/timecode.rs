@@ -0,0 +1,138 @@
+use crate::error::{Result, TranscriptError};
+
+/// Parse a timecode into seconds.
+///
+/// Accepts `ss`, `mm:ss` and `hh:mm:ss`, each optionally followed by a
+/// fractional part using `.` or `,` (so SRT timestamps round-trip).
+pub fn parse_timecode(timecode: &str) -> Result<f64> {
+    let invalid = || {
+        TranscriptError::InvalidArgument(format!("{} (expected ss, mm:ss or hh:mm:ss)", timecode))
+    };
+
+    let parts: Vec<&str> = timecode.trim().split(':').collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0.0;
+    for (index, part) in parts.iter().enumerate() {
+        let is_last = index == parts.len() - 1;
+        let value: f64 = if is_last {
+            part.replace(',', ".").parse().map_err(|_| invalid())?
+        } else {
+            part.parse::<u32>().map_err(|_| invalid())? as f64
+        };
+        if value < 0.0 || !value.is_finite() {
+            return Err(invalid());
+        }
+        // Minutes and seconds fields must stay below 60 when a larger unit precedes them
+        if index > 0 && value >= 60.0 {
+            return Err(invalid());
+        }
+        seconds = seconds * 60.0 + value;
+    }
+
+    Ok(seconds)
+}
+
+/// Format seconds as `hh:mm:ss`, optionally followed by `millis_sep` and milliseconds
+///
+/// The value is rounded to whole milliseconds once, before it is split up, so the
+/// SRT, VTT and display forms of a time always agree. Negative values format as zero.
+pub fn format_timecode(seconds: f64, millis_sep: char, with_millis: bool) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let total_secs = total_millis / 1000;
+    let hms = format!(
+        "{:02}:{:02}:{:02}",
+        total_secs / 3600,
+        (total_secs % 3600) / 60,
+        total_secs % 60
+    );
+    if with_millis {
+        format!("{}{}{:03}", hms, millis_sep, total_millis % 1000)
+    } else {
+        hms
+    }
+}
+
+/// SRT cue time, e.g. `00:01:05,500`
+pub fn format_srt_time(seconds: f64) -> String {
+    format_timecode(seconds, ',', true)
+}
+
+/// WebVTT cue time, e.g. `00:01:05.500`
+pub fn format_vtt_time(seconds: f64) -> String {
+    format_timecode(seconds, '.', true)
+}
+
+/// Parse a `START-END` range such as `1:05-2:30` into seconds
+pub fn parse_clip(range: &str) -> Result<(f64, f64)> {
+    let (start, end) = range.split_once('-').ok_or_else(|| {
+        TranscriptError::InvalidArgument(format!("{} (expected START-END, e.g. 1:05-2:30)", range))
+    })?;
+
+    let start = parse_timecode(start)?;
+    let end = parse_timecode(end)?;
+    if start >= end {
+        return Err(TranscriptError::InvalidArgument(format!(
+            "{} (clip start must be before clip end)",
+            range
+        )));
+    }
+
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timecode() {
+        assert_eq!(parse_timecode("42").unwrap(), 42.0);
+        assert_eq!(parse_timecode("1:05").unwrap(), 65.0);
+        assert_eq!(parse_timecode("01:02:03").unwrap(), 3723.0);
+        assert_eq!(parse_timecode("00:01:05,500").unwrap(), 65.5);
+        assert_eq!(parse_timecode("1:05.25").unwrap(), 65.25);
+    }
+
+    #[test]
+    fn test_parse_timecode_invalid() {
+        assert!(parse_timecode("").is_err());
+        assert!(parse_timecode("abc").is_err());
+        assert!(parse_timecode("1:75").is_err());
+        assert!(parse_timecode("1:2:3:4").is_err());
+    }
+
+    #[test]
+    fn test_format_timecode() {
+        assert_eq!(format_timecode(0.0, ',', true), "00:00:00,000");
+        assert_eq!(format_timecode(65.5, ',', true), "00:01:05,500");
+        assert_eq!(format_timecode(3661.123, '.', true), "01:01:01.123");
+        assert_eq!(format_timecode(3725.9, ',', false), "01:02:05");
+        assert_eq!(format_timecode(360000.0, ',', false), "100:00:00");
+        assert_eq!(format_timecode(-2.0, ',', true), "00:00:00,000");
+    }
+
+    #[test]
+    fn test_format_timecode_rounds_before_splitting() {
+        // 59.9996s must not come out as 00:00:59,1000 or disagree between formats
+        assert_eq!(format_srt_time(59.9996), "00:01:00,000");
+        assert_eq!(format_vtt_time(59.9996), "00:01:00.000");
+        assert_eq!(format_timecode(59.9996, ',', false), "00:01:00");
+        assert_eq!(format_srt_time(0.3), "00:00:00,300");
+    }
+
+    #[test]
+    fn test_parse_clip() {
+        assert_eq!(parse_clip("1:05-2:30").unwrap(), (65.0, 150.0));
+        assert_eq!(parse_clip("01:02:03-01:05:00").unwrap(), (3723.0, 3900.0));
+    }
+
+    #[test]
+    fn test_parse_clip_invalid() {
+        assert!(parse_clip("1:05").is_err());
+        assert!(parse_clip("2:30-1:05").is_err());
+        assert!(parse_clip("x-1:05").is_err());
+    }
+}
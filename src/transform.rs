@@ -0,0 +1,1125 @@
+use crate::TranscriptItem;
+
+/// Keep only the cues whose `[start, start + duration)` span intersects the
+/// `[start, end)` window. A `None` bound leaves that side of the window open.
+///
+/// When `clamp` is set, cues straddling a window edge are trimmed so they
+/// start and end inside the window.
+pub fn slice_by_time(
+    items: &[TranscriptItem],
+    start: Option<f64>,
+    end: Option<f64>,
+    clamp: bool,
+) -> Vec<TranscriptItem> {
+    let window_start = start.unwrap_or(0.0);
+    let window_end = end.unwrap_or(f64::INFINITY);
+
+    items
+        .iter()
+        .filter(|item| {
+            let item_end = item.start + item.duration;
+            item.start < window_end && (item_end > window_start || item.start >= window_start)
+        })
+        .map(|item| {
+            if !clamp {
+                return item.clone();
+            }
+            let clamped_start = item.start.max(window_start);
+            let clamped_end = (item.start + item.duration).min(window_end);
+            TranscriptItem {
+                text: item.text.clone(),
+                start: clamped_start,
+                duration: (clamped_end - clamped_start).max(0.0),
+            }
+        })
+        .collect()
+}
+
+/// Add `offset` seconds (possibly negative) to every cue's start
+///
+/// A cue pushed partly before zero starts at zero instead, with its duration
+/// shortened by the amount cut off. Cues that end at or before zero are dropped.
+pub fn shift_timestamps(items: &[TranscriptItem], offset: f64) -> Vec<TranscriptItem> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let start = item.start + offset;
+            let end = start + item.duration;
+            if start >= 0.0 {
+                Some(TranscriptItem {
+                    text: item.text.clone(),
+                    start,
+                    duration: item.duration,
+                })
+            } else if end > 0.0 {
+                Some(TranscriptItem {
+                    text: item.text.clone(),
+                    start: 0.0,
+                    duration: end,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Drop cues shorter than `min_duration` seconds, such as the zero-length cues some
+/// tracks contain. A `min_duration` of zero keeps everything.
+pub fn drop_short_cues(items: &[TranscriptItem], min_duration: f64) -> Vec<TranscriptItem> {
+    items
+        .iter()
+        .filter(|item| item.duration >= min_duration)
+        .cloned()
+        .collect()
+}
+
+/// Trim each cue that runs past the start of the next one so it ends where the next
+/// cue starts, so players show one subtitle at a time instead of stacking them
+///
+/// Expects cues in start order. Cues that don't overlap are left unchanged.
+pub fn fix_overlaps(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let mut result = items.to_vec();
+    for index in 1..result.len() {
+        let next_start = result[index].start;
+        let previous = &mut result[index - 1];
+        if previous.start + previous.duration > next_start {
+            previous.duration = (next_start - previous.start).max(0.0);
+        }
+    }
+    result
+}
+
+/// Shortest duration [`repace`] gives a cue, unless the next cue starts sooner
+const MIN_PACED_DURATION: f64 = 1.0;
+
+/// Recompute cue durations from their length, for auto captions whose durations are
+/// zero or all the same and make subtitles flash
+///
+/// The speaking rate is the median, over all cues, of the seconds per character from
+/// a cue's start to the next cue's, so a few long pauses between cues don't slow it
+/// down. Each cue then lasts as long as its text takes at that rate: at least a
+/// second, but never past the start of the next cue, so cues can't overlap. Cues
+/// sharing a start time are shown together until the next later cue. The last cue has
+/// no next cue and just gets its length at that rate. Expects cues in start order; a
+/// transcript with no gaps to measure is returned unchanged.
+pub fn repace(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let chars = |item: &TranscriptItem| item.text.chars().count() as f64;
+
+    // Start of the next cue that starts later than each one
+    let mut next_start = vec![None; items.len()];
+    for index in (0..items.len().saturating_sub(1)).rev() {
+        next_start[index] = if items[index + 1].start > items[index].start {
+            Some(items[index + 1].start)
+        } else {
+            next_start[index + 1]
+        };
+    }
+
+    // One rate sample per start time, over the text of every cue starting then
+    let mut seconds_per_char = Vec::new();
+    let mut index = 0;
+    while index < items.len() {
+        let group = items[index..]
+            .iter()
+            .take_while(|item| item.start == items[index].start)
+            .count();
+        let text: f64 = items[index..index + group].iter().map(chars).sum();
+        if let Some(next) = next_start[index] {
+            if text > 0.0 {
+                seconds_per_char.push((next - items[index].start) / text);
+            }
+        }
+        index += group;
+    }
+    if seconds_per_char.is_empty() {
+        return items.to_vec();
+    }
+    seconds_per_char.sort_by(f64::total_cmp);
+    let seconds_per_char = seconds_per_char[seconds_per_char.len() / 2];
+
+    items
+        .iter()
+        .zip(&next_start)
+        .map(|(item, next)| {
+            let paced = (chars(item) * seconds_per_char).max(MIN_PACED_DURATION);
+            let duration = match next {
+                Some(next) => paced.min(next - item.start),
+                None => paced,
+            };
+            TranscriptItem {
+                duration,
+                ..item.clone()
+            }
+        })
+        .collect()
+}
+
+/// A cue in one language together with its text in another
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BilingualCue {
+    pub start: f64,
+    pub duration: f64,
+    pub original: String,
+    pub translated: String,
+}
+
+/// Pair each `source` cue with the `target` text spoken at the same time
+///
+/// YouTube's translations keep the source cue timing, so cues usually pair up one to
+/// one. A separately uploaded track can be cut differently, so each target cue goes
+/// to the source cue it overlaps most (or, if it overlaps none, the one starting
+/// nearest to it), and cues that receive several are joined with spaces.
+pub fn combine_languages(
+    source: &[TranscriptItem],
+    target: &[TranscriptItem],
+) -> Vec<BilingualCue> {
+    let mut translated: Vec<Vec<&str>> = vec![Vec::new(); source.len()];
+
+    for cue in target {
+        let overlap = |item: &TranscriptItem| {
+            let start = item.start.max(cue.start);
+            let end = (item.start + item.duration).min(cue.start + cue.duration);
+            end - start
+        };
+        let best = source
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                let by_overlap = overlap(a).max(0.0).total_cmp(&overlap(b).max(0.0));
+                // Without any overlap, prefer the cue starting nearest
+                by_overlap.then_with(|| {
+                    (b.start - cue.start)
+                        .abs()
+                        .total_cmp(&(a.start - cue.start).abs())
+                })
+            })
+            .map(|(index, _)| index);
+        if let Some(index) = best {
+            translated[index].push(&cue.text);
+        }
+    }
+
+    source
+        .iter()
+        .zip(translated)
+        .map(|(item, texts)| BilingualCue {
+            start: item.start,
+            duration: item.duration,
+            original: item.text.clone(),
+            translated: texts.join(" "),
+        })
+        .collect()
+}
+
+/// Group cues under the chapter they start in
+///
+/// `chapters` are `(title, start)` pairs in order. Each cue goes to the last chapter
+/// starting at or before it; cues before the first chapter go to the first one.
+/// Chapters left without cues are omitted.
+pub fn split_by_chapters<'a>(
+    items: &'a [TranscriptItem],
+    chapters: &'a [(String, f64)],
+) -> Vec<(&'a str, Vec<&'a TranscriptItem>)> {
+    let mut sections: Vec<(&str, Vec<&TranscriptItem>)> = chapters
+        .iter()
+        .map(|(title, _)| (title.as_str(), Vec::new()))
+        .collect();
+    if sections.is_empty() {
+        return sections;
+    }
+
+    for item in items {
+        let index = chapters
+            .partition_point(|(_, start)| *start <= item.start)
+            .saturating_sub(1);
+        sections[index].1.push(item);
+    }
+
+    sections.retain(|(_, cues)| !cues.is_empty());
+    sections
+}
+
+/// Sound events that are stripped when written in parentheses, e.g. `(laughter)`.
+/// Anything in square brackets is always treated as an annotation.
+const PARENTHESIZED_ANNOTATIONS: &[&str] = &[
+    "applause",
+    "background noise",
+    "cheering",
+    "cheers",
+    "coughing",
+    "coughs",
+    "crosstalk",
+    "inaudible",
+    "laughing",
+    "laughs",
+    "laughter",
+    "music",
+    "sighs",
+    "silence",
+];
+
+/// Remove non-speech annotations such as `[Music]`, `[Applause]` and `(laughter)`
+///
+/// Every `[...]` segment is removed, apart from YouTube's `[ __ ]` marker for a
+/// censored word, along with parenthesized sound events from a fixed list (so
+/// spoken asides in parentheses are kept). Cues that contained nothing but
+/// annotations are dropped.
+pub fn strip_annotations(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let pattern = format!(
+        r"(?i)\[[^\]]*\]|\((?:{})\)",
+        PARENTHESIZED_ANNOTATIONS.join("|")
+    );
+    let annotation = regex::Regex::new(&pattern).expect("annotation pattern is valid");
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let stripped = annotation.replace_all(&item.text, |caps: &regex::Captures| {
+                // The censored-word marker stands for speech, so it stays
+                if caps[0].trim_matches(['[', ']', ' ']) == "__" {
+                    caps[0].to_string()
+                } else {
+                    " ".to_string()
+                }
+            });
+            let text = stripped.split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                return None;
+            }
+            Some(TranscriptItem {
+                text,
+                start: item.start,
+                duration: item.duration,
+            })
+        })
+        .collect()
+}
+
+/// Remove timecodes such as `0:15`, `12:03` or `(1:02:03)` that uploaders typed into
+/// the caption text
+///
+/// Only whole words with two-digit seconds count, so ratios like "3:1" and scores
+/// like "2:0" stay, and only where they read as a timestamp: in brackets or
+/// parentheses anywhere, or bare at the start of a cue. Bare ones mid-sentence, as in
+/// "a 16:10 display", "the 21:15 train" or "John 3:16", stay, as does a leading one
+/// followed by "ratio" or by "am" or "pm". Punctuation right after a removed timecode
+/// stays with the word before it, and cues with no timecode are left as they were,
+/// line breaks included. Cues that contained nothing but timecodes are dropped.
+pub fn strip_timecodes(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let bare = regex::Regex::new(r"^(?:\d{1,2}:)?\d{1,2}:[0-5]\d([.,;:-]?)$")
+        .expect("timecode pattern is valid");
+    let bracketed = regex::Regex::new(
+        r"^(?:\[(?:\d{1,2}:)?\d{1,2}:[0-5]\d\]|\((?:\d{1,2}:)?\d{1,2}:[0-5]\d\))([.,;:-]?)$",
+    )
+    .expect("timecode pattern is valid");
+    let word = regex::Regex::new(r"\S+").expect("word pattern is valid");
+    let is_time_of_day_or_ratio = |word: &str| {
+        let word = word.trim_end_matches(['.', ',']).to_lowercase();
+        matches!(word.as_str(), "am" | "pm" | "a.m" | "p.m" | "ratio")
+    };
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let words: Vec<regex::Match> = word.find_iter(&item.text).collect();
+            // Bare timecodes count only in the run of them that opens the cue
+            let leading = words
+                .iter()
+                .take_while(|word| {
+                    bare.is_match(word.as_str()) || bracketed.is_match(word.as_str())
+                })
+                .count();
+            let timecodes: Vec<(&regex::Match, &str)> = words
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| {
+                    !words
+                        .get(index + 1)
+                        .is_some_and(|next| is_time_of_day_or_ratio(next.as_str()))
+                })
+                .filter_map(|(index, word)| {
+                    let caps = bracketed
+                        .captures(word.as_str())
+                        .or_else(|| bare.captures(word.as_str()).filter(|_| index < leading))?;
+                    Some((
+                        word,
+                        caps.get(1).map_or("", |punctuation| punctuation.as_str()),
+                    ))
+                })
+                .collect();
+            if timecodes.is_empty() {
+                return Some(item.clone());
+            }
+
+            let mut text = String::new();
+            let mut rest = 0;
+            for (timecode, punctuation) in timecodes {
+                let before = &item.text[rest..timecode.start()];
+                rest = timecode.end();
+                if punctuation.is_empty() || (text.trim().is_empty() && before.trim().is_empty()) {
+                    // Drop the timecode along with the space after it
+                    text.push_str(before);
+                    let remaining = &item.text[rest..];
+                    rest += remaining.len() - remaining.trim_start().len();
+                } else {
+                    text.push_str(before.trim_end());
+                    text.push_str(punctuation);
+                }
+            }
+            text.push_str(&item.text[rest..]);
+            let text = text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(TranscriptItem {
+                text: text.to_string(),
+                start: item.start,
+                duration: item.duration,
+            })
+        })
+        .collect()
+}
+
+/// A `--replace` rule: a regex and the text that replaces its matches
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    pattern: regex::Regex,
+    replacement: String,
+    all: bool,
+}
+
+impl Replacement {
+    /// Parse a sed-style `/pattern/replacement/flags` rule
+    ///
+    /// The first character is the delimiter, so `|a/b|c|` works for patterns with
+    /// slashes; `\` before the delimiter makes it literal. The replacement may refer to
+    /// groups as `$1` or `${name}`. Flags are `g` (replace every match, not just the
+    /// first) and the regex flags `i`, `m`, `s` and `x`.
+    ///
+    /// ```
+    /// use ytt::transform::Replacement;
+    /// let rule = Replacement::parse("/jon (\\w+)/John $1/gi").unwrap();
+    /// assert_eq!(rule.apply("JON smith met jon doe"), "John smith met John doe");
+    /// ```
+    pub fn parse(spec: &str) -> crate::Result<Self> {
+        let invalid = |reason: &str| {
+            crate::TranscriptError::InvalidArgument(format!("--replace {}: {}", spec, reason))
+        };
+        let mut chars = spec.chars();
+        let delimiter = chars
+            .next()
+            .filter(|c| !c.is_alphanumeric() && *c != '\\' && !c.is_whitespace())
+            .ok_or_else(|| invalid("expected /pattern/replacement/flags"))?;
+
+        let mut parts = vec![String::new()];
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                    Some(next) => parts.last_mut().unwrap().extend(['\\', next]),
+                    None => parts.last_mut().unwrap().push('\\'),
+                },
+                _ if c == delimiter => parts.push(String::new()),
+                _ => parts.last_mut().unwrap().push(c),
+            }
+        }
+        let [pattern, replacement, flags] = <[String; 3]>::try_from(parts)
+            .map_err(|_| invalid("expected /pattern/replacement/flags"))?;
+
+        let mut builder = regex::RegexBuilder::new(&pattern);
+        let mut all = false;
+        for flag in flags.chars() {
+            match flag {
+                'g' => all = true,
+                'i' => {
+                    builder.case_insensitive(true);
+                }
+                'm' => {
+                    builder.multi_line(true);
+                }
+                's' => {
+                    builder.dot_matches_new_line(true);
+                }
+                'x' => {
+                    builder.ignore_whitespace(true);
+                }
+                _ => return Err(invalid(&format!("unknown flag '{}'", flag))),
+            }
+        }
+        let pattern = builder.build().map_err(|e| invalid(&e.to_string()))?;
+
+        Ok(Self {
+            pattern,
+            replacement,
+            all,
+        })
+    }
+
+    /// `text` with the first match, or every match with the `g` flag, replaced
+    pub fn apply(&self, text: &str) -> String {
+        if self.all {
+            self.pattern
+                .replace_all(text, self.replacement.as_str())
+                .into_owned()
+        } else {
+            self.pattern
+                .replace(text, self.replacement.as_str())
+                .into_owned()
+        }
+    }
+}
+
+/// Apply every replacement to each cue's text, in order
+///
+/// Cues whose text ends up empty are dropped.
+pub fn apply_replacements(
+    items: &[TranscriptItem],
+    replacements: &[Replacement],
+) -> Vec<TranscriptItem> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let text = replacements
+                .iter()
+                .fold(item.text.clone(), |text, replacement| {
+                    replacement.apply(&text)
+                });
+            let text = text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(TranscriptItem {
+                text: text.to_string(),
+                start: item.start,
+                duration: item.duration,
+            })
+        })
+        .collect()
+}
+
+/// Recase ALL-CAPS caption text: lowercase it, then capitalize the first letter of each
+/// sentence and the pronoun "I" (also in I'm, I'll, I've and I'd)
+///
+/// A heuristic: names and acronyms come out lowercase.
+///
+/// ```
+/// assert_eq!(ytt::transform::recase("SO I'M BACK. WHAT NOW?"), "So I'm back. What now?");
+/// ```
+pub fn recase(text: &str) -> String {
+    recase_from(text, true)
+}
+
+/// [`recase`], capitalizing the first letter only when `sentence_start` is set
+fn recase_from(text: &str, sentence_start: bool) -> String {
+    let chars: Vec<char> = text.to_lowercase().chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut capitalize_next = sentence_start;
+
+    for (i, &c) in chars.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        if c.is_alphanumeric() {
+            let pronoun_i = c == 'i'
+                && !previous.is_some_and(char::is_alphanumeric)
+                && !next.is_some_and(char::is_alphanumeric);
+            if (capitalize_next && c.is_alphabetic()) || pronoun_i {
+                result.extend(c.to_uppercase());
+            } else {
+                result.push(c);
+            }
+            capitalize_next = false;
+        } else {
+            result.push(c);
+            // Only punctuation ending a word ends a sentence, so 3.14 and U.S.A stay put
+            if matches!(c, '.' | '?' | '!') && next.is_none_or(char::is_whitespace) {
+                capitalize_next = true;
+            }
+        }
+    }
+
+    result
+}
+
+/// [`recase`] every cue that has no lowercase letters (`--fix-case`)
+///
+/// Sentences run across cues, so a cue's first letter is only capitalized when the
+/// previous cue ended with `.`, `?` or `!`. Cues already in mixed case are kept as is.
+pub fn fix_case(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let mut sentence_start = true;
+    items
+        .iter()
+        .map(|item| {
+            let text = if item.text.chars().any(char::is_lowercase) {
+                item.text.clone()
+            } else {
+                recase_from(&item.text, sentence_start)
+            };
+            if let Some(last) = text.trim_end().chars().last() {
+                sentence_start = matches!(last, '.' | '?' | '!');
+            }
+            TranscriptItem {
+                text,
+                ..item.clone()
+            }
+        })
+        .collect()
+}
+
+/// Tidy caption text: collapse runs of whitespace into single spaces, remove
+/// spaces before `.`, `?`, `!` and `,`, and add the missing space after them
+///
+/// A space is only added after `.`, `?` or `!` when a lowercase letter precedes it and
+/// an uppercase one follows (`end.Next`), and after `,` when a letter follows, so
+/// numbers (`3.14`, `1,000`), abbreviations (`U.S.`) and domains (`example.com`)
+/// are left alone.
+pub fn normalize_text(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let chars: Vec<char> = collapsed.chars().collect();
+    let mut result = String::with_capacity(collapsed.len());
+
+    for (index, &c) in chars.iter().enumerate() {
+        let next = chars.get(index + 1).copied();
+        if c == ' ' && matches!(next, Some('.' | '?' | '!' | ',')) {
+            continue;
+        }
+        result.push(c);
+
+        let previous = result.chars().rev().nth(1);
+        let needs_space = match (c, next) {
+            ('.' | '?' | '!', Some(next)) => {
+                next.is_uppercase() && previous.is_some_and(char::is_lowercase)
+            }
+            (',', Some(next)) => next.is_alphabetic(),
+            _ => false,
+        };
+        if needs_space {
+            result.push(' ');
+        }
+    }
+
+    result
+}
+
+/// Drop a cue whose trimmed text is exactly the same as the previous cue's, extending
+/// the previous cue to cover both
+///
+/// A stricter, safer cleanup than [`dedupe_rolling`]: only back-to-back repeats of a
+/// whole cue are removed. Running both is fine.
+pub fn drop_duplicate_cues(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let mut result: Vec<TranscriptItem> = Vec::with_capacity(items.len());
+
+    for item in items {
+        if let Some(last) = result.last_mut() {
+            if last.text.trim() == item.text.trim() {
+                let end = (item.start + item.duration).max(last.start + last.duration);
+                last.duration = end - last.start;
+                continue;
+            }
+        }
+        result.push(item.clone());
+    }
+
+    result
+}
+
+/// Remove the words a rolling auto-caption cue repeats from the end of the previous cue
+///
+/// YouTube's auto-captions often show the tail of one cue again at the head of
+/// the next. For each pair of consecutive cues, the longest word sequence that is
+/// both a suffix of the previous cue and a prefix of the current one is stripped
+/// from the current cue (compared case-insensitively). Cues left empty are dropped
+/// and their time is folded into the preceding cue.
+pub fn dedupe_rolling(items: &[TranscriptItem]) -> Vec<TranscriptItem> {
+    let mut result: Vec<TranscriptItem> = Vec::with_capacity(items.len());
+    let mut previous_words: Vec<String> = Vec::new();
+
+    for item in items {
+        let words: Vec<&str> = item.text.split_whitespace().collect();
+        let normalized: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+
+        let max_overlap = previous_words.len().min(normalized.len());
+        let overlap = (1..=max_overlap)
+            .rev()
+            .find(|&k| previous_words[previous_words.len() - k..] == normalized[..k])
+            .unwrap_or(0);
+
+        previous_words = normalized;
+
+        if overlap == words.len() {
+            if let Some(last) = result.last_mut() {
+                let end = (item.start + item.duration).max(last.start + last.duration);
+                last.duration = end - last.start;
+                continue;
+            }
+        }
+
+        result.push(TranscriptItem {
+            text: words[overlap..].join(" "),
+            start: item.start,
+            duration: item.duration,
+        });
+    }
+
+    result.retain(|item| !item.text.is_empty());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, start: f64, duration: f64) -> TranscriptItem {
+        TranscriptItem {
+            text: text.to_string(),
+            start,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_slice_by_time_keeps_overlapping_cues() {
+        let items = vec![
+            item("a", 0.0, 5.0),
+            item("b", 5.0, 5.0),
+            item("c", 10.0, 5.0),
+            item("d", 15.0, 5.0),
+        ];
+
+        let sliced = slice_by_time(&items, Some(7.0), Some(12.0), false);
+        let texts: Vec<&str> = sliced.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["b", "c"]);
+        assert_eq!(sliced[0].start, 5.0);
+        assert_eq!(sliced[0].duration, 5.0);
+    }
+
+    #[test]
+    fn test_slice_by_time_end_is_exclusive() {
+        let items = vec![item("a", 0.0, 5.0), item("b", 5.0, 5.0)];
+
+        let sliced = slice_by_time(&items, None, Some(5.0), false);
+        assert_eq!(sliced.len(), 1);
+        assert_eq!(sliced[0].text, "a");
+    }
+
+    #[test]
+    fn test_slice_by_time_open_ended() {
+        let items = vec![
+            item("a", 0.0, 5.0),
+            item("b", 5.0, 5.0),
+            item("c", 10.0, 5.0),
+        ];
+
+        assert_eq!(slice_by_time(&items, Some(6.0), None, false).len(), 2);
+        assert_eq!(slice_by_time(&items, None, None, false).len(), 3);
+    }
+
+    #[test]
+    fn test_slice_by_time_clamps_boundaries() {
+        let items = vec![item("a", 0.0, 5.0), item("b", 5.0, 5.0)];
+
+        let sliced = slice_by_time(&items, Some(2.0), Some(8.0), true);
+        assert_eq!(sliced.len(), 2);
+        assert_eq!(sliced[0].start, 2.0);
+        assert_eq!(sliced[0].duration, 3.0);
+        assert_eq!(sliced[1].start, 5.0);
+        assert_eq!(sliced[1].duration, 3.0);
+    }
+
+    #[test]
+    fn test_shift_timestamps_positive() {
+        let items = vec![item("a", 0.0, 2.0), item("b", 2.0, 2.0)];
+
+        let shifted = shift_timestamps(&items, 1.5);
+        assert_eq!(shifted[0].start, 1.5);
+        assert_eq!(shifted[1].start, 3.5);
+        assert_eq!(shifted[1].duration, 2.0);
+    }
+
+    #[test]
+    fn test_shift_timestamps_negative_clamps_to_zero() {
+        let items = vec![
+            item("a", 0.0, 2.0),
+            item("b", 2.0, 3.0),
+            item("c", 6.0, 1.0),
+        ];
+
+        let shifted = shift_timestamps(&items, -3.0);
+        let texts: Vec<&str> = shifted.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["b", "c"]);
+        assert_eq!(shifted[0].start, 0.0);
+        assert_eq!(shifted[0].duration, 2.0);
+        assert_eq!(shifted[1].start, 3.0);
+    }
+
+    #[test]
+    fn test_drop_short_cues() {
+        let items = vec![
+            item("a", 0.0, 2.0),
+            item("flash", 2.0, 0.0),
+            item("b", 2.0, 0.1),
+        ];
+
+        let kept = drop_short_cues(&items, 0.1);
+        let texts: Vec<&str> = kept.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "b"]);
+        assert_eq!(drop_short_cues(&items, 0.0).len(), 3);
+    }
+
+    #[test]
+    fn test_fix_overlaps() {
+        let items = vec![
+            item("a", 0.0, 3.0),
+            item("b", 2.0, 2.0),
+            item("c", 5.0, 1.0),
+        ];
+
+        let fixed = fix_overlaps(&items);
+        assert_eq!(fixed[0].duration, 2.0);
+        assert_eq!(fixed[1].duration, 2.0);
+        assert_eq!(fixed[2].duration, 1.0);
+        assert_eq!(fixed[1].start, 2.0);
+    }
+
+    #[test]
+    fn test_combine_languages_pairs_matching_cues() {
+        let source = vec![item("hello", 0.0, 2.0), item("good morning", 2.0, 2.0)];
+        let target = vec![item("hola", 0.0, 2.0), item("buenos días", 2.0, 2.0)];
+
+        let combined = combine_languages(&source, &target);
+        assert_eq!(combined.len(), 2);
+        assert_eq!(combined[1].original, "good morning");
+        assert_eq!(combined[1].translated, "buenos días");
+        assert_eq!(combined[1].start, 2.0);
+    }
+
+    #[test]
+    fn test_combine_languages_aligns_by_time() {
+        let source = vec![
+            item("one", 0.0, 4.0),
+            item("two", 4.0, 4.0),
+            item("three", 20.0, 2.0),
+        ];
+        let target = vec![
+            item("uno", 0.5, 1.5),
+            item("y", 2.0, 1.0),
+            item("dos", 3.5, 4.0),
+            item("tres", 18.0, 1.0),
+        ];
+
+        let combined = combine_languages(&source, &target);
+        let translated: Vec<&str> = combined.iter().map(|c| c.translated.as_str()).collect();
+        assert_eq!(translated, vec!["uno y", "dos", "tres"]);
+    }
+
+    #[test]
+    fn test_repace() {
+        let items = vec![
+            item("a short one", 0.0, 0.0),
+            item("this cue has quite a lot more text", 2.0, 5.0),
+            item("hi", 6.0, 5.0),
+            item("the end", 6.5, 0.0),
+        ];
+
+        let paced = repace(&items);
+        // Median of 2s over 11 characters, 4s over 34 and 0.5s over 2
+        let rate = 2.0 / 11.0;
+        assert!((paced[0].duration - 2.0).abs() < 1e-9);
+        assert_eq!(paced[1].duration, 4.0);
+        assert_eq!(paced[2].duration, 0.5);
+        assert!((paced[3].duration - 7.0 * rate).abs() < 1e-9);
+        for pair in paced.windows(2) {
+            assert!(pair[0].start + pair[0].duration <= pair[1].start);
+        }
+        assert_eq!(paced[1].start, 2.0);
+    }
+
+    #[test]
+    fn test_repace_ignores_long_pauses() {
+        let items = vec![
+            item("ten chars!", 0.0, 0.0),
+            item("ten chars!", 2.0, 0.0),
+            item("ten chars!", 4.0, 0.0),
+            item("ten chars!", 6.0, 0.0),
+            // A minute of silence
+            item("ten chars!", 66.0, 0.0),
+            item("ten chars!", 68.0, 0.0),
+        ];
+
+        // 0.2s per character, not the 1.36 a mean over the pause would give
+        let paced = repace(&items);
+        let durations: Vec<f64> = paced.iter().map(|i| i.duration).collect();
+        assert_eq!(durations, vec![2.0, 2.0, 2.0, 2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn test_repace_cues_sharing_a_start() {
+        let items = vec![
+            item("first line", 0.0, 0.0),
+            item("second line", 0.0, 0.0),
+            item("next", 5.0, 0.0),
+        ];
+
+        // Both lines are on screen until "next", at 5s over their 21 characters
+        let paced = repace(&items);
+        let rate = 5.0 / 21.0;
+        assert!((paced[0].duration - 10.0 * rate).abs() < 1e-9);
+        assert!((paced[1].duration - 11.0 * rate).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_repace_without_gaps_is_unchanged() {
+        let items = vec![item("same", 1.0, 0.0), item("time", 1.0, 0.0)];
+        assert_eq!(repace(&items), items);
+        assert!(repace(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_strip_annotations() {
+        let items = vec![
+            item("[Music]", 0.0, 2.0),
+            item("hello [Applause] everyone", 2.0, 2.0),
+            item("(Laughter)", 4.0, 1.0),
+            item("that was (mostly) fine (laughs)", 5.0, 2.0),
+            item("[ __ ] [Music]", 7.0, 1.0),
+            item("what the [ __ ] was that", 8.0, 1.0),
+        ];
+
+        let stripped = strip_annotations(&items);
+        let texts: Vec<&str> = stripped.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "hello everyone",
+                "that was (mostly) fine",
+                "[ __ ]",
+                "what the [ __ ] was that"
+            ]
+        );
+        assert_eq!(stripped[1].start, 5.0);
+    }
+
+    #[test]
+    fn test_apply_replacements() {
+        let items = vec![
+            item("jon said hi to jon", 0.0, 2.0),
+            item("[Music]", 2.0, 1.0),
+            item("path a/b", 3.0, 1.0),
+        ];
+        let replacements = vec![
+            Replacement::parse("/jon/John/").unwrap(),
+            Replacement::parse("/\\[music\\]//gi").unwrap(),
+            Replacement::parse("|a/b|c\\|d|").unwrap(),
+            Replacement::parse("/John/Jon/g").unwrap(),
+        ];
+
+        let replaced = apply_replacements(&items, &replacements);
+        let texts: Vec<&str> = replaced.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["Jon said hi to jon", "path c|d"]);
+        assert_eq!(replaced[1].start, 3.0);
+    }
+
+    #[test]
+    fn test_replacement_parse_errors() {
+        assert!(Replacement::parse("jon/John/").is_err());
+        assert!(Replacement::parse("/jon/John").is_err());
+        assert!(Replacement::parse("/jon/John/g/").is_err());
+        assert!(Replacement::parse("/jon/John/q").is_err());
+        assert!(Replacement::parse("/(unclosed/x/").is_err());
+    }
+
+    #[test]
+    fn test_strip_timecodes() {
+        let items = vec![
+            item("0:15 welcome back", 0.0, 2.0),
+            item("(1:02:03)", 2.0, 1.0),
+            item("skip ahead to (12:30), then", 3.0, 2.0),
+            item("[4:05] the intro", 5.0, 2.0),
+            item("0:15 1:30 [2:45] chapters", 7.0, 2.0),
+            item("0:15, first line\nsecond  line [3:10]", 9.0, 2.0),
+        ];
+
+        let stripped = strip_timecodes(&items);
+        let texts: Vec<&str> = stripped.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "welcome back",
+                "skip ahead to, then",
+                "the intro",
+                "chapters",
+                "first line\nsecond  line"
+            ]
+        );
+        assert_eq!(stripped[1].start, 3.0);
+    }
+
+    #[test]
+    fn test_strip_timecodes_keeps_other_numbers() {
+        let items = vec![
+            item("mix it at a 3:1 ratio", 0.0, 2.0),
+            item("the game ended 2:0", 2.0, 2.0),
+            item("meet at 10:30 am sharp", 4.0, 2.0),
+            item("version 1.2:30 of the spec", 6.0, 2.0),
+            item("buy a 16:10 display", 8.0, 2.0),
+            item("16:10 ratio screens", 10.0, 2.0),
+            item("take the 21:15 train", 12.0, 2.0),
+            item("as John 3:16 says", 14.0, 2.0),
+            item("wait until 12:30, then go", 16.0, 2.0),
+            item("(it was 4:05 already)", 18.0, 2.0),
+            item("two  spaces\nand a line break", 20.0, 2.0),
+        ];
+
+        let stripped = strip_timecodes(&items);
+        let texts: Vec<&str> = stripped.iter().map(|i| i.text.as_str()).collect();
+        let unchanged: Vec<&str> = items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, unchanged);
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("  so   today\n we  "), "so today we");
+        assert_eq!(normalize_text("it works .Really ?"), "it works. Really?");
+        assert_eq!(
+            normalize_text("first,second.Third!Fourth"),
+            "first, second. Third! Fourth"
+        );
+        assert_eq!(
+            normalize_text("pi is 3.14, about 1,000 in the U.S. on example.com"),
+            "pi is 3.14, about 1,000 in the U.S. on example.com"
+        );
+    }
+
+    #[test]
+    fn test_drop_duplicate_cues() {
+        let items = vec![
+            item("hello there", 0.0, 2.0),
+            item(" hello there ", 2.0, 1.5),
+            item("Hello there", 3.5, 1.0),
+            item("hello there", 4.5, 1.0),
+        ];
+
+        let kept = drop_duplicate_cues(&items);
+        let texts: Vec<&str> = kept.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(texts, vec!["hello there", "Hello there", "hello there"]);
+        assert_eq!(kept[0].duration, 3.5);
+        assert_eq!(kept[1].duration, 1.0);
+    }
+
+    #[test]
+    fn test_dedupe_rolling_strips_overlap() {
+        let items = vec![
+            item("so today we are going", 0.0, 2.0),
+            item("we are going to talk about", 2.0, 2.0),
+            item("talk about Rust", 4.0, 2.0),
+        ];
+
+        let deduped = dedupe_rolling(&items);
+        let texts: Vec<&str> = deduped.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["so today we are going", "to talk about", "Rust"]
+        );
+        assert_eq!(deduped[1].start, 2.0);
+    }
+
+    #[test]
+    fn test_dedupe_rolling_is_case_insensitive() {
+        let items = vec![item("Hello World", 0.0, 1.0), item("world peace", 1.0, 1.0)];
+
+        let deduped = dedupe_rolling(&items);
+        assert_eq!(deduped[1].text, "peace");
+    }
+
+    #[test]
+    fn test_dedupe_rolling_merges_fully_repeated_cue() {
+        let items = vec![
+            item("hello there", 0.0, 2.0),
+            item("hello there", 2.0, 1.5),
+            item("friend", 3.5, 1.0),
+        ];
+
+        let deduped = dedupe_rolling(&items);
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[0].text, "hello there");
+        assert_eq!(deduped[0].duration, 3.5);
+        assert_eq!(deduped[1].text, "friend");
+    }
+
+    #[test]
+    fn test_dedupe_rolling_leaves_distinct_cues() {
+        let items = vec![item("one two", 0.0, 1.0), item("three four", 1.0, 1.0)];
+
+        let deduped = dedupe_rolling(&items);
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[1].text, "three four");
+    }
+
+    #[test]
+    fn test_split_by_chapters() {
+        let items = vec![
+            item("welcome", 0.5, 2.0),
+            item("setup", 61.0, 3.0),
+            item("more setup", 90.0, 3.0),
+            item("questions", 305.0, 2.0),
+        ];
+        let chapters = vec![
+            ("Intro".to_string(), 1.0),
+            ("Setup".to_string(), 60.0),
+            ("Unused".to_string(), 120.0),
+            ("Q&A".to_string(), 300.0),
+        ];
+
+        let sections = split_by_chapters(&items, &chapters);
+        let summary: Vec<(&str, Vec<&str>)> = sections
+            .iter()
+            .map(|(title, cues)| (*title, cues.iter().map(|c| c.text.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Intro", vec!["welcome"]),
+                ("Setup", vec!["setup", "more setup"]),
+                ("Q&A", vec!["questions"]),
+            ]
+        );
+        assert!(split_by_chapters(&items, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_recase() {
+        assert_eq!(
+            recase("HELLO WORLD. I THINK I'LL STAY!"),
+            "Hello world. I think I'll stay!"
+        );
+        assert_eq!(
+            recase("IT COSTS 3.14 DOLLARS IN THE U.S.A"),
+            "It costs 3.14 dollars in the u.s.a"
+        );
+        assert_eq!(
+            recase("IS IT IN ITALY? IF SO, I'D GO"),
+            "Is it in italy? If so, I'd go"
+        );
+        assert_eq!(recase(""), "");
+    }
+
+    #[test]
+    fn test_fix_case() {
+        let items = vec![
+            item("SO WHEN I STARTED", 0.0, 1.0),
+            item("THIS PROJECT. AND THEN", 1.0, 1.0),
+            item("Already Mixed Case", 2.0, 1.0),
+            item("WE SHIPPED IT", 3.0, 1.0),
+        ];
+        let texts: Vec<String> = fix_case(&items).into_iter().map(|i| i.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "So when I started",
+                "this project. And then",
+                "Already Mixed Case",
+                "we shipped it"
+            ]
+        );
+    }
+}